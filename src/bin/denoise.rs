@@ -0,0 +1,101 @@
+use std::{error::Error, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use raytracer::{
+    config::RaytracerConfig, primitives::Aov, rendering::FrameBuffer,
+    utilities::exr_writer::EXRCompression,
+};
+
+/// Denoises an OpenEXR render saved by the `render` binary. The render should include the
+/// normal and albedo layers, as written with `--aov normal --aov albedo`.
+#[derive(Debug, Parser)]
+struct Args {
+    /// OpenEXR file with the colour, depth and output variables of a render.
+    input: PathBuf,
+
+    /// Output image, `.png`, `.ppm`, `.pfm`, `.hdr` or `.exr`.
+    /// Defaults to `<input>.denoised.exr`.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Config providing the `denoiser` and `tone_mapping` settings.
+    #[arg(long, default_value = "Config.toml")]
+    config: PathBuf,
+
+    /// Number of filter passes.
+    #[arg(long)]
+    iterations: Option<u32>,
+
+    /// How much neighbouring colours may differ.
+    #[arg(long)]
+    colour_sigma: Option<f32>,
+
+    /// How much neighbouring normals may differ.
+    #[arg(long)]
+    normal_sigma: Option<f32>,
+
+    /// How much neighbouring depths may differ, relative to the depth.
+    #[arg(long)]
+    depth_sigma: Option<f32>,
+
+    /// How much neighbouring albedos may differ.
+    #[arg(long)]
+    albedo_sigma: Option<f32>,
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut config = if args.config.exists() {
+        RaytracerConfig::from_toml_file(&args.config.to_string_lossy())?
+    } else {
+        RaytracerConfig::default()
+    };
+    let denoiser = &mut config.denoiser;
+    denoiser.iterations = args.iterations.unwrap_or(denoiser.iterations);
+    denoiser.colour_sigma = args.colour_sigma.unwrap_or(denoiser.colour_sigma);
+    denoiser.normal_sigma = args.normal_sigma.unwrap_or(denoiser.normal_sigma);
+    denoiser.depth_sigma = args.depth_sigma.unwrap_or(denoiser.depth_sigma);
+    denoiser.albedo_sigma = args.albedo_sigma.unwrap_or(denoiser.albedo_sigma);
+
+    let (mut fb, aovs) = FrameBuffer::read_exr(&args.input.to_string_lossy(), &config)?;
+    for aov in [Aov::Normal, Aov::Albedo] {
+        if !aovs.contains(&aov) {
+            eprintln!(
+                "Warning: no {} layer in {}, edges are only preserved by colour and depth.",
+                aov.name(),
+                args.input.display()
+            );
+        }
+    }
+
+    println!("Denoising {}x{} image...", fb.width, fb.height);
+    fb.denoise(&config.denoiser);
+
+    let output = args
+        .output
+        .unwrap_or_else(|| args.input.with_extension("denoised.exr"));
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let is_exr = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+    if is_exr {
+        fb.write_exr(&output.to_string_lossy(), &aovs, EXRCompression::ZIP)?;
+    } else {
+        fb.write_rgb_file(&output.to_string_lossy())?;
+    }
+    println!("Denoised: {}", output.display());
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, ValueEnum};
+use raytracer::{
+    config::RaytracerConfig,
+    primitives::Aov,
+    rendering::{Camera, FrameBuffer, Raytracer},
+    scene::{loader::CameraDescription, PhotonScene, Scene, SceneDescription},
+    utilities::exr_writer::EXRCompression,
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Integrator {
+    /// Direct lighting with reflections and refractions.
+    Basic,
+    /// Photon mapping for global illumination and caustics.
+    Photon,
+}
+
+/// Renders a scene file.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Scene description file. Mesh paths in it are relative to the file.
+    scene: PathBuf,
+
+    #[arg(short, long, value_enum, default_value = "basic")]
+    integrator: Integrator,
+
+    /// Output image, `.png`, `.ppm`, `.pfm`, `.hdr` or `.exr`.
+    /// Defaults to `./output/<scene name>.png`.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Image width in pixels.
+    #[arg(long)]
+    width: Option<u16>,
+
+    /// Image height in pixels.
+    #[arg(long)]
+    height: Option<u16>,
+
+    /// Rays per pixel for the sampling and thin lens cameras. Must be a square number.
+    #[arg(short, long)]
+    samples: Option<u32>,
+
+    /// Base config, which the scene's `[config]` overrides are applied to.
+    #[arg(long, default_value = "Config.toml")]
+    config: PathBuf,
+
+    /// Config override such as `photon_mapping.num_photons=10000`. Can be repeated, and takes
+    /// precedence over the scene file.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+
+    /// Output variable to write alongside the image: normal, albedo, object_id, material_id,
+    /// position, direct, indirect, photon or sample_count. Can be repeated. They are added as
+    /// layers when the output is `.exr`, and otherwise written next to it as
+    /// `<output>.<name>.<extension>`.
+    #[arg(long = "aov", value_name = "NAME", value_parser = parse_aov)]
+    aovs: Vec<Aov>,
+
+    /// Denoise the image before writing it, as with `denoiser.enabled=true`.
+    #[arg(long)]
+    denoise: bool,
+
+    /// Render the frames of the scene's `[animation]` as a numbered sequence, written to
+    /// `<output>.0000.<extension>` onwards.
+    #[arg(long)]
+    animate: bool,
+}
+
+fn parse_aov(name: &str) -> Result<Aov, String> {
+    Aov::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Aov::ALL.iter().map(|aov| aov.name()).collect();
+        format!("expected one of {}", names.join(", "))
+    })
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut description = SceneDescription::from_file(&args.scene)?;
+
+    for assignment in &args.overrides {
+        description.set_config(assignment)?;
+    }
+    if let Some(width) = args.width {
+        description.set_config(&format!("framebuffer.width = {width}"))?;
+    }
+    if let Some(height) = args.height {
+        description.set_config(&format!("framebuffer.height = {height}"))?;
+    }
+    if let Some(samples) = args.samples {
+        description.set_config(&format!("camera.num_camera_ray_samples = {samples}"))?;
+        if let Some(camera_samples) = description
+            .camera
+            .as_mut()
+            .and_then(CameraDescription::samples_mut)
+        {
+            *camera_samples = Some(samples);
+        }
+    }
+
+    let base_config = if args.config.exists() {
+        RaytracerConfig::from_toml_file(&args.config.to_string_lossy())?
+    } else {
+        RaytracerConfig::default()
+    };
+    let config = description.config(&base_config)?;
+
+    // Each frame of an animation has its own shutter interval, which places the camera, lights
+    // and moving objects.
+    let frames: Vec<RaytracerConfig> = match (&description.animation, args.animate) {
+        (Some(timeline), true) => (0..timeline.frames)
+            .map(|frame| RaytracerConfig {
+                motion_blur: timeline.motion_blur(frame),
+                ..config
+            })
+            .collect(),
+        (None, true) => return Err("the scene has no [animation] section to render.".into()),
+        (_, false) => vec![config],
+    };
+
+    let output = args.output.clone().unwrap_or_else(|| {
+        let name = args.scene.file_stem().unwrap_or("render".as_ref());
+        PathBuf::from("./output").join(name).with_extension("png")
+    });
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let frame_output = |frame: usize| {
+        if args.animate {
+            println!("\nFrame {}/{}", frame + 1, frames.len());
+            output.with_extension(format!(
+                "{frame:04}.{}",
+                output.extension().unwrap_or_default().to_string_lossy()
+            ))
+        } else {
+            output.clone()
+        }
+    };
+
+    match args.integrator {
+        Integrator::Basic => {
+            let mut scene = Scene::new(&frames[0]);
+            description.populate(&mut scene)?;
+
+            println!(
+                "Rendering {} objects and {} lights...",
+                scene.objects.len(),
+                scene.lights.len()
+            );
+            for (frame, config) in frames.iter().enumerate() {
+                // The lights are already in place for the first frame.
+                if frame > 0 {
+                    scene.set_config(config);
+                    description.update_lights(&mut scene)?;
+                }
+                render_frame(&description, &scene, config, &args, &frame_output(frame))?;
+            }
+        }
+        Integrator::Photon => {
+            let mut scene = PhotonScene::new(&frames[0]);
+            description.populate(&mut scene)?;
+
+            for (frame, config) in frames.iter().enumerate() {
+                let output = frame_output(frame);
+                if frame > 0 {
+                    scene.set_config(config);
+                    description.update_lights(&mut scene)?;
+                }
+
+                // Photon maps are reused between frames unless something affecting them changed.
+                if scene.photon_maps_outdated() {
+                    println!(
+                        "Building photon maps for {} objects and {} lights...",
+                        scene.objects.len(),
+                        scene.lights.len()
+                    );
+                    scene.setup();
+                }
+                render_frame(&description, &scene, config, &args, &output)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders the scene as it is at the time of the config, and writes the image and AOVs.
+fn render_frame<S: Raytracer + Sync>(
+    description: &SceneDescription,
+    scene: &S,
+    config: &RaytracerConfig,
+    args: &Args,
+    output: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut fb = FrameBuffer::new(config)?;
+    let mut camera = description.camera(config)?;
+    camera.render(scene, &mut fb);
+
+    if args.denoise || config.denoiser.enabled {
+        println!("Denoising...");
+        fb.denoise(&config.denoiser);
+    }
+
+    let is_exr = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+
+    if is_exr {
+        fb.write_exr(&output.to_string_lossy(), &args.aovs, EXRCompression::ZIP)?;
+    } else {
+        fb.write_rgb_file(&output.to_string_lossy())?;
+
+        for aov in &args.aovs {
+            let mut aov_output = output.to_path_buf();
+            aov_output.set_extension(format!(
+                "{}.{}",
+                aov.name(),
+                output.extension().unwrap_or_default().to_string_lossy()
+            ));
+            fb.write_aov_file(*aov, &aov_output.to_string_lossy())?;
+            println!("Rendered: {}", aov_output.display());
+        }
+    }
+    println!("\nRendered: {}", output.display());
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -11,6 +11,18 @@ pub struct RaytracerConfig {
     pub sampler: SamplerConfig,
     pub framebuffer: FramebufferConfig,
     pub cornell_box: CornellBoxConfig,
+    #[serde(default)]
+    pub acceleration: AccelerationConfig,
+    #[serde(default)]
+    pub tone_mapping: ToneMappingConfig,
+    #[serde(default)]
+    pub denoiser: DenoiserConfig,
+    #[serde(default)]
+    pub adaptive_sampling: AdaptiveSamplingConfig,
+    #[serde(default)]
+    pub pixel_filter: PixelFilterConfig,
+    #[serde(default)]
+    pub motion_blur: MotionBlurConfig,
 }
 
 impl fmt::Display for RaytracerConfig {
@@ -28,7 +40,7 @@ pub struct CameraConfig {
     pub num_camera_ray_samples: u32,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct PhotonMappingConfig {
     /// Recursion threshold for approximate radiance estimation
     pub recurse_approximate_threshold: u8,
@@ -85,6 +97,121 @@ pub struct CornellBoxConfig {
     pub height: f32,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AccelerationConfig {
+    /// Use a bounding volume hierarchy to find ray-object hits instead of testing every object
+    pub use_bvh: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ToneMappingConfig {
+    /// Exposure adjustment in stops, applied before tone mapping
+    pub exposure: f32,
+
+    /// Operator that maps radiance onto [0, 1]
+    pub operator: ToneMapOperator,
+
+    /// Radiance mapped to white by the extended Reinhard operator
+    pub white_point: f32,
+
+    /// Encoding of the tone mapped values written to 8 or 16-bit images
+    pub encoding: ColourEncoding,
+
+    /// Exponent used by the gamma encoding
+    pub gamma: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AdaptiveSamplingConfig {
+    /// Keep sampling noisy pixels of the sampling camera in batches of its number of samples
+    pub enabled: bool,
+
+    /// Maximum number of samples for a pixel
+    pub max_samples: u32,
+
+    /// Largest accepted 95% confidence interval of a pixel's brightness, relative to the
+    /// brightness
+    pub error_threshold: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PixelFilterConfig {
+    /// Filter that weights the sampling camera's samples into the pixels around them
+    pub filter: PixelFilterKind,
+
+    /// Half-width of the filter in pixels. Defaults to 0.5 for box, 1 for tent, 1.5 for
+    /// Gaussian, 2 for Mitchell-Netravali and 3 for Lanczos
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radius: Option<f32>,
+
+    /// Falloff of the Gaussian filter
+    pub gaussian_alpha: f32,
+
+    /// B parameter of the Mitchell-Netravali filter
+    pub mitchell_b: f32,
+
+    /// C parameter of the Mitchell-Netravali filter
+    pub mitchell_c: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MotionBlurConfig {
+    /// Time at which the shutter opens. The sampling cameras cast rays at times spread over
+    /// the shutter interval, so objects moving during it are blurred
+    pub shutter_open: f32,
+
+    /// Time at which the shutter closes. Equal to the opening time for no motion blur
+    pub shutter_close: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelFilterKind {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DenoiserConfig {
+    /// Denoise renders before they are written
+    pub enabled: bool,
+
+    /// Number of à-trous passes, each doubling the spacing of the filter taps
+    pub iterations: u32,
+
+    /// How much neighbouring colours may differ, halved after every pass
+    pub colour_sigma: f32,
+
+    /// How much neighbouring normals may differ
+    pub normal_sigma: f32,
+
+    /// How much neighbouring depths may differ, relative to the depth of the pixel
+    pub depth_sigma: f32,
+
+    /// How much neighbouring albedos may differ
+    pub albedo_sigma: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneMapOperator {
+    Clamp,
+    Reinhard,
+    ExtendedReinhard,
+    AcesFilmic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColourEncoding {
+    Linear,
+    Srgb,
+    Gamma,
+}
+
 impl Default for CameraConfig {
     fn default() -> Self {
         Self {
@@ -151,6 +278,68 @@ impl Default for CornellBoxConfig {
     }
 }
 
+impl Default for AccelerationConfig {
+    fn default() -> Self {
+        Self { use_bvh: true }
+    }
+}
+
+impl Default for ToneMappingConfig {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            operator: ToneMapOperator::Clamp,
+            white_point: 4.0,
+            encoding: ColourEncoding::Srgb,
+            gamma: 2.2,
+        }
+    }
+}
+
+impl Default for AdaptiveSamplingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_samples: 256,
+            error_threshold: 0.05,
+        }
+    }
+}
+
+impl Default for MotionBlurConfig {
+    fn default() -> Self {
+        Self {
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+        }
+    }
+}
+
+impl Default for PixelFilterConfig {
+    fn default() -> Self {
+        Self {
+            filter: PixelFilterKind::Box,
+            radius: None,
+            gaussian_alpha: 2.0,
+            mitchell_b: 1.0 / 3.0,
+            mitchell_c: 1.0 / 3.0,
+        }
+    }
+}
+
+impl Default for DenoiserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            iterations: 5,
+            colour_sigma: 0.5,
+            normal_sigma: 0.3,
+            depth_sigma: 0.1,
+            albedo_sigma: 0.1,
+        }
+    }
+}
+
 impl RaytracerConfig {
     pub fn new() -> Self {
         Self::from_toml_file("Config.toml").unwrap_or_else(|_| Self::default())
@@ -0,0 +1,318 @@
+use crate::primitives::{ray::Ray, AABB};
+
+/// Maximum number of primitives stored in a leaf node.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Size of the fixed traversal stack.
+/// Median splits keep the tree balanced, so its depth never exceeds log2 of the primitive count.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, Debug)]
+struct BVHNode {
+    bounds: AABB,
+    /// Leaf: index of the first primitive in `indices`.
+    /// Interior: index of the second child (the first child always directly follows its parent).
+    offset: usize,
+    /// Number of primitives in a leaf, zero for interior nodes.
+    count: usize,
+}
+
+/// Bounding volume hierarchy over a list of primitives.
+///
+/// The hierarchy only stores primitive indices and their bounds, so it can index anything that
+/// can be bounded by a box (scene objects, triangles, ...). Primitives without bounds, such as
+/// infinite planes, are kept aside and tested on every query.
+#[derive(Debug, Default)]
+pub struct BVH {
+    nodes: Vec<BVHNode>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+impl BVH {
+    /// Builds the hierarchy from the bounds of each primitive, indexed by position.
+    ///
+    /// Primitives with an empty box can never be hit and are left out entirely.
+    pub fn new(bounds: &[Option<AABB>]) -> Self {
+        let mut bvh = Self::default();
+
+        for (index, primitive_bounds) in bounds.iter().enumerate() {
+            match primitive_bounds {
+                Some(primitive_bounds) if primitive_bounds.is_empty() => {}
+                Some(_) => bvh.indices.push(index),
+                None => bvh.unbounded.push(index),
+            }
+        }
+
+        if !bvh.indices.is_empty() {
+            bvh.build_node(bounds, 0, bvh.indices.len());
+        }
+
+        bvh
+    }
+
+    /// Bounds of all the bounded primitives.
+    pub fn bounds(&self) -> Option<AABB> {
+        self.nodes.first().map(|root| root.bounds)
+    }
+
+    /// Recursively builds the subtree over `indices[start..end]` and returns its node index.
+    fn build_node(&mut self, bounds: &[Option<AABB>], start: usize, end: usize) -> usize {
+        let primitive_bounds = |index: usize| bounds[index].unwrap_or_default();
+
+        let node_bounds = self.indices[start..end]
+            .iter()
+            .fold(AABB::empty(), |acc, &index| {
+                acc.union(&primitive_bounds(index))
+            });
+
+        let node_index = self.nodes.len();
+        self.nodes.push(BVHNode {
+            bounds: node_bounds,
+            offset: start,
+            count: end - start,
+        });
+
+        if end - start <= MAX_LEAF_SIZE {
+            return node_index;
+        }
+
+        // Split at the median centroid along the axis in which the centroids are most spread out.
+        let centroid_bounds = self.indices[start..end]
+            .iter()
+            .fold(AABB::empty(), |acc, &index| {
+                acc.grow(primitive_bounds(index).centroid())
+            });
+        let axis = centroid_bounds.longest_axis();
+        let middle = (start + end) / 2;
+
+        self.indices[start..end].select_nth_unstable_by(middle - start, |&a, &b| {
+            primitive_bounds(a).centroid()[axis].total_cmp(&primitive_bounds(b).centroid()[axis])
+        });
+
+        self.build_node(bounds, start, middle);
+        let right = self.build_node(bounds, middle, end);
+
+        self.nodes[node_index].offset = right;
+        self.nodes[node_index].count = 0;
+
+        node_index
+    }
+
+    /// Finds the closest hit along the ray.
+    ///
+    /// `intersect` is called with a primitive index and the current search distance, and returns
+    /// the distance of a hit if it found one closer than that. The search distance then shrinks
+    /// so that boxes further away than the closest hit so far are skipped.
+    pub fn closest_hit<F>(&self, ray: &Ray, max_distance: f32, mut intersect: F)
+    where
+        F: FnMut(usize, f32) -> Option<f32>,
+    {
+        self.traverse(ray, 0.0, max_distance, |index, max_distance| {
+            if let Some(distance) = intersect(index, *max_distance) {
+                *max_distance = distance;
+            }
+            false
+        });
+    }
+
+    /// Calls `visit` for every primitive whose box the ray passes through between
+    /// `min_distance` and `max_distance`.
+    pub fn for_each_candidate<F>(
+        &self,
+        ray: &Ray,
+        min_distance: f32,
+        max_distance: f32,
+        mut visit: F,
+    ) where
+        F: FnMut(usize),
+    {
+        self.traverse(ray, min_distance, max_distance, |index, _| {
+            visit(index);
+            false
+        });
+    }
+
+    /// Returns `true` as soon as `occludes` reports a hit for any primitive whose box the ray
+    /// enters before `max_distance`.
+    pub fn any_hit<F>(&self, ray: &Ray, max_distance: f32, mut occludes: F) -> bool
+    where
+        F: FnMut(usize) -> bool,
+    {
+        self.traverse(ray, 0.0, max_distance, |index, _| occludes(index))
+    }
+
+    /// Visits the candidate primitives for the ray, nearest boxes first.
+    /// Stops and returns `true` when `visit` returns `true`.
+    fn traverse<F>(&self, ray: &Ray, min_distance: f32, mut max_distance: f32, mut visit: F) -> bool
+    where
+        F: FnMut(usize, &mut f32) -> bool,
+    {
+        for &index in &self.unbounded {
+            if visit(index, &mut max_distance) {
+                return true;
+            }
+        }
+
+        let Some(root) = self.nodes.first() else {
+            return false;
+        };
+        let Some(root_distance) = root.bounds.intersect(ray, min_distance, max_distance) else {
+            return false;
+        };
+
+        // Stack of nodes still to visit, with the distance at which the ray enters them.
+        let mut stack = [(0, 0.0); MAX_DEPTH];
+        stack[0] = (0, root_distance);
+        let mut stack_size = 1;
+
+        while stack_size > 0 {
+            stack_size -= 1;
+            let (node_index, entry_distance) = stack[stack_size];
+
+            // The search distance may have shrunk since this node was pushed.
+            if entry_distance > max_distance {
+                continue;
+            }
+
+            let node = &self.nodes[node_index];
+            if node.count > 0 {
+                for &index in &self.indices[node.offset..node.offset + node.count] {
+                    if visit(index, &mut max_distance) {
+                        return true;
+                    }
+                }
+                continue;
+            }
+
+            let left = node_index + 1;
+            let right = node.offset;
+            let left_distance = self.nodes[left]
+                .bounds
+                .intersect(ray, min_distance, max_distance);
+            let right_distance =
+                self.nodes[right]
+                    .bounds
+                    .intersect(ray, min_distance, max_distance);
+
+            // Push the further child first so that the nearer one is visited next.
+            match (left_distance, right_distance) {
+                (Some(left_distance), Some(right_distance)) => {
+                    let (near, far) = if left_distance <= right_distance {
+                        ((left, left_distance), (right, right_distance))
+                    } else {
+                        ((right, right_distance), (left, left_distance))
+                    };
+                    stack[stack_size] = far;
+                    stack[stack_size + 1] = near;
+                    stack_size += 2;
+                }
+                (Some(left_distance), None) => {
+                    stack[stack_size] = (left, left_distance);
+                    stack_size += 1;
+                }
+                (None, Some(right_distance)) => {
+                    stack[stack_size] = (right, right_distance);
+                    stack_size += 1;
+                }
+                (None, None) => {}
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{Vector, Vertex};
+
+    /// Unit boxes spaced out along the z axis.
+    fn boxes_along_z(count: usize) -> Vec<Option<AABB>> {
+        (0..count)
+            .map(|i| {
+                let z = 3.0 * i as f32;
+                Some(AABB::new(
+                    Vector::new(-1.0, -1.0, z),
+                    Vector::new(1.0, 1.0, z + 1.0),
+                ))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bvh_visits_every_box_on_the_ray() {
+        let bvh = BVH::new(&boxes_along_z(20));
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        let mut visited = Vec::new();
+        bvh.closest_hit(&ray, f32::INFINITY, |index, _| {
+            visited.push(index);
+            None
+        });
+
+        visited.sort();
+        assert_eq!(visited, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_bvh_closest_hit_prunes_further_boxes() {
+        let bvh = BVH::new(&boxes_along_z(20));
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        let mut visited = 0;
+        let mut closest = None;
+        bvh.closest_hit(&ray, f32::INFINITY, |index, max_distance| {
+            visited += 1;
+            // Pretend each box is hit on its front face.
+            let distance = 5.0 + 3.0 * index as f32;
+            if distance < max_distance {
+                closest = Some(index);
+                Some(distance)
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(closest, Some(0));
+        assert!(visited < 20);
+    }
+
+    #[test]
+    fn test_bvh_misses_offset_ray() {
+        let bvh = BVH::new(&boxes_along_z(20));
+        let ray = Ray::new(Vertex::new(5.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(!bvh.any_hit(&ray, f32::INFINITY, |_| true));
+    }
+
+    #[test]
+    fn test_bvh_any_hit_respects_max_distance() {
+        let bvh = BVH::new(&boxes_along_z(20));
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(bvh.any_hit(&ray, 10.0, |_| true));
+        assert!(!bvh.any_hit(&ray, 4.0, |_| true));
+    }
+
+    #[test]
+    fn test_bvh_unbounded_primitives_are_always_visited() {
+        let mut bounds = boxes_along_z(3);
+        bounds.push(None);
+        let bvh = BVH::new(&bounds);
+
+        let ray = Ray::new(Vertex::new(5.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(bvh.any_hit(&ray, f32::INFINITY, |index| index == 3));
+    }
+
+    #[test]
+    fn test_bvh_bounds() {
+        let bvh = BVH::new(&boxes_along_z(4));
+        let bounds = bvh.bounds().unwrap();
+
+        assert_eq!(bounds.min, Vector::new(-1.0, -1.0, 0.0));
+        assert_eq!(bounds.max, Vector::new(1.0, 1.0, 10.0));
+        assert!(BVH::new(&[None]).bounds().is_none());
+    }
+}
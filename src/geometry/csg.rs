@@ -1,6 +1,9 @@
 use crate::{
-    geometry::traits::{HitPool, Intersection, Transformable},
-    primitives::{ray::Ray, Hit, Transform},
+    geometry::{
+        traits::{Bounded, Intersection, Transformable},
+        HitList,
+    },
+    primitives::{ray::Ray, Hit, Transform, AABB},
     shading::Material,
 };
 
@@ -86,50 +89,55 @@ impl CSG {
     }
 }
 
+impl CSG {
+    /// Whether the ray can hit the combined object before `max_distance`, judging by its bounds.
+    fn may_hit(&self, ray: &Ray, max_distance: f32) -> bool {
+        self.bounding_box()
+            .is_none_or(|bounds| bounds.intersect(ray, 0.0, max_distance).is_some())
+    }
+}
+
 impl Intersection for CSG {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
-        let mut result: Vec<Hit> = Vec::new();
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        let left_hits = self.left_object.generate_hits(ray);
+        let right_hits = self.right_object.generate_hits(ray);
 
-        let mut left_hitpool = self.left_object.generate_hitpool(ray);
-        let mut right_hitpool = self.right_object.generate_hitpool(ray);
+        // The children's hits are merged in distance order, so each kept hit goes on the end.
+        let mut keep = |hit: &Hit, entering: bool| hits.insert(Hit { entering, ..*hit });
 
         let mut left_index = 0;
         let mut right_index = 0;
-        while left_index < left_hitpool.len() && right_index < right_hitpool.len() {
+        while left_index < left_hits.len() && right_index < right_hits.len() {
             let mut state = 0;
 
-            if left_hitpool[left_index].entering {
+            if left_hits[left_index].entering {
                 state += 4;
             }
-            if right_hitpool[right_index].entering {
+            if right_hits[right_index].entering {
                 state += 2;
             }
-            if left_hitpool[left_index].distance > right_hitpool[right_index].distance {
+            if left_hits[left_index].distance > right_hits[right_index].distance {
                 state += 1;
             }
 
             match ACTIONS[self.mode as usize][state] {
                 Action::CsgAEnter => {
-                    result.push(left_hitpool[left_index]);
-                    result.last_mut().unwrap().entering = true;
+                    keep(&left_hits[left_index], true);
                     left_index += 1;
                 }
                 Action::CsgAExit => {
-                    result.push(left_hitpool[left_index]);
-                    result.last_mut().unwrap().entering = false;
+                    keep(&left_hits[left_index], false);
                     left_index += 1;
                 }
                 Action::CsgADrop => {
                     left_index += 1;
                 }
                 Action::CsgBEnter => {
-                    result.push(right_hitpool[right_index]);
-                    result.last_mut().unwrap().entering = true;
+                    keep(&right_hits[right_index], true);
                     right_index += 1;
                 }
                 Action::CsgBExit => {
-                    result.push(right_hitpool[right_index]);
-                    result.last_mut().unwrap().entering = false;
+                    keep(&right_hits[right_index], false);
                     right_index += 1;
                 }
                 Action::CsgBDrop => {
@@ -138,28 +146,48 @@ impl Intersection for CSG {
             }
         }
 
-        match self.mode {
-            Mode::CsgDiff => {
-                if left_index < left_hitpool.len() {
-                    result.extend(left_hitpool.flatten().iter().skip(left_index).cloned());
-                }
-            }
+        let remaining = match self.mode {
+            Mode::CsgDiff => &left_hits[left_index..],
             Mode::CsgUnion => {
-                if left_index >= left_hitpool.len() {
-                    result.extend(right_hitpool.flatten().iter().skip(right_index).cloned());
+                if left_index >= left_hits.len() {
+                    &right_hits[right_index..]
                 } else {
-                    result.extend(left_hitpool.flatten().iter().skip(left_index).cloned());
+                    &left_hits[left_index..]
                 }
             }
-            Mode::CsgInter => {}
+            Mode::CsgInter => &[],
+        };
+
+        for hit in remaining {
+            hits.insert(*hit);
         }
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        if !self.may_hit(ray, t_max) {
+            return None;
+        }
+
+        self.generate_hits(ray)
+            .iter()
+            .find(|hit| t_min < hit.distance && hit.distance < t_max)
+            .copied()
+    }
 
-        left_hitpool.clear();
-        right_hitpool.clear();
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        if !self.may_hit(ray, t_max) {
+            return false;
+        }
 
-        for hit in result {
-            hitpool.insert(hit);
+        // Every hit of the combined object is a hit of one of its children, so there is nothing
+        // to merge unless a child is hit within the range.
+        if !self.left_object.any_hit(ray, t_min, t_max)
+            && !self.right_object.any_hit(ray, t_min, t_max)
+        {
+            return false;
         }
+
+        self.closest_hit(ray, t_min, t_max).is_some()
     }
 }
 
@@ -170,6 +198,25 @@ impl Transformable for CSG {
     }
 }
 
+impl Bounded for CSG {
+    fn bounding_box(&self) -> Option<AABB> {
+        let left = self.left_object.bounding_box();
+        let right = self.right_object.bounding_box();
+
+        match self.mode {
+            // Unbounded if either side is unbounded.
+            Mode::CsgUnion => left.zip(right).map(|(left, right)| left.union(&right)),
+            // Never larger than whichever sides are bounded.
+            Mode::CsgInter => match (left, right) {
+                (Some(left), Some(right)) => Some(left.intersection(&right)),
+                (bounds, None) | (None, bounds) => bounds,
+            },
+            // Subtracting can only remove from the left object.
+            Mode::CsgDiff => left,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -228,4 +275,162 @@ mod tests {
             _ => panic!("Expected CsgDiff mode"),
         }
     }
+
+    #[test]
+    fn test_csg_bounding_box() {
+        use crate::geometry::plane::Plane;
+        use crate::primitives::{Colour, Vector};
+
+        let material =
+            Material::phong(Colour::default(), Colour::default(), Colour::default(), 1.0);
+        let spheres = || {
+            (
+                Sphere::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0, material),
+                Sphere::new(Vertex::new(1.0, 0.0, 0.0, 1.0), 1.0, material),
+            )
+        };
+
+        let (left, right) = spheres();
+        let union = CSG::new(Mode::CsgUnion, left.into(), right.into(), material);
+        let bounds = union.bounding_box().unwrap();
+        assert_eq!(bounds.min, Vector::new(-1.0, -1.0, -1.0));
+        assert_eq!(bounds.max, Vector::new(2.0, 1.0, 1.0));
+
+        let (left, right) = spheres();
+        let inter = CSG::new(Mode::CsgInter, left.into(), right.into(), material);
+        let bounds = inter.bounding_box().unwrap();
+        assert_eq!(bounds.min, Vector::new(0.0, -1.0, -1.0));
+        assert_eq!(bounds.max, Vector::new(1.0, 1.0, 1.0));
+
+        let (left, right) = spheres();
+        let diff = CSG::new(Mode::CsgDiff, left.into(), right.into(), material);
+        let bounds = diff.bounding_box().unwrap();
+        assert_eq!(bounds.min, Vector::new(-1.0, -1.0, -1.0));
+        assert_eq!(bounds.max, Vector::new(1.0, 1.0, 1.0));
+
+        // A sphere clipped by a half-space stays bounded by the sphere.
+        let (sphere, _) = spheres();
+        let plane = Plane::new(0.0, 1.0, 0.0, 0.0, material);
+        let clipped = CSG::new(Mode::CsgInter, sphere.into(), plane.into(), material);
+        assert!(clipped.bounding_box().is_some());
+        assert!(clipped.bounding_sphere().is_some());
+
+        let (sphere, _) = spheres();
+        let plane = Plane::new(0.0, 1.0, 0.0, 0.0, material);
+        let merged = CSG::new(Mode::CsgUnion, sphere.into(), plane.into(), material);
+        assert!(merged.bounding_box().is_none());
+    }
+
+    #[test]
+    fn test_csg_any_hit() {
+        use crate::primitives::{Colour, Vector};
+
+        let material =
+            Material::phong(Colour::default(), Colour::default(), Colour::default(), 1.0);
+        let sphere1 = Sphere::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0, material);
+        let sphere2 = Sphere::new(Vertex::new(0.0, 0.0, 1.0, 1.0), 1.0, material);
+
+        // The lens shaped overlap spans z in [0, 1].
+        let csg = CSG::new(Mode::CsgInter, sphere1.into(), sphere2.into(), material);
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(csg.any_hit(&ray, 0.0, f32::INFINITY));
+        assert!(csg.any_hit(&ray, 0.0, 5.5));
+        // The child spheres are hit before the overlap starts.
+        assert!(!csg.any_hit(&ray, 0.0, 4.5));
+
+        let offset = Ray::new(Vertex::new(5.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(!csg.any_hit(&offset, 0.0, f32::INFINITY));
+    }
+
+    #[test]
+    fn test_csg_spans() {
+        use crate::primitives::{Colour, Vector};
+
+        let material =
+            Material::phong(Colour::default(), Colour::default(), Colour::default(), 1.0);
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        // Spheres spanning z in [-1, 1] and [0, 2] along the ray.
+        let expected = [
+            (Mode::CsgUnion, vec![(4.0, true), (7.0, false)]),
+            (Mode::CsgInter, vec![(5.0, true), (6.0, false)]),
+            (Mode::CsgDiff, vec![(4.0, true), (5.0, false)]),
+        ];
+
+        for (mode, spans) in expected {
+            let sphere1 = Sphere::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0, material);
+            let sphere2 = Sphere::new(Vertex::new(0.0, 0.0, 1.0, 1.0), 1.0, material);
+            let csg = CSG::new(mode, sphere1.into(), sphere2.into(), material);
+
+            let hits: Vec<(f32, bool)> = csg
+                .generate_hits(&ray)
+                .iter()
+                .map(|hit| (hit.distance, hit.entering))
+                .collect();
+            assert_eq!(hits, spans);
+
+            let closest = csg.closest_hit(&ray, 0.0, f32::INFINITY).unwrap();
+            assert_eq!(closest.distance, spans[0].0);
+            let beyond_first = csg.closest_hit(&ray, spans[0].0, f32::INFINITY).unwrap();
+            assert_eq!(beyond_first.distance, spans[1].0);
+        }
+    }
+
+    #[test]
+    fn test_csg_keeps_every_span_of_a_dense_mesh() {
+        use crate::geometry::polymesh::{PolyMesh, PolyMeshGeometry};
+        use crate::primitives::{Colour, Vector};
+        use crate::utilities::obj_reader::Triangle;
+
+        let material =
+            Material::phong(Colour::default(), Colour::default(), Colour::default(), 1.0);
+
+        // A stack of 20 thin slabs, each a front and a back face, so a ray along the z axis
+        // enters and exits 40 times.
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for k in 0..40 {
+            let z = k as f32 * 0.5;
+            let first = vertices.len();
+            vertices.push(Vertex::new(-1.0, -1.0, z, 1.0));
+            vertices.push(Vertex::new(3.0, -1.0, z, 1.0));
+            vertices.push(Vertex::new(-1.0, 3.0, z, 1.0));
+            // Back faces are wound the other way round so the ray exits through them.
+            let (a, b) = if k % 2 == 0 {
+                (first + 1, first + 2)
+            } else {
+                (first + 2, first + 1)
+            };
+            triangles.push(Triangle {
+                vertex_indices: [first, a, b],
+                vertex_normal_indices: [0, 0, 0],
+                face_normal: Vector::new(0.0, 0.0, if k % 2 == 0 { -1.0 } else { 1.0 }),
+            });
+        }
+        let normals = vec![Vertex::new(0.0, 0.0, 1.0, 1.0)];
+        let slabs = PolyMesh {
+            geometry: PolyMeshGeometry::new(vertices, normals, triangles, false),
+            material,
+        };
+        let ray = Ray::new(
+            Vertex::new(0.0, 0.0, -10.0, 1.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        assert_eq!(slabs.generate_hits(&ray).len(), 40);
+
+        // Clipping the slabs by a sphere around all of them leaves every slab in place.
+        let sphere = Sphere::new(Vertex::new(0.0, 0.0, 10.0, 1.0), 15.0, material);
+        let csg = CSG::new(Mode::CsgInter, slabs.into(), sphere.into(), material);
+
+        let hits: Vec<(f32, bool)> = csg
+            .generate_hits(&ray)
+            .iter()
+            .map(|hit| (hit.distance, hit.entering))
+            .collect();
+        let expected: Vec<(f32, bool)> = (0..40)
+            .map(|k| (10.0 + k as f32 * 0.5, k % 2 == 0))
+            .collect();
+        assert_eq!(hits, expected);
+    }
 }
@@ -0,0 +1,128 @@
+use std::ops::Deref;
+
+use crate::primitives::Hit;
+
+/// Number of hits a `HitList` stores inline before moving them to the heap.
+pub const INLINE_HITS: usize = 32;
+
+/// List of hits sorted by distance, stored inline so that building one usually never allocates.
+///
+/// Closed objects produce their hits in entering/exiting pairs, so the list reads as a sequence
+/// of spans along the ray over which the ray is inside the object. This is what CSG combines.
+/// Dropping any hit would break those spans, so past `INLINE_HITS` every hit moves to the heap.
+#[derive(Clone, Debug)]
+pub struct HitList {
+    hits: [Hit; INLINE_HITS],
+    len: usize,
+    /// Every hit, once there are more than fit inline.
+    spilled: Vec<Hit>,
+}
+
+impl HitList {
+    pub fn new() -> Self {
+        Self {
+            hits: [Hit::default(); INLINE_HITS],
+            len: 0,
+            spilled: Vec::new(),
+        }
+    }
+
+    /// Inserts the hit, keeping the list sorted by distance.
+    pub fn insert(&mut self, hit: Hit) {
+        if self.len == INLINE_HITS && self.spilled.is_empty() {
+            self.spilled.reserve(2 * INLINE_HITS);
+            self.spilled.extend_from_slice(&self.hits);
+        }
+
+        if !self.spilled.is_empty() {
+            let index = self.spilled.partition_point(|other| other <= &hit);
+            self.spilled.insert(index, hit);
+            return;
+        }
+
+        // Shift the further hits along.
+        let index = self.hits[..self.len].partition_point(|other| other <= &hit);
+        self.hits.copy_within(index..self.len, index + 1);
+        self.hits[index] = hit;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.spilled.clear();
+    }
+}
+
+impl Default for HitList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for HitList {
+    type Target = [Hit];
+
+    fn deref(&self) -> &[Hit] {
+        if self.spilled.is_empty() {
+            &self.hits[..self.len]
+        } else {
+            &self.spilled
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::{Vector, Vertex};
+
+    fn hit(distance: f32) -> Hit {
+        Hit::new(distance, true, Vertex::default(), Vector::default())
+    }
+
+    #[test]
+    fn test_hit_list_is_sorted() {
+        let mut hits = HitList::new();
+        for distance in [3.0, 1.0, f32::INFINITY, 2.0, f32::NEG_INFINITY] {
+            hits.insert(hit(distance));
+        }
+
+        let distances: Vec<f32> = hits.iter().map(|hit| hit.distance).collect();
+        assert_eq!(
+            distances,
+            vec![f32::NEG_INFINITY, 1.0, 2.0, 3.0, f32::INFINITY]
+        );
+    }
+
+    #[test]
+    fn test_hit_list_keeps_every_hit() {
+        let mut hits = HitList::new();
+        for i in 0..INLINE_HITS {
+            hits.insert(hit(i as f32 + 1.0));
+        }
+        assert_eq!(hits.len(), INLINE_HITS);
+
+        hits.insert(hit(100.0));
+        hits.insert(hit(0.5));
+        hits.insert(hit(1.5));
+
+        assert_eq!(hits.len(), INLINE_HITS + 3);
+        assert_eq!(hits[0].distance, 0.5);
+        assert_eq!(hits[2].distance, 1.5);
+        assert_eq!(hits[INLINE_HITS + 1].distance, INLINE_HITS as f32);
+        assert_eq!(hits[INLINE_HITS + 2].distance, 100.0);
+    }
+
+    #[test]
+    fn test_hit_list_clear() {
+        let mut hits = HitList::new();
+        for i in 0..=INLINE_HITS {
+            hits.insert(hit(i as f32));
+        }
+        hits.clear();
+
+        assert!(hits.is_empty());
+        hits.insert(hit(1.0));
+        assert_eq!(hits.len(), 1);
+    }
+}
@@ -0,0 +1,298 @@
+use std::sync::Arc;
+
+use crate::{
+    geometry::{
+        traits::{Bounded, Intersection, Transformable},
+        HitList, SceneObject,
+    },
+    primitives::{ray::Ray, AnimatedTransform, Hit, Transform, Vertex, AABB},
+    shading::Material,
+};
+
+/// A placement of shared geometry in the scene.
+///
+/// The geometry is loaded once and shared between any number of instances, each with its own
+/// transform and material. Rays are transformed into object space for intersection, and the
+/// hits transformed back into world space.
+///
+/// An animated instance moves over the shutter interval, and each ray sees it where it is at
+/// the ray's time, which blurs it in the render.
+#[derive(Debug)]
+pub struct Instance {
+    pub object: Arc<SceneObject>,
+    /// Object to world space.
+    transform: Transform,
+    /// World to object space.
+    inverse: Transform,
+    /// Object to world space over time, if the instance moves.
+    motion: Option<AnimatedTransform>,
+    /// Used in place of the shared object's own material.
+    pub material: Material,
+}
+
+impl Instance {
+    pub fn new(object: Arc<SceneObject>, transform: Transform, material: Material) -> Self {
+        Self {
+            object,
+            transform,
+            inverse: transform.inverse(),
+            motion: None,
+            material,
+        }
+    }
+
+    /// An instance that moves with the given transform over time.
+    pub fn animated(
+        object: Arc<SceneObject>,
+        motion: AnimatedTransform,
+        material: Material,
+    ) -> Self {
+        let (transform, _) = motion.at(0.0);
+        let mut instance = Self::new(object, transform, material);
+        if motion.is_animated() {
+            instance.motion = Some(motion);
+        }
+        instance
+    }
+
+    /// Transform from object to world space, at time zero for an animated instance.
+    pub fn transform_matrix(&self) -> &Transform {
+        &self.transform
+    }
+
+    /// Whether the instance, or the geometry it places, moves at any point between the two
+    /// times.
+    pub fn moves_between(&self, start: f32, end: f32) -> bool {
+        self.motion
+            .as_ref()
+            .is_some_and(|motion| motion.moves_between(start, end))
+            || self.object.moves_between(start, end)
+    }
+
+    /// Triangles covering the placed geometry's surface at the given time, if it can be
+    /// sampled.
+    pub fn surface_triangles(&self, time: f32) -> Option<Vec<[Vertex; 3]>> {
+        let (transform, _) = self.transforms_at(time);
+        let mut triangles = self.object.surface_triangles(time)?;
+        for vertex in triangles.iter_mut().flatten() {
+            transform.apply_to_vertex(vertex);
+        }
+        Some(triangles)
+    }
+
+    /// Transform and its inverse at the ray's time.
+    fn transforms_at(&self, time: f32) -> (Transform, Transform) {
+        match &self.motion {
+            Some(motion) => motion.at(time),
+            None => (self.transform, self.inverse),
+        }
+    }
+
+    /// Returns the ray in object space, along with the object space length of a unit of
+    /// distance along the world space ray.
+    ///
+    /// The object space direction is normalised, as the geometry expects, so distances have to
+    /// be scaled by this factor on the way in and out.
+    fn object_ray(ray: &Ray, inverse: &Transform) -> (Ray, f32) {
+        let mut position = ray.position;
+        inverse.apply_to_vertex(&mut position);
+
+        let mut direction = ray.direction;
+        inverse.apply_to_vector(&mut direction);
+        let scale = direction.length();
+
+        (
+            Ray::new(position, direction / scale).with_time(ray.time),
+            scale,
+        )
+    }
+
+    fn world_hit(hit: &Hit, scale: f32, (transform, inverse): &(Transform, Transform)) -> Hit {
+        let mut position = hit.position;
+        transform.apply_to_vertex(&mut position);
+
+        // Normals are transformed by the inverse transpose to stay perpendicular to the surface.
+        let mut normal = hit.normal;
+        inverse.transpose().apply_to_vector(&mut normal);
+
+        Hit::new(
+            hit.distance / scale,
+            hit.entering,
+            position,
+            normal.normalise(),
+        )
+    }
+}
+
+impl Intersection for Instance {
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        let transforms = self.transforms_at(ray.time);
+        let (object_ray, scale) = Self::object_ray(ray, &transforms.1);
+
+        for hit in self.object.generate_hits(&object_ray).iter() {
+            hits.insert(Self::world_hit(hit, scale, &transforms));
+        }
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        let transforms = self.transforms_at(ray.time);
+        let (object_ray, scale) = Self::object_ray(ray, &transforms.1);
+
+        self.object
+            .closest_hit(&object_ray, t_min * scale, t_max * scale)
+            .map(|hit| Self::world_hit(&hit, scale, &transforms))
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        let (_, inverse) = self.transforms_at(ray.time);
+        let (object_ray, scale) = Self::object_ray(ray, &inverse);
+
+        self.object
+            .any_hit(&object_ray, t_min * scale, t_max * scale)
+    }
+}
+
+impl Transformable for Instance {
+    /// Moves the instance without touching the shared geometry.
+    fn transform(&mut self, trans: &Transform) {
+        self.transform = *trans * self.transform;
+        self.inverse = self.transform.inverse();
+        if let Some(motion) = &mut self.motion {
+            motion.then(trans);
+        }
+    }
+}
+
+impl Bounded for Instance {
+    fn bounding_box(&self) -> Option<AABB> {
+        self.object.bounding_box().map(|bounds| match &self.motion {
+            Some(motion) => motion.bounding_box(&bounds),
+            None => bounds.transform(&self.transform),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        geometry::Sphere,
+        primitives::{Colour, Vector, Vertex},
+    };
+    use approx::assert_relative_eq;
+
+    fn material() -> Material {
+        Material::phong(Colour::default(), Colour::default(), Colour::default(), 1.0)
+    }
+
+    fn unit_sphere() -> Arc<SceneObject> {
+        Arc::new(Sphere::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0, material()).into())
+    }
+
+    /// Scales by 2 along x and moves to (5, 0, 0).
+    fn stretch_and_move() -> Transform {
+        Transform::new([
+            [2.0, 0.0, 0.0, 5.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    #[test]
+    fn test_instance_hit_in_world_space() {
+        let instance = Instance::new(unit_sphere(), stretch_and_move(), material());
+
+        // Along the stretched axis the ellipsoid spans x in [3, 7].
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0));
+        let hit = instance.first_hit(&ray).unwrap();
+        assert_relative_eq!(hit.distance, 3.0, epsilon = 1e-5);
+        assert_relative_eq!(hit.position.vector.x, 3.0, epsilon = 1e-5);
+        assert_relative_eq!(hit.normal.x, -1.0, epsilon = 1e-5);
+
+        let hits = instance.generate_hits(&ray);
+        assert_eq!(hits.len(), 2);
+        assert_relative_eq!(hits[1].distance, 7.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_instance_normal_uses_inverse_transpose() {
+        let instance = Instance::new(unit_sphere(), stretch_and_move(), material());
+
+        // Hit the ellipsoid (x - 5)^2 / 4 + y^2 + z^2 = 1 at (5 + sqrt(2), sqrt(0.5), 0).
+        let target = Vector::new(5.0 + 2.0_f32.sqrt(), 0.5_f32.sqrt(), 0.0);
+        let origin = target + Vector::new(0.0, 5.0, 0.0);
+        let ray = Ray::new(
+            Vertex::new(origin.x, origin.y, origin.z, 1.0),
+            Vector::new(0.0, -1.0, 0.0),
+        );
+
+        let hit = instance.first_hit(&ray).unwrap();
+        let expected = Vector::new(2.0_f32.sqrt() / 4.0, 0.5_f32.sqrt(), 0.0).normalise();
+        assert_relative_eq!(hit.normal.x, expected.x, epsilon = 1e-4);
+        assert_relative_eq!(hit.normal.y, expected.y, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_instance_ranges_are_in_world_space() {
+        let instance = Instance::new(unit_sphere(), stretch_and_move(), material());
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0));
+
+        assert!(instance.any_hit(&ray, 0.0, 4.0));
+        assert!(!instance.any_hit(&ray, 0.0, 2.5));
+
+        let far = instance.closest_hit(&ray, 4.0, f32::INFINITY).unwrap();
+        assert_relative_eq!(far.distance, 7.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_instances_share_geometry() {
+        let sphere = unit_sphere();
+        let mut moved = Instance::new(Arc::clone(&sphere), Transform::identity(), material());
+        let still = Instance::new(Arc::clone(&sphere), Transform::identity(), material());
+
+        moved.transform(&stretch_and_move());
+
+        let bounds = moved.bounding_box().unwrap();
+        assert_relative_eq!(bounds.min.x, 3.0, epsilon = 1e-5);
+        assert_relative_eq!(bounds.max.x, 7.0, epsilon = 1e-5);
+
+        let bounds = still.bounding_box().unwrap();
+        assert_relative_eq!(bounds.min.x, -1.0, epsilon = 1e-5);
+        assert_eq!(Arc::strong_count(&sphere), 3);
+    }
+
+    #[test]
+    fn test_animated_instance_moves_over_time() {
+        let motion = AnimatedTransform::between(
+            0.0,
+            Transform::identity(),
+            1.0,
+            Transform::new([
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 4.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+        );
+        let instance = Instance::animated(unit_sphere(), motion, material());
+
+        // Looking along x at height 2, the sphere passes by halfway through.
+        let ray = |time| {
+            Ray::new(Vertex::new(-5.0, 2.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0)).with_time(time)
+        };
+        assert!(instance.first_hit(&ray(0.0)).is_none());
+        assert!(instance.first_hit(&ray(1.0)).is_none());
+
+        let hit = instance.first_hit(&ray(0.5)).unwrap();
+        assert_relative_eq!(hit.distance, 4.0, epsilon = 1e-4);
+        assert_relative_eq!(hit.normal.x, -1.0, epsilon = 1e-4);
+
+        let bounds = instance.bounding_box().unwrap();
+        assert_relative_eq!(bounds.min.y, -1.0, epsilon = 1e-5);
+        assert_relative_eq!(bounds.max.y, 5.0, epsilon = 1e-5);
+
+        assert!(instance.moves_between(0.5, 2.0));
+        assert!(!instance.moves_between(1.0, 2.0));
+    }
+}
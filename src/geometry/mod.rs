@@ -1,4 +1,7 @@
+pub mod bvh;
 pub mod csg;
+pub mod hit_list;
+pub mod instance;
 pub mod plane;
 pub mod polymesh;
 pub mod quadratic;
@@ -6,10 +9,13 @@ pub mod scene_object;
 pub mod sphere;
 pub mod traits;
 
+pub use bvh::BVH;
 pub use csg::CSG;
+pub use hit_list::HitList;
+pub use instance::Instance;
 pub use plane::{Plane, PlaneGeometry};
 pub use polymesh::{PolyMesh, PolyMeshGeometry};
 pub use quadratic::{Quadratic, QuadraticGeometry};
 pub use scene_object::SceneObject;
 pub use sphere::{Sphere, SphereGeometry};
-pub use traits::{Bounded, HitPool, Intersection, Transformable};
+pub use traits::{Bounded, Intersection, Transformable};
@@ -1,6 +1,9 @@
 use crate::{
-    geometry::traits::{HitPool, Intersection, Transformable},
-    primitives::{ray::Ray, Hit, Transform, Vector, Vertex},
+    geometry::{
+        traits::{Bounded, Intersection, Transformable},
+        HitList,
+    },
+    primitives::{ray::Ray, Hit, Transform, Vector, Vertex, AABB},
     shading::Material,
 };
 
@@ -24,8 +27,10 @@ impl PlaneGeometry {
     }
 }
 
-impl Intersection for PlaneGeometry {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
+impl PlaneGeometry {
+    /// Signed distance from the ray origin to the plane, and the alignment of the ray direction
+    /// with the plane normal. Both are scaled by the length of the normal.
+    fn ray_alignment(&self, ray: &Ray) -> (f32, f32) {
         let distance_to_plane: f32 = self.a * ray.position.vector.x
             + self.b * ray.position.vector.y
             + self.c * ray.position.vector.z
@@ -34,17 +39,37 @@ impl Intersection for PlaneGeometry {
         let direction_dot_normal =
             self.a * ray.direction.x + self.b * ray.direction.y + self.c * ray.direction.z;
 
+        (distance_to_plane, direction_dot_normal)
+    }
+
+    fn hit_at(&self, ray: &Ray, t: f32, entering: bool) -> Hit {
+        let hit_position = ray.position + t * ray.direction;
+        let mut hit_normal = Vector::new(self.a, self.b, self.c);
+
+        // Flip normal if pointing away from the surface we are looking at.
+        if hit_normal.dot(ray.direction) > 0.0 {
+            hit_normal = hit_normal.negate();
+        }
+
+        Hit::new(t, entering, hit_position, hit_normal)
+    }
+}
+
+impl Intersection for PlaneGeometry {
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        let (distance_to_plane, direction_dot_normal) = self.ray_alignment(ray);
+
         if direction_dot_normal == 0.0 {
             // Ray is parallel to the plane.
             if distance_to_plane < 0.0 {
                 // The ray starts outside the plane and will never intersect.
-                hitpool.insert(Hit::new(
+                hits.insert(Hit::new(
                     f32::NEG_INFINITY,
                     true,
                     Vertex::default(),
                     Vector::default(),
                 ));
-                hitpool.insert(Hit::new(
+                hits.insert(Hit::new(
                     f32::INFINITY,
                     false,
                     Vertex::default(),
@@ -56,27 +81,20 @@ impl Intersection for PlaneGeometry {
         }
 
         let t = distance_to_plane / -direction_dot_normal;
-        let hit_position = ray.position + t * ray.direction;
-        let mut hit_normal = Vector::new(self.a, self.b, self.c);
-
-        // Flip normal if pointing away from the surface we are looking at.
-        if hit_normal.dot(ray.direction) > 0.0 {
-            hit_normal = hit_normal.negate();
-        }
 
         if direction_dot_normal > 0.0 {
             // Ray comes from outside to inside.
-            hitpool.insert(Hit::new(
+            hits.insert(Hit::new(
                 f32::NEG_INFINITY,
                 true,
                 Vertex::default(),
                 Vector::default(),
             ));
-            hitpool.insert(Hit::new(t, false, hit_position, hit_normal));
+            hits.insert(self.hit_at(ray, t, false));
         } else {
             // Ray comes from inside to outside.
-            hitpool.insert(Hit::new(t, true, hit_position, hit_normal));
-            hitpool.insert(Hit::new(
+            hits.insert(self.hit_at(ray, t, true));
+            hits.insert(Hit::new(
                 f32::INFINITY,
                 false,
                 Vertex::default(),
@@ -84,6 +102,30 @@ impl Intersection for PlaneGeometry {
             ));
         }
     }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        let (distance_to_plane, direction_dot_normal) = self.ray_alignment(ray);
+
+        if direction_dot_normal == 0.0 {
+            // A parallel ray only has hits at infinity.
+            return None;
+        }
+
+        let t = distance_to_plane / -direction_dot_normal;
+        (t_min < t && t < t_max).then(|| self.hit_at(ray, t, direction_dot_normal < 0.0))
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        let (distance_to_plane, direction_dot_normal) = self.ray_alignment(ray);
+
+        if direction_dot_normal == 0.0 {
+            // A parallel ray only has hits at infinity.
+            return false;
+        }
+
+        let t = distance_to_plane / -direction_dot_normal;
+        t_min < t && t < t_max
+    }
 }
 
 impl Transformable for PlaneGeometry {
@@ -99,6 +141,13 @@ impl Transformable for PlaneGeometry {
     }
 }
 
+impl Bounded for PlaneGeometry {
+    /// Planes are infinite.
+    fn bounding_box(&self) -> Option<AABB> {
+        None
+    }
+}
+
 #[derive(Debug)]
 pub struct Plane {
     pub geometry: PlaneGeometry,
@@ -115,8 +164,16 @@ impl Plane {
 }
 
 impl Intersection for Plane {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
-        self.geometry.intersect(ray, hitpool)
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        self.geometry.intersect(ray, hits)
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        self.geometry.closest_hit(ray, t_min, t_max)
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.geometry.any_hit(ray, t_min, t_max)
     }
 }
 
@@ -126,6 +183,12 @@ impl Transformable for Plane {
     }
 }
 
+impl Bounded for Plane {
+    fn bounding_box(&self) -> Option<AABB> {
+        self.geometry.bounding_box()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,8 +211,8 @@ mod tests {
         // Ray shooting upward from origin
         let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(0.0, 1.0, 0.0));
 
-        let hitpool = plane.generate_hitpool(&ray);
-        assert!(hitpool.len() > 0);
+        let hits = plane.generate_hits(&ray);
+        assert!(!hits.is_empty());
     }
 
     #[test]
@@ -160,10 +223,10 @@ mod tests {
         // Ray parallel to plane
         let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0));
 
-        let hitpool = plane.generate_hitpool(&ray);
+        let hits = plane.generate_hits(&ray);
         // Parallel rays should not intersect or have special handling
         // Length could be 0 or 2 depending on implementation
-        let _ = hitpool;
+        let _ = hits;
     }
 
     #[test]
@@ -184,9 +247,9 @@ mod tests {
         // Ray starting on the plane
         let ray = Ray::new(Vertex::new(5.0, 0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0));
 
-        let hitpool = plane.generate_hitpool(&ray);
+        let hits = plane.generate_hits(&ray);
         // Check it doesn't panic
-        drop(hitpool);
+        let _ = hits;
     }
 
     #[test]
@@ -208,8 +271,21 @@ mod tests {
         // Ray pointing away from plane
         let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(0.0, -1.0, 0.0));
 
-        let hitpool = plane.generate_hitpool(&ray);
+        let hits = plane.generate_hits(&ray);
         // Check it doesn't panic
-        drop(hitpool);
+        let _ = hits;
+    }
+
+    #[test]
+    fn test_plane_any_hit() {
+        let plane = PlaneGeometry::new(0.0, 1.0, 0.0, 0.0);
+        let ray = Ray::new(Vertex::new(0.0, 5.0, 0.0, 1.0), Vector::new(0.0, -1.0, 0.0));
+
+        assert!(plane.any_hit(&ray, 0.0, 10.0));
+        assert!(!plane.any_hit(&ray, 0.0, 4.0));
+
+        // Parallel rays are never occluded by the sentinel hits at infinity.
+        let parallel = Ray::new(Vertex::new(0.0, -5.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(!plane.any_hit(&parallel, 0.0, f32::INFINITY));
     }
 }
@@ -1,8 +1,11 @@
 use std::io;
 
 use crate::{
-    geometry::traits::{Bounded, HitPool, Intersection, Transformable},
-    primitives::{ray::Ray, Hit, Transform, Vector, Vertex},
+    geometry::{
+        traits::{Bounded, Intersection, Transformable},
+        HitList, BVH,
+    },
+    primitives::{ray::Ray, Hit, Transform, Vector, Vertex, AABB},
     shading::Material,
     utilities::obj_reader::{ObjReader, Triangle},
 };
@@ -21,27 +24,70 @@ pub struct PolyMeshGeometry {
     pub vertices: Vec<Vertex>,
     pub vertex_normals: Vec<Vertex>,
     pub triangles: Vec<Triangle>,
+    /// Traverse the triangle BVH when intersecting. When disabled every triangle is tested,
+    /// which returns the same hits and is kept for testing.
+    pub use_bvh: bool,
+    bvh: BVH,
 }
 
 impl PolyMeshGeometry {
+    pub fn new(
+        vertices: Vec<Vertex>,
+        vertex_normals: Vec<Vertex>,
+        triangles: Vec<Triangle>,
+        smooth: bool,
+    ) -> Self {
+        let mut mesh = Self {
+            smooth,
+            vertices,
+            vertex_normals,
+            triangles,
+            use_bvh: true,
+            bvh: BVH::default(),
+        };
+        mesh.build_bvh();
+        mesh
+    }
+
     pub fn from_obj(file_path: &str, smooth: bool) -> io::Result<Self> {
         let obj_reader = ObjReader::new(file_path)?;
 
-        Ok(Self {
+        Ok(Self::new(
+            obj_reader.vertices().to_vec(),
+            obj_reader.vertex_normals().to_vec(),
+            obj_reader.triangles(),
             smooth,
-            vertices: obj_reader.vertices().to_vec(),
-            vertex_normals: obj_reader.vertex_normals().to_vec(),
-            triangles: obj_reader.triangles(),
-        })
+        ))
     }
 
-    fn add_hit(
-        &self,
-        hitpool: &mut HitPool,
-        triangle_index: usize,
-        ray: &Ray,
-        intersection: IntersectionData,
-    ) {
+    /// Corners of each triangle.
+    pub fn surface_triangles(&self) -> Vec<[Vertex; 3]> {
+        self.triangles
+            .iter()
+            .map(|triangle| triangle.vertex_indices.map(|index| self.vertices[index]))
+            .collect()
+    }
+
+    /// Rebuilds the triangle BVH.
+    /// Must be called after the vertices or triangles are modified directly.
+    pub fn build_bvh(&mut self) {
+        let bounds: Vec<Option<AABB>> = self
+            .triangles
+            .iter()
+            .map(|triangle| {
+                Some(AABB::from_points(
+                    triangle
+                        .vertex_indices
+                        .iter()
+                        .map(|&index| &self.vertices[index].vector),
+                ))
+            })
+            .collect();
+
+        self.bvh = BVH::new(&bounds);
+    }
+
+    fn hit_at(&self, triangle_index: usize, ray: &Ray, intersection: IntersectionData) -> Hit {
         let hit_position = ray.position + intersection.t * ray.direction;
 
         let mut hit_normal = if self.smooth {
@@ -64,12 +110,12 @@ impl PolyMeshGeometry {
             hit_normal = hit_normal.negate();
         }
 
-        hitpool.insert(Hit::new(
+        Hit::new(
             intersection.t,
             intersection.entering,
             hit_position,
             hit_normal,
-        ));
+        )
     }
 
     /// Triangle intersection using the `Möller–Trumbore intersection algorithm`.
@@ -131,13 +177,63 @@ impl PolyMeshGeometry {
 }
 
 impl Intersection for PolyMeshGeometry {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
-        // For each triangle in the model.
-        for i in 0..self.triangles.len() {
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        let mut test_triangle = |i: usize| {
             if let Some(intersection) = self.triangle_intersection(ray, i) {
-                self.add_hit(hitpool, i, ray, intersection);
+                hits.insert(self.hit_at(i, ray, intersection));
+            }
+        };
+
+        if self.use_bvh {
+            // Only the triangles whose boxes the line of the ray passes through. Hits behind
+            // the origin are kept like in the linear search, as they close off its spans.
+            self.bvh
+                .for_each_candidate(ray, f32::NEG_INFINITY, f32::INFINITY, &mut test_triangle);
+        } else {
+            // For each triangle in the model.
+            for i in 0..self.triangles.len() {
+                test_triangle(i);
+            }
+        }
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        let mut closest: Option<(usize, IntersectionData)> = None;
+
+        let mut test_triangle = |i: usize, max_distance: f32| {
+            let intersection = self
+                .triangle_intersection(ray, i)
+                .filter(|intersection| t_min < intersection.t && intersection.t < max_distance)?;
+            let distance = intersection.t;
+            closest = Some((i, intersection));
+            Some(distance)
+        };
+
+        if self.use_bvh {
+            self.bvh.closest_hit(ray, t_max, &mut test_triangle);
+        } else {
+            let mut max_distance = t_max;
+            for i in 0..self.triangles.len() {
+                if let Some(distance) = test_triangle(i, max_distance) {
+                    max_distance = distance;
+                }
             }
         }
+
+        closest.map(|(i, intersection)| self.hit_at(i, ray, intersection))
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        let hits_triangle = |i: usize| {
+            self.triangle_intersection(ray, i)
+                .is_some_and(|intersection| t_min < intersection.t && intersection.t < t_max)
+        };
+
+        if self.use_bvh {
+            self.bvh.any_hit(ray, t_max, hits_triangle)
+        } else {
+            (0..self.triangles.len()).any(hits_triangle)
+        }
     }
 }
 
@@ -159,10 +255,22 @@ impl Transformable for PolyMeshGeometry {
                 .transpose()
                 .apply_to_vector(&mut triangle.face_normal)
         }
+
+        self.build_bvh();
     }
 }
 
 impl Bounded for PolyMeshGeometry {
+    fn bounding_box(&self) -> Option<AABB> {
+        if self.vertices.is_empty() {
+            return None;
+        }
+
+        Some(AABB::from_points(
+            self.vertices.iter().map(|vertex| &vertex.vector),
+        ))
+    }
+
     fn bounding_sphere(&self) -> Option<(Vertex, f32)> {
         if self.vertices.is_empty() {
             return None;
@@ -219,8 +327,16 @@ impl PolyMesh {
 }
 
 impl Intersection for PolyMesh {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
-        self.geometry.intersect(ray, hitpool)
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        self.geometry.intersect(ray, hits)
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        self.geometry.closest_hit(ray, t_min, t_max)
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.geometry.any_hit(ray, t_min, t_max)
     }
 }
 
@@ -231,6 +347,10 @@ impl Transformable for PolyMesh {
 }
 
 impl Bounded for PolyMesh {
+    fn bounding_box(&self) -> Option<AABB> {
+        self.geometry.bounding_box()
+    }
+
     fn bounding_sphere(&self) -> Option<(Vertex, f32)> {
         self.geometry.bounding_sphere()
     }
@@ -260,12 +380,7 @@ mod tests {
             face_normal: Vector::new(0.0, 0.0, 1.0),
         }];
 
-        PolyMeshGeometry {
-            smooth: false,
-            vertices,
-            vertex_normals,
-            triangles,
-        }
+        PolyMeshGeometry::new(vertices, vertex_normals, triangles, false)
     }
 
     #[test]
@@ -302,9 +417,8 @@ mod tests {
     }
 
     #[test]
-    fn test_add_hit_normal_calculation() {
+    fn test_hit_normal_calculation() {
         let mesh = create_triangle();
-        let mut hitpool = HitPool::new();
 
         let ray = Ray::new(Vertex::new(0.5, 0.5, -1.0, 1.0), Vector::new(0.0, 0.0, 1.0));
 
@@ -315,13 +429,11 @@ mod tests {
             entering: true,
         };
 
-        mesh.add_hit(&mut hitpool, 0, &ray, intersection);
+        let hit = mesh.hit_at(0, &ray, intersection);
 
-        assert_eq!(hitpool.len(), 1);
-        let hits = hitpool.flatten();
-        assert_eq!(hits[0].entering, true);
+        assert!(hit.entering);
         // Normal is flipped to face the incoming ray
-        assert_relative_eq!(hits[0].normal.z, -1.0, epsilon = 1e-5);
+        assert_relative_eq!(hit.normal.z, -1.0, epsilon = 1e-5);
     }
 
     #[test]
@@ -334,14 +446,14 @@ mod tests {
 
         let ray = Ray::new(Vertex::new(0.5, 0.5, -1.0, 1.0), Vector::new(0.0, 0.0, 1.0));
 
-        let mut flat_hitpool = HitPool::new();
-        let mut smooth_hitpool = HitPool::new();
+        let mut flat_hits = HitList::new();
+        let mut smooth_hits = HitList::new();
 
-        flat_mesh.intersect(&ray, &mut flat_hitpool);
-        smooth_mesh.intersect(&ray, &mut smooth_hitpool);
+        flat_mesh.intersect(&ray, &mut flat_hits);
+        smooth_mesh.intersect(&ray, &mut smooth_hits);
 
-        assert_eq!(flat_hitpool.len(), 1);
-        assert_eq!(smooth_hitpool.len(), 1);
+        assert_eq!(flat_hits.len(), 1);
+        assert_eq!(smooth_hits.len(), 1);
     }
 
     #[test]
@@ -362,17 +474,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bounding_box_calculation() {
+        let mesh = create_triangle();
+        let bounds = mesh.bounding_box().unwrap();
+
+        assert_eq!(bounds.min, Vector::new(0.0, 0.0, 0.0));
+        assert_eq!(bounds.max, Vector::new(1.0, 1.0, 0.0));
+    }
+
     #[test]
     fn test_bounding_sphere_empty_mesh() {
-        let empty_mesh = PolyMeshGeometry {
-            smooth: false,
-            vertices: Vec::new(),
-            vertex_normals: Vec::new(),
-            triangles: Vec::new(),
-        };
+        let empty_mesh = PolyMeshGeometry::new(Vec::new(), Vec::new(), Vec::new(), false);
 
         let bounding = empty_mesh.bounding_sphere();
         assert!(bounding.is_none());
+        assert!(empty_mesh.bounding_box().is_none());
     }
 
     #[test]
@@ -390,12 +507,8 @@ mod tests {
         mesh.transform(&translation);
 
         // Vertices should be translated
-        for i in 0..mesh.vertices.len() {
-            assert_relative_eq!(
-                mesh.vertices[i].vector.x,
-                original_vertices[i].vector.x + 5.0,
-                epsilon = 1e-5
-            );
+        for (vertex, original) in mesh.vertices.iter().zip(&original_vertices) {
+            assert_relative_eq!(vertex.vector.x, original.vector.x + 5.0, epsilon = 1e-5);
         }
     }
 
@@ -406,11 +519,10 @@ mod tests {
         // Ray from behind (should flip normal)
         let ray = Ray::new(Vertex::new(0.5, 0.5, 1.0, 1.0), Vector::new(0.0, 0.0, -1.0));
 
-        let mut hitpool = HitPool::new();
-        mesh.intersect(&ray, &mut hitpool);
+        let mut hits = HitList::new();
+        mesh.intersect(&ray, &mut hits);
 
-        if hitpool.len() > 0 {
-            let hits = hitpool.flatten();
+        if !hits.is_empty() {
             // Normal should be flipped to face the ray
             assert!(hits[0].normal.dot(ray.direction) <= 0.0);
         }
@@ -431,12 +543,102 @@ mod tests {
             vertex_normal_indices: [3, 4, 5],
             face_normal: Vector::new(0.0, 0.0, 1.0),
         });
+        mesh.build_bvh();
 
         let ray = Ray::new(Vertex::new(0.5, 0.5, -1.0, 1.0), Vector::new(0.0, 0.0, 1.0));
 
-        let mut hitpool = HitPool::new();
-        mesh.intersect(&ray, &mut hitpool);
+        let mut hits = HitList::new();
+        mesh.intersect(&ray, &mut hits);
 
-        assert!(hitpool.len() > 0);
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn test_bvh_matches_linear_intersection() {
+        let mut mesh = PolyMeshGeometry::from_obj(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/src/assets/teapot-low.obj"),
+            true,
+        )
+        .unwrap();
+        mesh.transform(&Transform::new([
+            [2.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [0.0, 2.0, 0.0, 10.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]));
+
+        // Rays starting in front of the mesh, inside it and past it.
+        for z in [-20.0, 12.0, 40.0] {
+            for x in -10..=10 {
+                for y in -10..=10 {
+                    let ray = Ray::new(
+                        Vertex::new(x as f32 * 0.5, y as f32 * 0.5, z, 1.0),
+                        Vector::new(0.0, 0.01 * y as f32, 1.0).normalise(),
+                    );
+
+                    mesh.use_bvh = true;
+                    let bvh_hits: Vec<f32> = mesh
+                        .generate_hits(&ray)
+                        .iter()
+                        .map(|hit| hit.distance)
+                        .collect();
+
+                    mesh.use_bvh = false;
+                    let linear_hits: Vec<f32> = mesh
+                        .generate_hits(&ray)
+                        .iter()
+                        .map(|hit| hit.distance)
+                        .collect();
+
+                    assert_eq!(bvh_hits, linear_hits);
+
+                    let first = linear_hits.iter().find(|&&distance| distance > 0.0);
+                    for use_bvh in [true, false] {
+                        mesh.use_bvh = use_bvh;
+                        let closest = mesh.closest_hit(&ray, 0.0, f32::INFINITY);
+                        assert_eq!(closest.map(|hit| hit.distance).as_ref(), first);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_any_hit_matches_first_hit() {
+        let mut mesh = PolyMeshGeometry::from_obj(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/src/assets/teapot-low.obj"),
+            true,
+        )
+        .unwrap();
+        mesh.transform(&Transform::new([
+            [2.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [0.0, 2.0, 0.0, 10.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]));
+
+        // Rays starting in front of the mesh, inside it and past it.
+        for (x, y, z) in (-10..=10)
+            .flat_map(|x| (-10..=10).map(move |y| (x, y)))
+            .flat_map(|(x, y)| [-20.0, 12.0, 40.0].map(|z| (x, y, z)))
+        {
+            {
+                let ray = Ray::new(
+                    Vertex::new(x as f32 * 0.5, y as f32 * 0.5, z, 1.0),
+                    Vector::new(0.0, 0.01 * y as f32, 1.0).normalise(),
+                );
+
+                for max_distance in [20.0, 25.0, f32::INFINITY] {
+                    let expected = mesh
+                        .first_hit(&ray)
+                        .is_some_and(|hit| hit.distance < max_distance);
+
+                    mesh.use_bvh = true;
+                    assert_eq!(mesh.any_hit(&ray, 0.0, max_distance), expected);
+                    mesh.use_bvh = false;
+                    assert_eq!(mesh.any_hit(&ray, 0.0, max_distance), expected);
+                }
+            }
+        }
     }
 }
@@ -1,11 +1,16 @@
+use serde::Deserialize;
+
 use crate::{
-    geometry::traits::{HitPool, Intersection, Transformable},
-    primitives::{ray::Ray, Hit, Transform, Vector, Vertex},
+    geometry::{
+        traits::{Bounded, Intersection, Transformable},
+        HitList,
+    },
+    primitives::{ray::Ray, Hit, Transform, Vector, Vertex, AABB},
     shading::Material,
 };
 
 /// Coefficients for a quadratic surface: ax² + 2bxy + 2cxz + 2dx + ey² + 2fyz + 2gy + hz² + 2iz + j = 0
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub struct QuadraticCoefficients {
     pub a: f32,
     pub b: f32,
@@ -22,17 +27,29 @@ pub struct QuadraticCoefficients {
 #[derive(Clone, Copy, Debug)]
 pub struct QuadraticGeometry {
     coeffs: QuadraticCoefficients,
+    /// Optional box the surface is clipped to. Unclipped quadrics may extend to infinity.
+    bounds: Option<AABB>,
 }
 
+/// Tolerance used when testing whether a hit lies inside the clipping bounds.
+const CLIPPING_TOLERANCE: f32 = 1e-4;
+
 impl QuadraticGeometry {
     /// Quadratic surface `ax^2 + 2bxy + 2cxz + 2dx + ey^2 + 2fyz + 2gy + hz^2 + 2iz + j = 0`.
     pub fn new(coefficients: QuadraticCoefficients) -> Self {
         Self {
             coeffs: coefficients,
+            bounds: None,
         }
     }
 
-    fn add_hit(&self, hitpool: &mut HitPool, ray: &Ray, t: f32, entering: bool) {
+    /// Clips the surface to the box, discarding any hits outside of it.
+    pub fn with_bounds(mut self, bounds: AABB) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    fn hit_at(&self, ray: &Ray, t: f32, entering: bool) -> Hit {
         let hit_position: Vertex = ray.position + t * ray.direction;
         let mut hit_normal = Vector::new(
             self.coeffs.a * hit_position.vector.x
@@ -55,12 +72,21 @@ impl QuadraticGeometry {
             hit_normal = hit_normal.negate();
         }
 
-        hitpool.insert(Hit::new(t, entering, hit_position, hit_normal));
+        Hit::new(t, entering, hit_position, hit_normal)
     }
-}
 
-impl Intersection for QuadraticGeometry {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
+    /// Whether the hit at distance `t` along the ray lies inside the clipping bounds.
+    fn within_bounds(&self, ray: &Ray, t: f32) -> bool {
+        self.bounds.is_none_or(|bounds| {
+            bounds.contains(
+                (ray.position + t * ray.direction).vector,
+                CLIPPING_TOLERANCE,
+            )
+        })
+    }
+
+    /// Distances at which the ray enters and exits the surface, if it hits it at all.
+    fn intersection_distances(&self, ray: &Ray) -> Option<(f32, f32)> {
         let (dir_x, dir_y, dir_z) = (ray.direction.x, ray.direction.y, ray.direction.z);
         let (pos_x, pos_y, pos_z) = (
             ray.position.vector.x,
@@ -98,17 +124,52 @@ impl Intersection for QuadraticGeometry {
         let discriminant = b_quadratic.powi(2) - 4.0 * a_quadratic * c_quadratic;
         if discriminant < 0.0 || a_quadratic == 0.0 {
             // No intersection or ray is tangent to the surface.
-            return;
+            return None;
         }
 
         let t0 = (-b_quadratic - (discriminant).sqrt()) / (2.0 * a_quadratic);
         let t1 = (-b_quadratic + (discriminant).sqrt()) / (2.0 * a_quadratic);
         if t0 < 0.0 && t1 < 0.0 {
-            return;
+            return None;
+        }
+
+        Some((t0, t1))
+    }
+}
+
+impl Intersection for QuadraticGeometry {
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        if let Some((t0, t1)) = self.intersection_distances(ray) {
+            for (t, entering) in [(t0, true), (t1, false)] {
+                if self.within_bounds(ray, t) {
+                    hits.insert(self.hit_at(ray, t, entering));
+                }
+            }
         }
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        let (t0, t1) = self.intersection_distances(ray)?;
+
+        // The roots are only ordered by distance when the leading coefficient is positive.
+        let candidates = if t0 <= t1 {
+            [(t0, true), (t1, false)]
+        } else {
+            [(t1, false), (t0, true)]
+        };
+
+        candidates
+            .into_iter()
+            .find(|&(t, _)| t_min < t && t < t_max && self.within_bounds(ray, t))
+            .map(|(t, entering)| self.hit_at(ray, t, entering))
+    }
 
-        self.add_hit(hitpool, ray, t0, true);
-        self.add_hit(hitpool, ray, t1, false);
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.intersection_distances(ray).is_some_and(|(t0, t1)| {
+            [t0, t1]
+                .into_iter()
+                .any(|t| t_min < t && t < t_max && self.within_bounds(ray, t))
+        })
     }
 }
 
@@ -120,7 +181,10 @@ impl Transformable for QuadraticGeometry {
             [self.coeffs.c, self.coeffs.f, self.coeffs.h, self.coeffs.i],
             [self.coeffs.d, self.coeffs.g, self.coeffs.i, self.coeffs.j],
         ]);
-        let transformed_quadratic = trans.transpose() * (quadratic * *trans);
+        // A point p lies on the moved surface if the inverse transform takes it onto the
+        // original one, so the matrix becomes inverse^T * Q * inverse.
+        let inverse = trans.inverse();
+        let transformed_quadratic = inverse.transpose() * (quadratic * inverse);
 
         (
             self.coeffs.a,
@@ -145,6 +209,16 @@ impl Transformable for QuadraticGeometry {
             transformed_quadratic.matrix[2][3],
             transformed_quadratic.matrix[3][3],
         );
+
+        if let Some(bounds) = self.bounds {
+            self.bounds = Some(bounds.transform(trans));
+        }
+    }
+}
+
+impl Bounded for QuadraticGeometry {
+    fn bounding_box(&self) -> Option<AABB> {
+        self.bounds
     }
 }
 
@@ -161,11 +235,25 @@ impl Quadratic {
             material,
         }
     }
+
+    /// Clips the surface to the box, discarding any hits outside of it.
+    pub fn with_bounds(mut self, bounds: AABB) -> Self {
+        self.geometry = self.geometry.with_bounds(bounds);
+        self
+    }
 }
 
 impl Intersection for Quadratic {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
-        self.geometry.intersect(ray, hitpool)
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        self.geometry.intersect(ray, hits)
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        self.geometry.closest_hit(ray, t_min, t_max)
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.geometry.any_hit(ray, t_min, t_max)
     }
 }
 
@@ -175,6 +263,12 @@ impl Transformable for Quadratic {
     }
 }
 
+impl Bounded for Quadratic {
+    fn bounding_box(&self) -> Option<AABB> {
+        self.geometry.bounding_box()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -251,4 +345,121 @@ mod tests {
         let hit = quad.first_hit(&ray);
         assert!(hit.is_none());
     }
+
+    #[test]
+    fn test_quadratic_clipping_bounds() {
+        // Cylinder along z-axis: x^2 + y^2 - 1 = 0, clipped to z in [0, 2].
+        let coeffs = QuadraticCoefficients {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+            e: 1.0,
+            f: 0.0,
+            g: 0.0,
+            h: 0.0,
+            i: 0.0,
+            j: -1.0,
+        };
+        let bounds = AABB::new(Vector::new(-1.0, -1.0, 0.0), Vector::new(1.0, 1.0, 2.0));
+
+        let unclipped = QuadraticGeometry::new(coeffs);
+        let clipped = QuadraticGeometry::new(coeffs).with_bounds(bounds);
+        assert!(unclipped.bounding_box().is_none());
+
+        let inside = Ray::new(Vertex::new(-5.0, 0.0, 1.0, 1.0), Vector::new(1.0, 0.0, 0.0));
+        let outside = Ray::new(Vertex::new(-5.0, 0.0, 3.0, 1.0), Vector::new(1.0, 0.0, 0.0));
+
+        assert!(clipped.first_hit(&inside).is_some());
+        assert!(unclipped.first_hit(&outside).is_some());
+        assert!(clipped.first_hit(&outside).is_none());
+    }
+
+    #[test]
+    fn test_quadratic_bounds_follow_transform() {
+        let coeffs = QuadraticCoefficients {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+            e: 1.0,
+            f: 0.0,
+            g: 0.0,
+            h: 0.0,
+            i: 0.0,
+            j: -1.0,
+        };
+        let bounds = AABB::new(Vector::new(-1.0, -1.0, 0.0), Vector::new(1.0, 1.0, 2.0));
+        let mut quad = QuadraticGeometry::new(coeffs).with_bounds(bounds);
+
+        quad.transform(&Transform::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 5.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]));
+
+        let ray = Ray::new(Vertex::new(-5.0, 0.0, 6.0, 1.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(quad.first_hit(&ray).is_some());
+    }
+
+    #[test]
+    fn test_quadratic_any_hit() {
+        // x^2 + y^2 + z^2 - 1 = 0 (unit sphere)
+        let coeffs = QuadraticCoefficients {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+            e: 1.0,
+            f: 0.0,
+            g: 0.0,
+            h: 1.0,
+            i: 0.0,
+            j: -1.0,
+        };
+        let quad = QuadraticGeometry::new(coeffs);
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(quad.any_hit(&ray, 0.0, 5.0));
+        assert!(!quad.any_hit(&ray, 0.0, 3.0));
+
+        // Clipping away the front half leaves only the exit at distance 6.
+        let clipped = quad.with_bounds(AABB::new(
+            Vector::new(-1.0, -1.0, 0.0),
+            Vector::new(1.0, 1.0, 1.0),
+        ));
+        assert!(!clipped.any_hit(&ray, 0.0, 5.0));
+        assert!(clipped.any_hit(&ray, 0.0, 7.0));
+    }
+
+    #[test]
+    fn test_quadratic_transform_moves_surface() {
+        // x^2 + y^2 + z^2 - 1 = 0 (unit sphere)
+        let coeffs = QuadraticCoefficients {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+            e: 1.0,
+            f: 0.0,
+            g: 0.0,
+            h: 1.0,
+            i: 0.0,
+            j: -1.0,
+        };
+        let mut quad = QuadraticGeometry::new(coeffs);
+
+        quad.transform(&Transform::new([
+            [2.0, 0.0, 0.0, 5.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]));
+
+        // Stretched along x to span [3, 7].
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0));
+        let hit = quad.first_hit(&ray).unwrap();
+        assert!((hit.distance - 3.0).abs() < 1e-4);
+    }
 }
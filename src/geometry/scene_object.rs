@@ -1,10 +1,16 @@
 use crate::{
-    geometry::traits::{Bounded, HitPool, Intersection, Transformable},
-    primitives::{ray::Ray, Transform, Vertex},
+    geometry::{
+        traits::{Bounded, Intersection, Transformable},
+        HitList,
+    },
+    primitives::{ray::Ray, Hit, Transform, Vertex, AABB},
     shading::Material,
 };
 
-use super::{csg::CSG, plane::Plane, polymesh::PolyMesh, quadratic::Quadratic, sphere::Sphere};
+use super::{
+    csg::CSG, instance::Instance, plane::Plane, polymesh::PolyMesh, quadratic::Quadratic,
+    sphere::Sphere,
+};
 
 #[derive(Debug)]
 pub enum SceneObject {
@@ -13,6 +19,7 @@ pub enum SceneObject {
     Quadratic(Quadratic),
     PolyMesh(PolyMesh),
     CSG(Box<CSG>), // Boxed because CSG is recursive
+    Instance(Instance),
 }
 
 impl SceneObject {
@@ -23,6 +30,31 @@ impl SceneObject {
             SceneObject::Quadratic(q) => &q.material,
             SceneObject::PolyMesh(pm) => &pm.material,
             SceneObject::CSG(csg) => &csg.material,
+            SceneObject::Instance(instance) => &instance.material,
+        }
+    }
+
+    /// Triangles covering the object's surface as placed at the given time, for sampling
+    /// points on it. Spheres are approximated, and planes, quadratic surfaces and CSG objects
+    /// can't be sampled.
+    pub fn surface_triangles(&self, time: f32) -> Option<Vec<[Vertex; 3]>> {
+        match self {
+            SceneObject::Sphere(s) => Some(s.geometry.surface_triangles()),
+            SceneObject::PolyMesh(pm) => Some(pm.geometry.surface_triangles()),
+            SceneObject::Instance(instance) => instance.surface_triangles(time),
+            _ => None,
+        }
+    }
+
+    /// Whether the object, or any part of it, moves at any point between the two times.
+    pub fn moves_between(&self, start: f32, end: f32) -> bool {
+        match self {
+            SceneObject::CSG(csg) => {
+                csg.left_object.moves_between(start, end)
+                    || csg.right_object.moves_between(start, end)
+            }
+            SceneObject::Instance(instance) => instance.moves_between(start, end),
+            _ => false,
         }
     }
 }
@@ -57,14 +89,43 @@ impl From<CSG> for SceneObject {
     }
 }
 
+impl From<Instance> for SceneObject {
+    fn from(instance: Instance) -> Self {
+        SceneObject::Instance(instance)
+    }
+}
+
 impl Intersection for SceneObject {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
         match self {
-            SceneObject::Sphere(s) => s.intersect(ray, hitpool),
-            SceneObject::Plane(p) => p.intersect(ray, hitpool),
-            SceneObject::Quadratic(q) => q.intersect(ray, hitpool),
-            SceneObject::PolyMesh(pm) => pm.intersect(ray, hitpool),
-            SceneObject::CSG(csg) => csg.intersect(ray, hitpool),
+            SceneObject::Sphere(s) => s.intersect(ray, hits),
+            SceneObject::Plane(p) => p.intersect(ray, hits),
+            SceneObject::Quadratic(q) => q.intersect(ray, hits),
+            SceneObject::PolyMesh(pm) => pm.intersect(ray, hits),
+            SceneObject::CSG(csg) => csg.intersect(ray, hits),
+            SceneObject::Instance(instance) => instance.intersect(ray, hits),
+        }
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        match self {
+            SceneObject::Sphere(s) => s.closest_hit(ray, t_min, t_max),
+            SceneObject::Plane(p) => p.closest_hit(ray, t_min, t_max),
+            SceneObject::Quadratic(q) => q.closest_hit(ray, t_min, t_max),
+            SceneObject::PolyMesh(pm) => pm.closest_hit(ray, t_min, t_max),
+            SceneObject::CSG(csg) => csg.closest_hit(ray, t_min, t_max),
+            SceneObject::Instance(instance) => instance.closest_hit(ray, t_min, t_max),
+        }
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        match self {
+            SceneObject::Sphere(s) => s.any_hit(ray, t_min, t_max),
+            SceneObject::Plane(p) => p.any_hit(ray, t_min, t_max),
+            SceneObject::Quadratic(q) => q.any_hit(ray, t_min, t_max),
+            SceneObject::PolyMesh(pm) => pm.any_hit(ray, t_min, t_max),
+            SceneObject::CSG(csg) => csg.any_hit(ray, t_min, t_max),
+            SceneObject::Instance(instance) => instance.any_hit(ray, t_min, t_max),
         }
     }
 }
@@ -77,18 +138,31 @@ impl Transformable for SceneObject {
             SceneObject::Quadratic(q) => q.transform(trans),
             SceneObject::PolyMesh(pm) => pm.transform(trans),
             SceneObject::CSG(csg) => csg.transform(trans),
+            SceneObject::Instance(instance) => instance.transform(trans),
         }
     }
 }
 
 impl Bounded for SceneObject {
+    fn bounding_box(&self) -> Option<AABB> {
+        match self {
+            SceneObject::Sphere(s) => s.bounding_box(),
+            SceneObject::Plane(p) => p.bounding_box(),
+            SceneObject::Quadratic(q) => q.bounding_box(),
+            SceneObject::PolyMesh(pm) => pm.bounding_box(),
+            SceneObject::CSG(csg) => csg.bounding_box(),
+            SceneObject::Instance(instance) => instance.bounding_box(),
+        }
+    }
+
     fn bounding_sphere(&self) -> Option<(Vertex, f32)> {
         match self {
             SceneObject::Sphere(s) => s.bounding_sphere(),
-            SceneObject::Plane(_) => None,
-            SceneObject::Quadratic(_) => None,
+            SceneObject::Plane(p) => p.bounding_sphere(),
+            SceneObject::Quadratic(q) => q.bounding_sphere(),
             SceneObject::PolyMesh(pm) => pm.bounding_sphere(),
-            SceneObject::CSG(_) => None,
+            SceneObject::CSG(csg) => csg.bounding_sphere(),
+            SceneObject::Instance(instance) => instance.bounding_sphere(),
         }
     }
 }
@@ -1,23 +1,97 @@
+use std::f32::consts::PI;
+
 use crate::{
-    geometry::traits::{Bounded, HitPool, Intersection, Transformable},
-    primitives::{ray::Ray, Hit, Transform, Vertex},
+    geometry::{
+        traits::{Bounded, Intersection, Transformable},
+        HitList,
+    },
+    primitives::{ray::Ray, Hit, Transform, Vector, Vertex, AABB},
     shading::Material,
 };
 
+/// Tolerance used when deciding whether a transform keeps a sphere round.
+const SIMILARITY_TOLERANCE: f32 = 1e-5;
+
+/// Linear map taking offsets from the centre of a sphere to offsets from the centre of the
+/// ellipsoid it has been deformed into.
+#[derive(Debug, Clone, Copy)]
+struct Axes {
+    matrix: Transform,
+    inverse: Transform,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct SphereGeometry {
     pub center: Vertex,
     pub radius: f32,
+    /// Set once a non-uniform scale or shear has turned the sphere into an ellipsoid.
+    axes: Option<Axes>,
 }
 
 impl SphereGeometry {
     pub fn new(center: Vertex, radius: f32) -> Self {
-        Self { center, radius }
+        Self {
+            center,
+            radius,
+            axes: None,
+        }
+    }
+
+    /// Whether the sphere is still round, rather than deformed into an ellipsoid.
+    pub fn is_round(&self) -> bool {
+        self.axes.is_none()
+    }
+
+    /// Triangles approximating the surface, for sampling points on it.
+    pub fn surface_triangles(&self) -> Vec<[Vertex; 3]> {
+        const RINGS: usize = 16;
+        const SEGMENTS: usize = 32;
+
+        let point = |ring: usize, segment: usize| {
+            let theta = PI * ring as f32 / RINGS as f32;
+            let phi = 2.0 * PI * segment as f32 / SEGMENTS as f32;
+            let mut offset = self.radius
+                * Vector::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+            if let Some(axes) = &self.axes {
+                axes.matrix.apply_to_vector(&mut offset);
+            }
+            self.center + offset
+        };
+
+        let mut triangles = Vec::with_capacity(2 * RINGS * SEGMENTS);
+        for ring in 0..RINGS {
+            for segment in 0..SEGMENTS {
+                let corners = [
+                    point(ring, segment),
+                    point(ring, segment + 1),
+                    point(ring + 1, segment + 1),
+                    point(ring + 1, segment),
+                ];
+                // The triangles touching the poles would be degenerate.
+                if ring > 0 {
+                    triangles.push([corners[0], corners[1], corners[2]]);
+                }
+                if ring < RINGS - 1 {
+                    triangles.push([corners[0], corners[2], corners[3]]);
+                }
+            }
+        }
+        triangles
     }
 
-    fn add_hit(&self, hitpool: &mut HitPool, ray: &Ray, t: f32, entering: bool) {
+    fn hit_at(&self, ray: &Ray, t: f32, entering: bool) -> Hit {
         let hit_position = ray.position + t * ray.direction;
         let mut hit_normal = hit_position.vector - self.center.vector;
+
+        if let Some(axes) = &self.axes {
+            // Normals are transformed by the inverse transpose to stay perpendicular to the surface.
+            axes.inverse.apply_to_vector(&mut hit_normal);
+            axes.inverse.transpose().apply_to_vector(&mut hit_normal);
+        }
         hit_normal = hit_normal.normalise();
 
         // Flip normal if pointing away from the surface we are looking at.
@@ -25,45 +99,184 @@ impl SphereGeometry {
             hit_normal = hit_normal.negate();
         }
 
-        hitpool.insert(Hit::new(t, entering, hit_position, hit_normal));
+        Hit::new(t, entering, hit_position, hit_normal)
     }
-}
 
-impl Intersection for SphereGeometry {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
-        let ray_to_sphere = ray.position.vector - self.center.vector;
+    /// Distances at which the ray enters and exits the sphere, if it hits it at all.
+    fn intersection_distances(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let mut ray_to_sphere = ray.position.vector - self.center.vector;
+        let mut direction = ray.direction;
+
+        // Intersect an ellipsoid as a sphere in the frame where it is round.
+        // Distances along the undeformed direction are `scale` times those along the ray.
+        let mut scale = 1.0;
+        if let Some(axes) = &self.axes {
+            axes.inverse.apply_to_vector(&mut ray_to_sphere);
+            axes.inverse.apply_to_vector(&mut direction);
+            scale = direction.length();
+            direction = direction / scale;
+        }
 
         // Quadratic equation.
-        let a = ray.direction.dot(ray.direction);
-        let b = 2.0 * ray.direction.dot(ray_to_sphere);
+        let a = direction.dot(direction);
+        let b = 2.0 * direction.dot(ray_to_sphere);
         let c = ray_to_sphere.dot(ray_to_sphere) - self.radius.powi(2);
 
         let discriminant = b.powi(2) - 4.0 * a * c;
 
         if discriminant < 0.0 {
             // No intersection if the discriminant is negative.
-            return;
+            return None;
         }
 
         let sqrt_discriminant = discriminant.sqrt();
         let t0 = (-b - sqrt_discriminant) / 2.0;
         let t1 = (-b + sqrt_discriminant) / 2.0;
 
-        self.add_hit(hitpool, ray, t0, true);
-        self.add_hit(hitpool, ray, t1, false);
+        Some((t0 / scale, t1 / scale))
+    }
+}
+
+impl Intersection for SphereGeometry {
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        if let Some((t0, t1)) = self.intersection_distances(ray) {
+            hits.insert(self.hit_at(ray, t0, true));
+            hits.insert(self.hit_at(ray, t1, false));
+        }
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        let (t0, t1) = self.intersection_distances(ray)?;
+
+        if t_min < t0 && t0 < t_max {
+            Some(self.hit_at(ray, t0, true))
+        } else if t_min < t1 && t1 < t_max {
+            Some(self.hit_at(ray, t1, false))
+        } else {
+            None
+        }
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.intersection_distances(ray)
+            .is_some_and(|(t0, t1)| (t_min < t0 && t0 < t_max) || (t_min < t1 && t1 < t_max))
     }
 }
 
 impl Transformable for SphereGeometry {
+    /// Moves the centre, and deforms the sphere by the rest of the transform.
+    ///
+    /// Rotations and uniform scales keep the sphere round and only change its radius. Anything
+    /// else turns it into an ellipsoid.
     fn transform(&mut self, trans: &Transform) {
         trans.apply_to_vertex(&mut self.center);
+
+        let mut linear = *trans;
+        for row in 0..3 {
+            linear.matrix[row][3] = 0.0;
+        }
+        linear = match &self.axes {
+            Some(axes) => linear * axes.matrix,
+            None => linear,
+        };
+
+        // The columns of a rotation scaled by `s` are orthogonal with length `s`.
+        let columns: [Vector; 3] = std::array::from_fn(|column| {
+            Vector::new(
+                linear.matrix[0][column],
+                linear.matrix[1][column],
+                linear.matrix[2][column],
+            )
+        });
+        let scale_squared = columns[0].len_sqr();
+        let tolerance = SIMILARITY_TOLERANCE * scale_squared;
+        let is_similarity = (0..3).all(|i| {
+            (columns[i].len_sqr() - scale_squared).abs() <= tolerance
+                && (0..i).all(|j| columns[i].dot(columns[j]).abs() <= tolerance)
+        });
+
+        if is_similarity {
+            // A sphere looks the same however it is rotated.
+            self.radius *= scale_squared.sqrt();
+            self.axes = None;
+        } else {
+            self.axes = Some(Axes {
+                matrix: linear,
+                inverse: linear.inverse(),
+            });
+        }
     }
 }
 
 impl Bounded for SphereGeometry {
+    fn bounding_box(&self) -> Option<AABB> {
+        let Some(axes) = &self.axes else {
+            return Some(AABB::from_sphere(self.center, self.radius));
+        };
+
+        // The extent of the ellipsoid along each world axis is the radius times the length of
+        // the matching row of the deformation.
+        let half_extent = |row: usize| {
+            self.radius
+                * Vector::new(
+                    axes.matrix.matrix[row][0],
+                    axes.matrix.matrix[row][1],
+                    axes.matrix.matrix[row][2],
+                )
+                .length()
+        };
+        let half_extent = Vector::new(half_extent(0), half_extent(1), half_extent(2));
+
+        Some(AABB::new(
+            self.center.vector - half_extent,
+            self.center.vector + half_extent,
+        ))
+    }
+
     fn bounding_sphere(&self) -> Option<(Vertex, f32)> {
-        Some((self.center, self.radius))
+        let Some(axes) = &self.axes else {
+            return Some((self.center, self.radius));
+        };
+
+        // The longest semi-axis is the radius stretched by the largest singular value of the
+        // deformation.
+        let stretch_squared = largest_eigenvalue(&(axes.matrix.transpose() * axes.matrix));
+
+        Some((self.center, self.radius * stretch_squared.sqrt()))
+    }
+}
+
+/// Largest eigenvalue of the symmetric upper-left 3x3 block of the matrix.
+///
+/// Uses the closed form for the eigenvalues of a symmetric 3x3 matrix.
+/// Oliver K. Smith. 1961. Eigenvalues of a symmetric 3 × 3 matrix.
+/// Commun. ACM 4, 4 (April 1961), 168. https://doi.org/10.1145/355578.366316
+fn largest_eigenvalue(symmetric: &Transform) -> f32 {
+    let m = &symmetric.matrix;
+
+    let off_diagonal = m[0][1].powi(2) + m[0][2].powi(2) + m[1][2].powi(2);
+    if off_diagonal == 0.0 {
+        return m[0][0].max(m[1][1]).max(m[2][2]);
     }
+
+    let mean = (m[0][0] + m[1][1] + m[2][2]) / 3.0;
+    let spread = (((m[0][0] - mean).powi(2)
+        + (m[1][1] - mean).powi(2)
+        + (m[2][2] - mean).powi(2)
+        + 2.0 * off_diagonal)
+        / 6.0)
+        .sqrt();
+
+    // Determinant of (m - mean * I) / spread.
+    let b = |row: usize, column: usize| {
+        (m[row][column] - if row == column { mean } else { 0.0 }) / spread
+    };
+    let determinant = b(0, 0) * (b(1, 1) * b(2, 2) - b(1, 2) * b(2, 1))
+        - b(0, 1) * (b(1, 0) * b(2, 2) - b(1, 2) * b(2, 0))
+        + b(0, 2) * (b(1, 0) * b(2, 1) - b(1, 1) * b(2, 0));
+    let angle = (determinant / 2.0).clamp(-1.0, 1.0).acos() / 3.0;
+
+    mean + 2.0 * spread * angle.cos()
 }
 
 #[derive(Debug)]
@@ -82,8 +295,16 @@ impl Sphere {
 }
 
 impl Intersection for Sphere {
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool) {
-        self.geometry.intersect(ray, hitpool)
+    fn intersect(&self, ray: &Ray, hits: &mut HitList) {
+        self.geometry.intersect(ray, hits)
+    }
+
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        self.geometry.closest_hit(ray, t_min, t_max)
+    }
+
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.geometry.any_hit(ray, t_min, t_max)
     }
 }
 
@@ -94,6 +315,10 @@ impl Transformable for Sphere {
 }
 
 impl Bounded for Sphere {
+    fn bounding_box(&self) -> Option<AABB> {
+        self.geometry.bounding_box()
+    }
+
     fn bounding_sphere(&self) -> Option<(Vertex, f32)> {
         self.geometry.bounding_sphere()
     }
@@ -112,9 +337,9 @@ mod tests {
 
         let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
 
-        let hitpool = sphere.generate_hitpool(&ray);
+        let hits = sphere.generate_hits(&ray);
         // 2 hits (entry and exit)
-        assert_eq!(hitpool.len(), 2);
+        assert_eq!(hits.len(), 2);
     }
 
     #[test]
@@ -124,8 +349,8 @@ mod tests {
 
         let ray = Ray::new(Vertex::new(5.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
 
-        let hitpool = sphere.generate_hitpool(&ray);
-        assert_eq!(hitpool.len(), 0);
+        let hits = sphere.generate_hits(&ray);
+        assert_eq!(hits.len(), 0);
     }
 
     #[test]
@@ -137,7 +362,7 @@ mod tests {
 
         let hit = sphere.first_hit(&ray).unwrap();
         assert_relative_eq!(hit.distance, 4.0, epsilon = 1e-5);
-        assert_eq!(hit.entering, true);
+        assert!(hit.entering);
     }
 
     #[test]
@@ -162,6 +387,15 @@ mod tests {
         assert_eq!(bs_radius, 5.0);
     }
 
+    #[test]
+    fn test_sphere_bounding_box() {
+        let sphere = SphereGeometry::new(Vertex::new(1.0, 2.0, 3.0, 1.0), 2.0);
+
+        let bounds = sphere.bounding_box().unwrap();
+        assert_eq!(bounds.min, Vector::new(-1.0, 0.0, 1.0));
+        assert_eq!(bounds.max, Vector::new(3.0, 4.0, 5.0));
+    }
+
     #[test]
     fn test_sphere_transform() {
         let center = Vertex::new(0.0, 0.0, 0.0, 1.0);
@@ -184,9 +418,9 @@ mod tests {
         // Ray tangent to sphere
         let ray = Ray::new(Vertex::new(1.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
 
-        let hitpool = sphere.generate_hitpool(&ray);
+        let hits = sphere.generate_hits(&ray);
         // Tangent hit may have discriminant = 0
-        assert!(hitpool.len() <= 2);
+        assert!(hits.len() <= 2);
     }
 
     #[test]
@@ -209,4 +443,124 @@ mod tests {
             assert_relative_eq!(h.distance, 2.0, epsilon = 1e-5);
         }
     }
+
+    #[test]
+    fn test_sphere_any_hit() {
+        let sphere = SphereGeometry::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        // Hits at distances 4 and 6.
+        assert!(sphere.any_hit(&ray, 0.0, f32::INFINITY));
+        assert!(sphere.any_hit(&ray, 0.0, 5.0));
+        assert!(sphere.any_hit(&ray, 5.0, 10.0));
+        assert!(!sphere.any_hit(&ray, 0.0, 3.0));
+        assert!(!sphere.any_hit(&ray, 7.0, 10.0));
+    }
+
+    fn scale(x: f32, y: f32, z: f32) -> Transform {
+        Transform::new([
+            [x, 0.0, 0.0, 0.0],
+            [0.0, y, 0.0, 0.0],
+            [0.0, 0.0, z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    #[test]
+    fn test_sphere_uniform_scale() {
+        let mut sphere = SphereGeometry::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        sphere.transform(&scale(2.0, 2.0, 2.0));
+
+        assert_relative_eq!(sphere.radius, 2.0, epsilon = 1e-5);
+
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = sphere.first_hit(&ray).unwrap();
+        assert_relative_eq!(hit.distance, 3.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_sphere_non_uniform_scale() {
+        let mut sphere = SphereGeometry::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        sphere.transform(&scale(3.0, 1.0, 2.0));
+
+        // Semi-axes of 3, 1 and 2 along x, y and z.
+        for (direction, expected) in [
+            (Vector::new(1.0, 0.0, 0.0), 3.0),
+            (Vector::new(0.0, 1.0, 0.0), 1.0),
+            (Vector::new(0.0, 0.0, 1.0), 2.0),
+        ] {
+            let origin = -10.0 * direction;
+            let ray = Ray::new(Vertex::new(origin.x, origin.y, origin.z, 1.0), direction);
+
+            let hit = sphere.first_hit(&ray).unwrap();
+            assert_relative_eq!(hit.distance, 10.0 - expected, epsilon = 1e-4);
+            assert_relative_eq!(hit.normal.dot(direction), -1.0, epsilon = 1e-4);
+        }
+
+        // Normal on the ellipsoid x^2 / 9 + y^2 = 1 at (3 / sqrt(2), 1 / sqrt(2), 0).
+        let ray = Ray::new(
+            Vertex::new(3.0 / 2.0_f32.sqrt(), 10.0, 0.0, 1.0),
+            Vector::new(0.0, -1.0, 0.0),
+        );
+        let hit = sphere.first_hit(&ray).unwrap();
+        let expected = Vector::new(1.0 / 3.0, 1.0, 0.0).normalise();
+        assert_relative_eq!(hit.normal.x, expected.x, epsilon = 1e-4);
+        assert_relative_eq!(hit.normal.y, expected.y, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_ellipsoid_bounds() {
+        let mut sphere = SphereGeometry::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        sphere.transform(&scale(3.0, 1.0, 2.0));
+
+        // Rotate 90 degrees about z and move.
+        sphere.transform(&Transform::new([
+            [0.0, -1.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]));
+
+        let bounds = sphere.bounding_box().unwrap();
+        assert_relative_eq!(bounds.min.x, 0.0, epsilon = 1e-5);
+        assert_relative_eq!(bounds.max.x, 2.0, epsilon = 1e-5);
+        assert_relative_eq!(bounds.min.y, -3.0, epsilon = 1e-5);
+        assert_relative_eq!(bounds.max.y, 3.0, epsilon = 1e-5);
+        assert_relative_eq!(bounds.max.z, 2.0, epsilon = 1e-5);
+
+        let (center, radius) = sphere.bounding_sphere().unwrap();
+        assert_relative_eq!(center.vector.x, 1.0, epsilon = 1e-5);
+        assert_relative_eq!(radius, 3.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_ellipsoid_bounding_sphere_with_shear() {
+        let mut sphere = SphereGeometry::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        sphere.transform(&Transform::new([
+            [1.0, 1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]));
+
+        // Every point on the sheared sphere lies inside its bounding sphere.
+        let (center, radius) = sphere.bounding_sphere().unwrap();
+        for i in 0..64 {
+            let angle = i as f32 * std::f32::consts::TAU / 64.0;
+            let point = Vector::new(angle.cos() + angle.sin(), angle.sin(), 0.0);
+            assert!((point - center.vector).length() <= radius + 1e-4);
+        }
+        // The largest singular value of [[1, 1], [0, 1]] is the golden ratio.
+        assert_relative_eq!(radius, (1.0 + 5.0_f32.sqrt()) / 2.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_ellipsoid_scaled_back_is_a_sphere() {
+        let mut sphere = SphereGeometry::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        sphere.transform(&scale(2.0, 1.0, 1.0));
+        sphere.transform(&scale(0.5, 1.0, 1.0));
+
+        assert!(sphere.axes.is_none());
+        assert_relative_eq!(sphere.radius, 1.0, epsilon = 1e-5);
+    }
 }
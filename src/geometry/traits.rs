@@ -1,38 +1,41 @@
-use sortedlist_rs::SortedList;
+use crate::primitives::{ray::Ray, Hit, Transform, Vertex, AABB};
 
-use crate::primitives::{ray::Ray, Hit, Transform, Vertex};
-
-/// Sortedlist uses a list of sorted sublists to store elements.
-/// It has three internal lists:
-/// - `lists`: This is a list of sorted sublists. Each sublist contains a portion of the elements in sorted order.
-///   This allows for insertion and deletion by operating on smaller sublists rather than a single large list.
-/// - `maxes`: This list contains the maximum element of each sublist in `lists`.
-///   It is used for binary search to locate the sublist that may contain a specific element.
-/// - `index`: This is a tree of pair-wise sums of the lengths of the sublists in `lists`.
-///   It is used for indexing, allowing quick computation of the overall position of an element within the entire sortedlist.
-pub type HitPool = SortedList<Hit>;
+use super::HitList;
 
 /// Intersection trait that is focused on ray-object intersection calculations only.
 pub trait Intersection {
-    /// Computes and stores the intersections of a ray with this object.
-    fn intersect(&self, ray: &Ray, hitpool: &mut HitPool);
+    /// Computes and stores all the intersections of a ray with this object.
+    fn intersect(&self, ray: &Ray, hits: &mut HitList);
+
+    fn generate_hits(&self, ray: &Ray) -> HitList {
+        let mut hits = HitList::new();
+        self.intersect(ray, &mut hits);
+        hits
+    }
 
-    fn generate_hitpool(&self, ray: &Ray) -> HitPool {
-        let mut hitpool = SortedList::new();
-        self.intersect(ray, &mut hitpool);
-        hitpool
+    /// Selects the closest hit with a distance strictly between `t_min` and `t_max`.
+    ///
+    /// Callers searching several objects pass the distance of the closest hit found so far as
+    /// `t_max`, so implementors can skip work for anything further away. The default computes
+    /// every intersection.
+    fn closest_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<Hit> {
+        self.generate_hits(ray)
+            .iter()
+            .find(|hit| t_min < hit.distance && hit.distance < t_max)
+            .copied()
     }
 
-    /// Selects the first hit (with positive distance) from the hitpool.
+    /// Selects the first hit (with positive distance).
     fn first_hit(&self, ray: &Ray) -> Option<Hit> {
-        let mut hitpool = self.generate_hitpool(ray);
-        if let Some(index) = hitpool.flatten().iter().position(|&hit| hit.distance > 0.0) {
-            let hit = hitpool.remove(index);
-            hitpool.clear();
-            Some(hit)
-        } else {
-            None
-        }
+        self.closest_hit(ray, 0.0, f32::INFINITY)
+    }
+
+    /// Whether the ray hits this object at any distance strictly between `t_min` and `t_max`.
+    ///
+    /// Used for occlusion queries, where only the existence of a hit matters. Implementors
+    /// should override it to return as soon as a hit is found.
+    fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.closest_hit(ray, t_min, t_max).is_some()
     }
 }
 
@@ -44,6 +47,18 @@ pub trait Transformable {
 
 /// Bounded trait for objects with bounding volumes.
 pub trait Bounded {
+    /// Returns the axis-aligned bounding box, or `None` if the object is unbounded.
+    fn bounding_box(&self) -> Option<AABB>;
+
     /// Returns the bounding sphere (center, radius) if available.
-    fn bounding_sphere(&self) -> Option<(Vertex, f32)>;
+    /// Defaults to the sphere around the bounding box.
+    fn bounding_sphere(&self) -> Option<(Vertex, f32)> {
+        let bounds = self.bounding_box().filter(|bounds| !bounds.is_empty())?;
+        let center = bounds.centroid();
+
+        Some((
+            Vertex::new(center.x, center.y, center.z, 1.0),
+            bounds.extent().length() / 2.0,
+        ))
+    }
 }
@@ -0,0 +1,291 @@
+use super::{ray::Ray, Transform, Vector, Vertex};
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug)]
+pub struct AABB {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl AABB {
+    pub fn new(min: Vector, max: Vector) -> Self {
+        Self { min, max }
+    }
+
+    /// A box that contains nothing. Growing it by any point or box gives that point or box.
+    pub fn empty() -> Self {
+        Self::new(
+            Vector::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            Vector::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        )
+    }
+
+    /// Smallest box enclosing a bounding sphere.
+    pub fn from_sphere(center: Vertex, radius: f32) -> Self {
+        let extent = Vector::new(radius, radius, radius);
+        Self::new(center.vector - extent, center.vector + extent)
+    }
+
+    /// Smallest box enclosing all the given points.
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vector>) -> Self {
+        points
+            .into_iter()
+            .fold(Self::empty(), |bounds, point| bounds.grow(*point))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// Returns the box extended to contain the point.
+    pub fn grow(&self, point: Vector) -> Self {
+        Self::new(
+            Vector::new(
+                self.min.x.min(point.x),
+                self.min.y.min(point.y),
+                self.min.z.min(point.z),
+            ),
+            Vector::new(
+                self.max.x.max(point.x),
+                self.max.y.max(point.y),
+                self.max.z.max(point.z),
+            ),
+        )
+    }
+
+    /// Smallest box containing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        self.grow(other.min).grow(other.max)
+    }
+
+    /// Overlap of both boxes, which is empty if they do not overlap.
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::new(
+            Vector::new(
+                self.min.x.max(other.min.x),
+                self.min.y.max(other.min.y),
+                self.min.z.max(other.min.z),
+            ),
+            Vector::new(
+                self.max.x.min(other.max.x),
+                self.max.y.min(other.max.y),
+                self.max.z.min(other.max.z),
+            ),
+        )
+    }
+
+    /// Whether the point lies inside the box, allowing for a small tolerance.
+    pub fn contains(&self, point: Vector, tolerance: f32) -> bool {
+        (0..3).all(|axis| {
+            self.min[axis] - tolerance <= point[axis] && point[axis] <= self.max[axis] + tolerance
+        })
+    }
+
+    /// Box enclosing the transformed corners of this box.
+    pub fn transform(&self, trans: &Transform) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let mut bounds = Self::empty();
+        for corner in 0..8 {
+            let mut vertex = Vertex::new(
+                if corner & 1 == 0 {
+                    self.min.x
+                } else {
+                    self.max.x
+                },
+                if corner & 2 == 0 {
+                    self.min.y
+                } else {
+                    self.max.y
+                },
+                if corner & 4 == 0 {
+                    self.min.z
+                } else {
+                    self.max.z
+                },
+                1.0,
+            );
+            trans.apply_to_vertex(&mut vertex);
+            bounds = bounds.grow(vertex.vector);
+        }
+
+        bounds
+    }
+
+    pub fn centroid(&self) -> Vector {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn extent(&self) -> Vector {
+        self.max - self.min
+    }
+
+    /// Index of the axis along which the box is largest (0 = x, 1 = y, 2 = z).
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.extent();
+        if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Ray-box intersection using the slab method.
+    ///
+    /// Returns the distance at which the ray enters the box, clamped to `min_distance` when the
+    /// box starts before it, or `None` if the box is missed within
+    /// `[min_distance, max_distance]`. A `min_distance` of negative infinity finds boxes behind
+    /// the ray's origin too.
+    pub fn intersect(&self, ray: &Ray, min_distance: f32, max_distance: f32) -> Option<f32> {
+        let mut t_near = min_distance;
+        let mut t_far = max_distance;
+
+        for axis in 0..3 {
+            let inverse_direction = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.position.vector[axis]) * inverse_direction;
+            let mut t1 = (self.max[axis] - ray.position.vector[axis]) * inverse_direction;
+
+            if inverse_direction < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // `max`/`min` ignore the NaN produced when the ray lies exactly on a slab boundary.
+            t_near = t_near.max(t0);
+            t_far = t_far.min(t1);
+
+            if t_near > t_far {
+                return None;
+            }
+        }
+
+        Some(t_near)
+    }
+}
+
+impl Default for AABB {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn unit_box() -> AABB {
+        AABB::new(Vector::new(-1.0, -1.0, -1.0), Vector::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_aabb_ray_hit() {
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        let distance = unit_box().intersect(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_relative_eq!(distance, 4.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_aabb_ray_miss() {
+        let ray = Ray::new(Vertex::new(5.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(unit_box().intersect(&ray, 0.0, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_aabb_ray_beyond_max_distance() {
+        let ray = Ray::new(Vertex::new(0.0, 0.0, -5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(unit_box().intersect(&ray, 0.0, 3.0).is_none());
+    }
+
+    #[test]
+    fn test_aabb_ray_starting_inside() {
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(1.0, 0.0, 0.0));
+
+        assert_eq!(unit_box().intersect(&ray, 0.0, f32::INFINITY), Some(0.0));
+    }
+
+    #[test]
+    fn test_aabb_ray_behind_origin() {
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 5.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(unit_box().intersect(&ray, 0.0, f32::INFINITY).is_none());
+        assert_eq!(
+            unit_box().intersect(&ray, f32::NEG_INFINITY, f32::INFINITY),
+            Some(-6.0)
+        );
+    }
+
+    #[test]
+    fn test_aabb_flat_box() {
+        // Boxes around axis-aligned triangles have no thickness.
+        let flat = AABB::new(Vector::new(0.0, 0.0, 0.0), Vector::new(1.0, 1.0, 0.0));
+        let ray = Ray::new(Vertex::new(0.5, 0.5, -1.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(flat.intersect(&ray, 0.0, f32::INFINITY).is_some());
+    }
+
+    #[test]
+    fn test_aabb_union_and_centroid() {
+        let a = AABB::new(Vector::new(0.0, 0.0, 0.0), Vector::new(1.0, 1.0, 1.0));
+        let b = AABB::new(Vector::new(2.0, -1.0, 0.0), Vector::new(3.0, 0.0, 4.0));
+
+        let union = a.union(&b);
+        assert_eq!(union.min, Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(union.max, Vector::new(3.0, 1.0, 4.0));
+        assert_eq!(union.centroid(), Vector::new(1.5, 0.0, 2.0));
+        assert_eq!(union.longest_axis(), 2);
+    }
+
+    #[test]
+    fn test_aabb_intersection() {
+        let a = AABB::new(Vector::new(0.0, 0.0, 0.0), Vector::new(2.0, 2.0, 2.0));
+        let b = AABB::new(Vector::new(1.0, 1.0, 1.0), Vector::new(3.0, 3.0, 3.0));
+        let c = AABB::new(Vector::new(5.0, 5.0, 5.0), Vector::new(6.0, 6.0, 6.0));
+
+        let overlap = a.intersection(&b);
+        assert_eq!(overlap.min, Vector::new(1.0, 1.0, 1.0));
+        assert_eq!(overlap.max, Vector::new(2.0, 2.0, 2.0));
+        assert!(a.intersection(&c).is_empty());
+    }
+
+    #[test]
+    fn test_aabb_contains() {
+        let bounds = unit_box();
+
+        assert!(bounds.contains(Vector::new(0.5, -0.5, 1.0), 0.0));
+        assert!(!bounds.contains(Vector::new(0.0, 0.0, 1.1), 0.0));
+        assert!(bounds.contains(Vector::new(0.0, 0.0, 1.1), 0.2));
+    }
+
+    #[test]
+    fn test_aabb_transform() {
+        let translate_and_scale = Transform::new([
+            [2.0, 0.0, 0.0, 5.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        let bounds = unit_box().transform(&translate_and_scale);
+        assert_eq!(bounds.min, Vector::new(3.0, -1.0, -1.0));
+        assert_eq!(bounds.max, Vector::new(7.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_aabb_empty() {
+        let empty = AABB::empty();
+        assert!(empty.is_empty());
+
+        let point = Vector::new(1.0, 2.0, 3.0);
+        let grown = empty.grow(point);
+        assert!(!grown.is_empty());
+        assert_eq!(grown.min, point);
+        assert_eq!(grown.max, point);
+    }
+}
@@ -0,0 +1,653 @@
+use serde::Deserialize;
+
+use super::{Transform, Vector, AABB};
+
+/// How values are interpolated between keyframes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Straight lines between keyframes.
+    #[default]
+    Linear,
+    /// A smooth curve through the keyframes, with the tangent at each keyframe following its
+    /// neighbours (Catmull-Rom).
+    Spline,
+}
+
+impl Interpolation {
+    /// Value a fraction `t` of the way between the keyframes at indices 1 and 2, given with
+    /// their neighbours on either side. At the ends of a sequence the neighbour is the keyframe
+    /// itself.
+    pub fn blend(self, times: [f32; 4], values: [f32; 4], t: f32) -> f32 {
+        let [_, start, end, _] = values;
+        match self {
+            Interpolation::Linear => start + t * (end - start),
+            Interpolation::Spline => {
+                let (start_tangent, end_tangent) = Self::tangents(times, values);
+
+                let (t2, t3) = (t * t, t * t * t);
+                (2.0 * t3 - 3.0 * t2 + 1.0) * start
+                    + (t3 - 2.0 * t2 + t) * start_tangent
+                    + (-2.0 * t3 + 3.0 * t2) * end
+                    + (t3 - t2) * end_tangent
+            }
+        }
+    }
+
+    /// Smallest and largest value between the keyframes at indices 1 and 2, including any
+    /// overshoot of the spline.
+    pub fn range(self, times: [f32; 4], values: [f32; 4]) -> (f32, f32) {
+        let [_, start, end, _] = values;
+        let mut range = (start.min(end), start.max(end));
+
+        if self == Interpolation::Spline {
+            // The curve turns where the derivative 3at^2 + 2bt + c of the cubic is zero.
+            let (start_tangent, end_tangent) = Self::tangents(times, values);
+            let a = 2.0 * start + start_tangent - 2.0 * end + end_tangent;
+            let b = -3.0 * start - 2.0 * start_tangent + 3.0 * end - end_tangent;
+            let c = start_tangent;
+
+            let turns = if a.abs() < 1e-6 {
+                [-c / (2.0 * b), f32::NAN]
+            } else {
+                let root = (b * b - 3.0 * a * c).sqrt();
+                [(-b - root) / (3.0 * a), (-b + root) / (3.0 * a)]
+            };
+            for t in turns.into_iter().filter(|t| 0.0 < *t && *t < 1.0) {
+                let value = self.blend(times, values, t);
+                range = (range.0.min(value), range.1.max(value));
+            }
+        }
+        range
+    }
+
+    /// Tangents of the spline at the keyframes at indices 1 and 2, scaled to the length of the
+    /// segment so that unevenly spaced keyframes don't overshoot.
+    fn tangents(times: [f32; 4], values: [f32; 4]) -> (f32, f32) {
+        let duration = times[2] - times[1];
+        let tangent = |before: usize, after: usize| {
+            (values[after] - values[before]) / (times[after] - times[before]) * duration
+        };
+        (tangent(0, 2), tangent(1, 3))
+    }
+}
+
+/// A transform that changes over time, given by keyframes.
+///
+/// Between keyframes, each transform is split into a translation, a rotation and a scale
+/// (which may include shear) that are interpolated separately, so that rotating objects keep
+/// their shape. Before the first and after the last keyframe, the transform holds still.
+#[derive(Clone, Debug)]
+pub struct AnimatedTransform {
+    /// Sorted by time.
+    keyframes: Vec<Keyframe>,
+    interpolation: Interpolation,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Keyframe {
+    time: f32,
+    transform: Transform,
+    inverse: Transform,
+    decomposed: Decomposed,
+}
+
+impl Keyframe {
+    fn new(time: f32, transform: Transform) -> Self {
+        Self {
+            time,
+            transform,
+            inverse: transform.inverse(),
+            decomposed: Decomposed::new(&transform),
+        }
+    }
+}
+
+impl AnimatedTransform {
+    /// A transform that doesn't change.
+    pub fn fixed(transform: Transform) -> Self {
+        Self {
+            keyframes: vec![Keyframe::new(0.0, transform)],
+            interpolation: Interpolation::default(),
+        }
+    }
+
+    /// Moves from one transform to another over the given time interval.
+    pub fn between(start_time: f32, start: Transform, end_time: f32, end: Transform) -> Self {
+        Self::from_keyframes(vec![(start_time, start), (end_time, end)])
+    }
+
+    /// Takes keyframes in any order. Without any, the transform is the identity.
+    pub fn from_keyframes(mut keyframes: Vec<(f32, Transform)>) -> Self {
+        if keyframes.is_empty() {
+            return Self::fixed(Transform::identity());
+        }
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            keyframes: keyframes
+                .into_iter()
+                .map(|(time, transform)| Keyframe::new(time, transform))
+                .collect(),
+            interpolation: Interpolation::default(),
+        }
+    }
+
+    /// Follows a spline through the keyframes instead of straight lines. Rotations are always
+    /// interpolated along the shortest arc.
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.keyframes.len() > 1
+    }
+
+    /// Whether the transform changes at any point between the two times.
+    pub fn moves_between(&self, start: f32, end: f32) -> bool {
+        let first = self.keyframes[0].time;
+        let last = self.keyframes[self.keyframes.len() - 1].time;
+        self.is_animated() && end > first && start < last
+    }
+
+    /// The transform and its inverse at the given time.
+    pub fn at(&self, time: f32) -> (Transform, Transform) {
+        let first = &self.keyframes[0];
+        let last = &self.keyframes[self.keyframes.len() - 1];
+
+        if time <= first.time {
+            return (first.transform, first.inverse);
+        }
+        if time >= last.time {
+            return (last.transform, last.inverse);
+        }
+
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        let keys = self.segment(next);
+        let t = (time - keys[1].time) / (keys[2].time - keys[1].time);
+
+        let transform = Decomposed::blend(keys, self.interpolation, t).compose();
+        (transform, transform.inverse())
+    }
+
+    /// Applies another transform after this one, at every keyframe.
+    pub fn then(&mut self, trans: &Transform) {
+        for keyframe in &mut self.keyframes {
+            *keyframe = Keyframe::new(keyframe.time, *trans * keyframe.transform);
+        }
+    }
+
+    /// Box containing the given object space bounds throughout the motion.
+    pub fn bounding_box(&self, bounds: &AABB) -> AABB {
+        (1..self.keyframes.len()).fold(
+            bounds.transform(&self.keyframes[0].transform),
+            |result, next| result.union(&self.segment_bounds(next, bounds)),
+        )
+    }
+
+    /// The keyframes either side of the segment ending at keyframe `next`, with their
+    /// neighbours.
+    fn segment(&self, next: usize) -> [&Keyframe; 4] {
+        [
+            &self.keyframes[(next - 1).saturating_sub(1)],
+            &self.keyframes[next - 1],
+            &self.keyframes[next],
+            &self.keyframes[(next + 1).min(self.keyframes.len() - 1)],
+        ]
+    }
+
+    /// Box containing the bounds over the segment ending at keyframe `next`.
+    ///
+    /// The scale and translation are bounded exactly from the range of each of their entries.
+    /// The rotation is sampled, and as points swing along arcs that bulge out of the box between
+    /// the samples, the box is padded by the furthest an arc strays from its chord.
+    fn segment_bounds(&self, next: usize, bounds: &AABB) -> AABB {
+        const MAX_STEP_ANGLE: f32 = std::f32::consts::PI / 90.0;
+
+        let keys = self.segment(next);
+        let times = keys.map(|key| key.time);
+        let range = |value: &dyn Fn(&Decomposed) -> f32| {
+            self.interpolation
+                .range(times, keys.map(|key| value(&key.decomposed)))
+        };
+
+        let (mut min, mut max) = ([0.0; 3], [0.0; 3]);
+        for i in 0..3 {
+            for j in 0..3 {
+                let (low, high) = range(&|d| d.scale[i][j]);
+                let products = [
+                    low * bounds.min[j],
+                    low * bounds.max[j],
+                    high * bounds.min[j],
+                    high * bounds.max[j],
+                ];
+                min[i] += products.into_iter().fold(f32::INFINITY, f32::min);
+                max[i] += products.into_iter().fold(f32::NEG_INFINITY, f32::max);
+            }
+        }
+        let scaled = AABB::new(
+            Vector::new(min[0], min[1], min[2]),
+            Vector::new(max[0], max[1], max[2]),
+        );
+
+        let [_, start, end, _] = keys.map(|key| &key.decomposed);
+        let cos_half_angle = start.rotation.dot(&end.rotation).abs().min(1.0);
+        let angle = 2.0 * cos_half_angle.acos();
+        let steps = (angle / MAX_STEP_ANGLE).ceil().max(1.0);
+
+        let radius = (0..3)
+            .map(|axis| min[axis].abs().max(max[axis].abs()).powi(2))
+            .sum::<f32>()
+            .sqrt();
+        let padding = radius * (1.0 - (0.5 * angle / steps).cos());
+
+        let mut rotated = AABB::empty();
+        for step in 0..=steps as usize {
+            let rotation = Decomposed {
+                translation: Vector::new(0.0, 0.0, 0.0),
+                rotation: start.rotation.slerp(&end.rotation, step as f32 / steps),
+                scale: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            };
+            rotated = rotated.union(&scaled.transform(&rotation.compose()));
+        }
+
+        let (x, y, z) = (
+            range(&|d| d.translation.x),
+            range(&|d| d.translation.y),
+            range(&|d| d.translation.z),
+        );
+        let padding = Vector::new(padding, padding, padding);
+        AABB::new(
+            rotated.min - padding + Vector::new(x.0, y.0, z.0),
+            rotated.max + padding + Vector::new(x.1, y.1, z.1),
+        )
+    }
+}
+
+/// A transform split into translation, rotation and scale, applied in reverse order.
+#[derive(Clone, Copy, Debug)]
+struct Decomposed {
+    translation: Vector,
+    rotation: Quaternion,
+    scale: Matrix3,
+}
+
+type Matrix3 = [[f32; 3]; 3];
+
+impl Decomposed {
+    /// Polar decomposition of the upper 3x3 of the matrix into a rotation and a scale, by
+    /// repeatedly averaging the rotation with its inverse transpose.
+    ///
+    /// Shoemake, K. and Duff, T. (1992) Matrix Animation and Polar Decomposition.
+    /// Proceedings of Graphics Interface '92, 258-264.
+    fn new(transform: &Transform) -> Self {
+        let m = transform.matrix;
+        let translation = Vector::new(m[0][3], m[1][3], m[2][3]);
+
+        let linear: Matrix3 = std::array::from_fn(|i| std::array::from_fn(|j| m[i][j]));
+        let mut rotation = linear;
+        for _ in 0..100 {
+            let inverse_transpose = transpose3(&inverse3(&rotation));
+            let next: Matrix3 = std::array::from_fn(|i| {
+                std::array::from_fn(|j| 0.5 * (rotation[i][j] + inverse_transpose[i][j]))
+            });
+
+            let change: f32 = (0..3)
+                .flat_map(|i| (0..3).map(move |j| (i, j)))
+                .map(|(i, j)| (next[i][j] - rotation[i][j]).abs())
+                .sum();
+            rotation = next;
+            if change < 1e-6 {
+                break;
+            }
+        }
+
+        // A mirroring is kept in the scale, so the rotation is a proper one.
+        if determinant3(&rotation) < 0.0 {
+            rotation = rotation.map(|row| row.map(|value| -value));
+        }
+        let scale = multiply3(&transpose3(&rotation), &linear);
+
+        Self {
+            translation,
+            rotation: Quaternion::from_matrix(&rotation),
+            scale,
+        }
+    }
+
+    /// Interpolates between the middle two of four consecutive keyframes.
+    fn blend(keys: [&Keyframe; 4], interpolation: Interpolation, t: f32) -> Self {
+        let times = keys.map(|key| key.time);
+        let component = |value: fn(&Decomposed) -> f32| {
+            interpolation.blend(times, keys.map(|key| value(&key.decomposed)), t)
+        };
+
+        let [_, start, end, _] = keys.map(|key| &key.decomposed);
+        Self {
+            translation: Vector::new(
+                component(|d| d.translation.x),
+                component(|d| d.translation.y),
+                component(|d| d.translation.z),
+            ),
+            rotation: start.rotation.slerp(&end.rotation, t),
+            scale: std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    interpolation.blend(times, keys.map(|key| key.decomposed.scale[i][j]), t)
+                })
+            }),
+        }
+    }
+
+    fn compose(&self) -> Transform {
+        let linear = multiply3(&self.rotation.to_matrix(), &self.scale);
+        let t = self.translation;
+
+        Transform::new([
+            [linear[0][0], linear[0][1], linear[0][2], t.x],
+            [linear[1][0], linear[1][1], linear[1][2], t.y],
+            [linear[2][0], linear[2][1], linear[2][2], t.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Quaternion {
+    x: f32,
+    y: f32,
+    z: f32,
+    w: f32,
+}
+
+impl Quaternion {
+    /// Shoemake, K. (1985) Animating Rotation with Quaternion Curves.
+    /// SIGGRAPH Computer Graphics, 19(3), 245-254.
+    fn from_matrix(m: &Matrix3) -> Self {
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion {
+                w: 0.25 * s,
+                x: (m[2][1] - m[1][2]) / s,
+                y: (m[0][2] - m[2][0]) / s,
+                z: (m[1][0] - m[0][1]) / s,
+            }
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            Quaternion {
+                w: (m[2][1] - m[1][2]) / s,
+                x: 0.25 * s,
+                y: (m[0][1] + m[1][0]) / s,
+                z: (m[0][2] + m[2][0]) / s,
+            }
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            Quaternion {
+                w: (m[0][2] - m[2][0]) / s,
+                x: (m[0][1] + m[1][0]) / s,
+                y: 0.25 * s,
+                z: (m[1][2] + m[2][1]) / s,
+            }
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            Quaternion {
+                w: (m[1][0] - m[0][1]) / s,
+                x: (m[0][2] + m[2][0]) / s,
+                y: (m[1][2] + m[2][1]) / s,
+                z: 0.25 * s,
+            }
+        };
+
+        q.normalise()
+    }
+
+    fn to_matrix(self) -> Matrix3 {
+        let Quaternion { x, y, z, w } = self;
+
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+
+    fn dot(&self, other: &Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    fn scaled(&self, s: f32) -> Self {
+        Quaternion {
+            x: self.x * s,
+            y: self.y * s,
+            z: self.z * s,
+            w: self.w * s,
+        }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Quaternion {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+            w: self.w + other.w,
+        }
+    }
+
+    fn normalise(&self) -> Self {
+        self.scaled(1.0 / self.dot(self).sqrt())
+    }
+
+    /// Spherical linear interpolation along the shorter arc.
+    fn slerp(&self, other: &Self, t: f32) -> Self {
+        let mut cos_theta = self.dot(other);
+        let mut other = *other;
+        if cos_theta < 0.0 {
+            other = other.scaled(-1.0);
+            cos_theta = -cos_theta;
+        }
+
+        // Nearly the same rotation, where the arc is too short to divide by its sine.
+        if cos_theta > 0.9995 {
+            return self.scaled(1.0 - t).add(&other.scaled(t)).normalise();
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        self.scaled(((1.0 - t) * theta).sin() / sin_theta)
+            .add(&other.scaled((t * theta).sin() / sin_theta))
+    }
+}
+
+fn multiply3(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn transpose3(m: &Matrix3) -> Matrix3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| m[j][i]))
+}
+
+fn determinant3(m: &Matrix3) -> f32 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+fn inverse3(m: &Matrix3) -> Matrix3 {
+    let inverse_determinant = 1.0 / determinant3(m);
+
+    // Transposed matrix of cofactors, divided by the determinant.
+    std::array::from_fn(|i| {
+        std::array::from_fn(|j| {
+            let (r0, r1) = ((j + 1) % 3, (j + 2) % 3);
+            let (c0, c1) = ((i + 1) % 3, (i + 2) % 3);
+            (m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]) * inverse_determinant
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::Vertex;
+    use approx::assert_relative_eq;
+
+    fn translation(x: f32, y: f32, z: f32) -> Transform {
+        Transform::new([
+            [1.0, 0.0, 0.0, x],
+            [0.0, 1.0, 0.0, y],
+            [0.0, 0.0, 1.0, z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    fn rotation_z(degrees: f32) -> Transform {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Transform::new([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    fn assert_transforms_equal(a: &Transform, b: &Transform) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_relative_eq!(a.matrix[i][j], b.matrix[i][j], epsilon = 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_animated_transform_keyframes_are_exact() {
+        let scale_and_rotate = rotation_z(30.0)
+            * Transform::new([
+                [2.0, 0.5, 0.0, 1.0],
+                [0.0, 3.0, 0.0, 2.0],
+                [0.0, 0.0, 0.5, 3.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]);
+
+        let decomposed = Decomposed::new(&scale_and_rotate);
+        assert_transforms_equal(&decomposed.compose(), &scale_and_rotate);
+    }
+
+    #[test]
+    fn test_animated_transform_interpolates_translation() {
+        let motion = AnimatedTransform::between(
+            0.0,
+            translation(0.0, 0.0, 0.0),
+            2.0,
+            translation(4.0, 0.0, 0.0),
+        );
+        assert!(motion.is_animated());
+
+        let (transform, inverse) = motion.at(0.5);
+        assert_transforms_equal(&transform, &translation(1.0, 0.0, 0.0));
+        assert_transforms_equal(&inverse, &translation(-1.0, 0.0, 0.0));
+
+        // Holds still outside of the keyframes.
+        assert_transforms_equal(&motion.at(-1.0).0, &translation(0.0, 0.0, 0.0));
+        assert_transforms_equal(&motion.at(3.0).0, &translation(4.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_animated_transform_interpolates_rotation() {
+        let motion = AnimatedTransform::from_keyframes(vec![
+            (1.0, rotation_z(90.0)),
+            (0.0, rotation_z(0.0)),
+        ]);
+
+        // Halfway through a rotation keeps the shape instead of shrinking it.
+        let mut point = Vertex::new(1.0, 0.0, 0.0, 1.0);
+        motion.at(0.5).0.apply_to_vertex(&mut point);
+        assert_relative_eq!(point.vector.x, 0.5_f32.sqrt(), epsilon = 1e-5);
+        assert_relative_eq!(point.vector.y, 0.5_f32.sqrt(), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_animated_transform_spline() {
+        let keyframes = vec![
+            (0.0, translation(0.0, 0.0, 0.0)),
+            (1.0, translation(1.0, 1.0, 0.0)),
+            (2.0, translation(2.0, 0.0, 0.0)),
+        ];
+        let linear = AnimatedTransform::from_keyframes(keyframes.clone());
+        let spline =
+            AnimatedTransform::from_keyframes(keyframes).with_interpolation(Interpolation::Spline);
+
+        // Both pass through the keyframes, but the spline rounds off the corner.
+        assert_relative_eq!(spline.at(1.0).0.matrix[1][3], 1.0, epsilon = 1e-5);
+        assert_relative_eq!(linear.at(0.75).0.matrix[1][3], 0.75, epsilon = 1e-5);
+        assert!(spline.at(0.75).0.matrix[1][3] > 0.75);
+        assert_relative_eq!(spline.at(0.75).0.matrix[0][3], 0.75, epsilon = 1e-5);
+
+        assert!(spline.moves_between(1.5, 3.0));
+        assert!(!spline.moves_between(2.0, 3.0));
+        assert!(!spline.moves_between(-1.0, 0.0));
+
+        // Coming to rest at the last keyframe, the spline overshoots it.
+        let overshoot = AnimatedTransform::from_keyframes(vec![
+            (0.0, translation(0.0, 0.0, 0.0)),
+            (1.0, translation(1.0, 0.0, 0.0)),
+            (2.0, translation(1.0, 0.0, 0.0)),
+        ])
+        .with_interpolation(Interpolation::Spline);
+        let point = AABB::new(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 0.0));
+        let bounds = overshoot.bounding_box(&point);
+        assert!(overshoot.at(1.5).0.matrix[0][3] > 1.0);
+        for step in 0..=200 {
+            let x = overshoot.at(step as f32 / 100.0).0.matrix[0][3];
+            assert!(x <= bounds.max.x + 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_animated_transform_bounds_cover_motion() {
+        let motion = AnimatedTransform::between(
+            0.0,
+            translation(0.0, 0.0, 0.0),
+            1.0,
+            translation(5.0, 0.0, 0.0),
+        );
+        let unit = AABB::new(Vector::new(-1.0, -1.0, -1.0), Vector::new(1.0, 1.0, 1.0));
+
+        let bounds = motion.bounding_box(&unit);
+        assert_relative_eq!(bounds.min.x, -1.0, epsilon = 1e-5);
+        assert_relative_eq!(bounds.max.x, 6.0, epsilon = 1e-5);
+
+        let fixed = AnimatedTransform::fixed(translation(0.0, 2.0, 0.0));
+        assert!(!fixed.is_animated());
+        assert_relative_eq!(fixed.bounding_box(&unit).max.y, 3.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_animated_transform_bounds_cover_large_rotation() {
+        let motion = AnimatedTransform::between(0.0, rotation_z(0.0), 1.0, rotation_z(170.0));
+        let small = AABB::new(Vector::new(9.9, -0.1, -0.1), Vector::new(10.1, 0.1, 0.1));
+        let bounds = motion.bounding_box(&small);
+
+        // The box passes over the y axis between samples of the rotation.
+        for step in 0..=1000 {
+            let swept = small.transform(&motion.at(step as f32 / 1000.0).0);
+            assert!(bounds.contains(swept.min, 0.0) && bounds.contains(swept.max, 0.0));
+        }
+        assert!(bounds.max.y < 10.2);
+    }
+}
@@ -0,0 +1,215 @@
+use super::{Colour, Vector};
+
+/// Arbitrary output variables: extra channels captured at the first hit of each camera ray,
+/// alongside the beauty colour. Pixels that see nothing keep the default, with no ids.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Aovs {
+    /// World space shading normal.
+    pub normal: Vector,
+    /// Base colour of the material, without lighting.
+    pub albedo: Colour,
+    /// Index of the object in the scene.
+    pub object_id: Option<u32>,
+    /// Id shared by all objects with the same material.
+    pub material_id: Option<u32>,
+    /// World space hit position.
+    pub position: Vector,
+    /// Light arriving straight from the light sources.
+    pub direct: Colour,
+    /// Light from everything else: ambient, reflection, refraction and occlusion.
+    pub indirect: Colour,
+    /// Radiance estimated from the photon maps.
+    pub photon: Colour,
+    /// Number of camera rays traced for the pixel.
+    pub sample_count: u32,
+}
+
+impl Aovs {
+    /// Adds a sample of the same pixel, to be averaged by `average`.
+    ///
+    /// Ids can't be averaged, so the pixel takes the ids of the first sample that hit something.
+    pub fn accumulate(&mut self, sample: &Aovs) {
+        self.normal = self.normal + sample.normal;
+        self.albedo += sample.albedo;
+        self.object_id = self.object_id.or(sample.object_id);
+        self.material_id = self.material_id.or(sample.material_id);
+        self.position = self.position + sample.position;
+        self.direct += sample.direct;
+        self.indirect += sample.indirect;
+        self.photon += sample.photon;
+    }
+
+    /// Divides the accumulated samples by their number, which becomes the sample count.
+    /// Normals are renormalised.
+    pub fn average(&self, num_samples: u32) -> Aovs {
+        let n = num_samples as f32;
+        Aovs {
+            sample_count: num_samples,
+            normal: self.normal.normalise(),
+            albedo: self.albedo / n,
+            position: self.position / n,
+            direct: self.direct / n,
+            indirect: self.indirect / n,
+            photon: self.photon / n,
+            ..*self
+        }
+    }
+}
+
+/// The output variables that can be written out, each as an image or as a layer of an OpenEXR
+/// file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aov {
+    Normal,
+    Albedo,
+    ObjectId,
+    MaterialId,
+    Position,
+    Direct,
+    Indirect,
+    Photon,
+    SampleCount,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 9] = [
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::Position,
+        Aov::Direct,
+        Aov::Indirect,
+        Aov::Photon,
+        Aov::SampleCount,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+            Aov::Position => "position",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+            Aov::Photon => "photon",
+            Aov::SampleCount => "sample_count",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Aov> {
+        Aov::ALL.into_iter().find(|aov| aov.name() == name)
+    }
+
+    /// Names of the channels of the variable.
+    pub fn channels(&self) -> &'static [&'static str] {
+        match self {
+            Aov::Normal | Aov::Position => &["X", "Y", "Z"],
+            Aov::Albedo | Aov::Direct | Aov::Indirect | Aov::Photon => &["R", "G", "B"],
+            Aov::ObjectId | Aov::MaterialId => &["id"],
+            Aov::SampleCount => &["count"],
+        }
+    }
+
+    /// Values of the channels for one pixel, with -1 standing in for a missing id.
+    pub fn values(&self, aovs: &Aovs) -> [f32; 3] {
+        let vector = |vector: Vector| [vector.x, vector.y, vector.z];
+        let colour = |colour: Colour| [colour.r, colour.g, colour.b];
+        let id = |id: Option<u32>| [id.map_or(-1.0, |id| id as f32), 0.0, 0.0];
+
+        match self {
+            Aov::Normal => vector(aovs.normal),
+            Aov::Albedo => colour(aovs.albedo),
+            Aov::ObjectId => id(aovs.object_id),
+            Aov::MaterialId => id(aovs.material_id),
+            Aov::Position => vector(aovs.position),
+            Aov::Direct => colour(aovs.direct),
+            Aov::Indirect => colour(aovs.indirect),
+            Aov::Photon => colour(aovs.photon),
+            Aov::SampleCount => [aovs.sample_count as f32, 0.0, 0.0],
+        }
+    }
+
+    /// Sets the variable from the values of its channels, the inverse of `values`.
+    pub fn set_values(&self, aovs: &mut Aovs, values: [f32; 3]) {
+        let vector = Vector::new(values[0], values[1], values[2]);
+        let colour = Colour::new(values[0], values[1], values[2], 1.0);
+        let id = (values[0] >= 0.0).then_some(values[0] as u32);
+
+        match self {
+            Aov::Normal => aovs.normal = vector,
+            Aov::Albedo => aovs.albedo = colour,
+            Aov::ObjectId => aovs.object_id = id,
+            Aov::MaterialId => aovs.material_id = id,
+            Aov::Position => aovs.position = vector,
+            Aov::Direct => aovs.direct = colour,
+            Aov::Indirect => aovs.indirect = colour,
+            Aov::Photon => aovs.photon = colour,
+            Aov::SampleCount => aovs.sample_count = values[0].max(0.0) as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_aovs_average() {
+        let hit = Aovs {
+            normal: Vector::new(0.0, 1.0, 0.0),
+            albedo: Colour::new(1.0, 0.5, 0.0, 1.0),
+            object_id: Some(3),
+            material_id: Some(1),
+            ..Default::default()
+        };
+        let other_hit = Aovs {
+            normal: Vector::new(1.0, 0.0, 0.0),
+            object_id: Some(4),
+            ..Default::default()
+        };
+
+        let mut sum = Aovs::default();
+        sum.accumulate(&Aovs::default());
+        sum.accumulate(&hit);
+        sum.accumulate(&other_hit);
+        sum.accumulate(&hit);
+        let average = sum.average(4);
+
+        assert_relative_eq!(average.albedo.r, 0.5);
+        assert_relative_eq!(average.normal.length(), 1.0, epsilon = 1e-6);
+        assert_eq!(average.object_id, Some(3));
+        assert_eq!(average.material_id, Some(1));
+        assert_eq!(average.sample_count, 4);
+    }
+
+    #[test]
+    fn test_aov_names() {
+        for aov in Aov::ALL {
+            assert_eq!(Aov::from_name(aov.name()), Some(aov));
+        }
+        assert_eq!(Aov::from_name("beauty"), None);
+    }
+
+    #[test]
+    fn test_aov_set_values() {
+        let aovs = Aovs {
+            normal: Vector::new(0.0, 1.0, 0.0),
+            albedo: Colour::new(1.0, 0.5, 0.0, 1.0),
+            object_id: Some(3),
+            ..Default::default()
+        };
+
+        let mut restored = Aovs::default();
+        for aov in Aov::ALL {
+            aov.set_values(&mut restored, aov.values(&aovs));
+        }
+
+        assert_eq!(restored.normal, aovs.normal);
+        assert_eq!(restored.albedo, aovs.albedo);
+        assert_eq!(restored.object_id, Some(3));
+        assert_eq!(restored.material_id, None);
+    }
+}
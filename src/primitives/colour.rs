@@ -1,7 +1,7 @@
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign};
 
 /// RGBA colour with components in the range [0.0, 1.0].
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Colour {
     /// Red.
     pub r: f32,
@@ -36,6 +36,11 @@ impl Colour {
     pub fn average(&self) -> f32 {
         (self.r + self.g + self.b) / 3.0
     }
+
+    /// Perceived brightness, weighting the components as in Rec. 709.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
 }
 
 impl Default for Colour {
@@ -3,7 +3,7 @@ use std::cmp::Ordering;
 use super::{Vector, Vertex};
 
 /// Hit stores information about an intersection between a ray and an object.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Hit {
     /// The intersection distance.
     pub distance: f32,
@@ -1,3 +1,6 @@
+pub mod aabb;
+pub mod animated_transform;
+pub mod aov;
 pub mod colour;
 pub mod hit;
 pub mod photon;
@@ -7,6 +10,9 @@ pub mod transform;
 pub mod vector;
 pub mod vertex;
 
+pub use aabb::AABB;
+pub use animated_transform::{AnimatedTransform, Interpolation};
+pub use aov::{Aov, Aovs};
 pub use colour::Colour;
 pub use hit::Hit;
 pub use photon::Photon;
@@ -1,14 +1,19 @@
-use super::Colour;
+use super::{Aovs, Colour};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Pixel {
     pub colour: Colour,
     pub depth: f32,
+    pub aovs: Aovs,
 }
 
 impl Pixel {
     pub fn new(colour: Colour, depth: f32) -> Self {
-        Self { colour, depth }
+        Self {
+            colour,
+            depth,
+            aovs: Aovs::default(),
+        }
     }
 }
 
@@ -1,10 +1,13 @@
 use super::{Vector, Vertex};
 
-/// Ray consisting of a position and a (normalised) direction.
+/// Ray consisting of a position and a (normalised) direction, cast at a moment within the
+/// camera's shutter interval.
 #[derive(Debug)]
 pub struct Ray {
     pub position: Vertex,
     pub direction: Vector,
+    /// Time at which the ray is cast, used to place moving objects.
+    pub time: f32,
 }
 
 impl Ray {
@@ -12,8 +15,14 @@ impl Ray {
         Self {
             position,
             direction,
+            time: 0.0,
         }
     }
+
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
 }
 
 impl Default for Ray {
@@ -1,6 +1,6 @@
 use std::{
     cmp::PartialEq,
-    ops::{Add, Div, Mul, Neg, Sub},
+    ops::{Add, Div, Index, Mul, Neg, Sub},
 };
 
 /// A 3D vector.
@@ -140,6 +140,20 @@ impl Neg for Vector {
     }
 }
 
+/// Access a component by axis (0 = x, 1 = y, 2 = z).
+impl Index<usize> for Vector {
+    type Output = f32;
+
+    fn index(&self, axis: usize) -> &Self::Output {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vector axis out of range: {}", axis),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -350,6 +364,14 @@ mod tests {
         assert_eq!(result.z, 30.0);
     }
 
+    #[test]
+    fn test_vector_index_by_axis() {
+        let v = Vector::new(1.0, 2.0, 3.0);
+        assert_eq!(v[0], 1.0);
+        assert_eq!(v[1], 2.0);
+        assert_eq!(v[2], 3.0);
+    }
+
     #[test]
     fn test_vector_normalize_already_normalized() {
         let v = Vector::new(1.0, 0.0, 0.0);
@@ -3,7 +3,7 @@ use std::ops::{Add, Neg, Sub};
 use super::Vector;
 
 /// A 3D vertex (homogeneous coordinate).
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vertex {
     pub vector: Vector,
     pub w: f32,
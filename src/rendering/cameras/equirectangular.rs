@@ -0,0 +1,104 @@
+use std::f32::consts::PI;
+
+use crate::{
+    primitives::{ray::Ray, Vector, Vertex},
+    Camera, FrameBuffer, Raytracer,
+};
+
+use super::sampling::render_supersampled;
+
+/// EquirectangularCamera renders the full sphere of directions around it, with longitude
+/// across the image and latitude down it, as used for environment maps. The view direction is
+/// at the centre of the image, and a 2:1 image has square pixels at the equator.
+pub struct EquirectangularCamera {
+    pub width: u16,
+    pub height: u16,
+    /// Number of samples per pixel for anti-aliasing
+    pub num_samples: u32,
+
+    position: Vertex,
+    /// Camera's forward vector.
+    w: Vector,
+    /// Camera's right vector.
+    u: Vector,
+    /// Camera's up vector.
+    v: Vector,
+}
+
+impl EquirectangularCamera {
+    pub fn new(position: Vertex, lookat: Vector, up: Vector, num_samples: u32) -> Self {
+        let w = (position.vector - lookat).normalise();
+        let u = w.cross(up).normalise();
+        let v = u.cross(w);
+
+        Self {
+            width: u16::default(),
+            height: u16::default(),
+            num_samples,
+            position,
+            w,
+            u,
+            v,
+        }
+    }
+
+    fn get_pixel_ray(&self, x: f32, y: f32) -> Ray {
+        let longitude = (x / self.width as f32 - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - y / self.height as f32) * PI;
+
+        let direction = latitude.cos() * longitude.sin() * self.u + latitude.sin() * self.v
+            - latitude.cos() * longitude.cos() * self.w;
+
+        Ray::new(self.position, direction.normalise())
+    }
+}
+
+impl Default for EquirectangularCamera {
+    fn default() -> Self {
+        Self::new(
+            Vertex::default(),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+            16,
+        )
+    }
+}
+
+impl<S: Raytracer + Sync> Camera<S> for EquirectangularCamera {
+    fn render(&mut self, scene: &S, fb: &mut FrameBuffer) {
+        self.width = fb.width;
+        self.height = fb.height;
+
+        render_supersampled(scene, fb, self.num_samples, |x, y, _| {
+            Some(self.get_pixel_ray(x, y))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_equirectangular_camera_directions() {
+        let mut camera = EquirectangularCamera::new(
+            Vertex::new(0.0, 0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+            4,
+        );
+        camera.width = 200;
+        camera.height = 100;
+
+        let direction = |x: f32, y: f32| camera.get_pixel_ray(x, y).direction;
+
+        assert_relative_eq!(direction(100.0, 50.0).z, 1.0);
+        assert_relative_eq!(direction(100.0, 0.0).y, 1.0, epsilon = 1e-6);
+        assert_relative_eq!(direction(0.0, 50.0).z, -1.0, epsilon = 1e-6);
+
+        // A quarter of the way across is 90° to the left, opposite the camera's right vector.
+        let left = direction(50.0, 50.0);
+        assert_relative_eq!(left.dot(camera.u), -1.0, epsilon = 1e-6);
+    }
+}
@@ -0,0 +1,168 @@
+use serde::Deserialize;
+
+use crate::{
+    primitives::{ray::Ray, Vector, Vertex},
+    Camera, FrameBuffer, Raytracer,
+};
+
+use super::sampling::render_supersampled;
+
+/// How a fisheye lens maps the angle from the view direction to the distance from the image
+/// centre.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FisheyeProjection {
+    /// Distance proportional to the angle.
+    #[default]
+    Equidistant,
+    /// Equal areas of the image cover equal solid angles.
+    Equisolid,
+}
+
+/// FisheyeCamera sees a wide field of view, up to a full sphere, inside a circle that fits the
+/// shorter side of the image. The corners outside the circle stay black.
+pub struct FisheyeCamera {
+    pub width: u16,
+    pub height: u16,
+    /// Angle across the image circle, in radians.
+    pub field_of_view: f32,
+    pub projection: FisheyeProjection,
+    /// Number of samples per pixel for anti-aliasing
+    pub num_samples: u32,
+
+    position: Vertex,
+    /// Camera's forward vector.
+    w: Vector,
+    /// Camera's right vector.
+    u: Vector,
+    /// Camera's up vector.
+    v: Vector,
+}
+
+impl FisheyeCamera {
+    pub fn new(
+        field_of_view: f32,
+        projection: FisheyeProjection,
+        position: Vertex,
+        lookat: Vector,
+        up: Vector,
+        num_samples: u32,
+    ) -> Self {
+        let w = (position.vector - lookat).normalise();
+        let u = w.cross(up).normalise();
+        let v = u.cross(w);
+
+        Self {
+            width: u16::default(),
+            height: u16::default(),
+            field_of_view,
+            projection,
+            num_samples,
+            position,
+            w,
+            u,
+            v,
+        }
+    }
+
+    /// Ray through fractional pixel coordinates (x, y), or none outside the image circle.
+    fn get_pixel_ray(&self, x: f32, y: f32) -> Option<Ray> {
+        let radius = (self.width.min(self.height) as f32) / 2.0;
+        let x_v = (x - (self.width as f32) / 2.0) / radius;
+        let y_v = ((self.height as f32) / 2.0 - y) / radius;
+
+        let r = (x_v * x_v + y_v * y_v).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+
+        let max_theta = self.field_of_view / 2.0;
+        let theta = match self.projection {
+            FisheyeProjection::Equidistant => r * max_theta,
+            FisheyeProjection::Equisolid => 2.0 * (r * (max_theta / 2.0).sin()).asin(),
+        };
+        let phi = y_v.atan2(x_v);
+
+        let direction = theta.sin() * phi.cos() * self.u + theta.sin() * phi.sin() * self.v
+            - theta.cos() * self.w;
+
+        Some(Ray::new(self.position, direction.normalise()))
+    }
+}
+
+impl Default for FisheyeCamera {
+    fn default() -> Self {
+        Self::new(
+            std::f32::consts::PI,
+            FisheyeProjection::Equidistant,
+            Vertex::default(),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+            16,
+        )
+    }
+}
+
+impl<S: Raytracer + Sync> Camera<S> for FisheyeCamera {
+    fn render(&mut self, scene: &S, fb: &mut FrameBuffer) {
+        self.width = fb.width;
+        self.height = fb.height;
+
+        render_supersampled(scene, fb, self.num_samples, |x, y, _| {
+            self.get_pixel_ray(x, y)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use std::f32::consts::PI;
+
+    fn camera(projection: FisheyeProjection) -> FisheyeCamera {
+        let mut camera = FisheyeCamera::new(
+            PI,
+            projection,
+            Vertex::new(0.0, 0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+            4,
+        );
+        camera.width = 200;
+        camera.height = 100;
+        camera
+    }
+
+    #[test]
+    fn test_fisheye_camera_image_circle() {
+        for projection in [FisheyeProjection::Equidistant, FisheyeProjection::Equisolid] {
+            let camera = camera(projection);
+
+            let centre = camera.get_pixel_ray(100.0, 50.0).unwrap();
+            assert_relative_eq!(centre.direction.z, 1.0);
+
+            // The top of the circle looks straight up with a 180° field of view.
+            let top = camera.get_pixel_ray(100.0, 0.0).unwrap();
+            assert_relative_eq!(top.direction.y, 1.0, epsilon = 1e-6);
+
+            assert!(camera.get_pixel_ray(0.0, 0.0).is_none());
+            assert!(camera.get_pixel_ray(40.0, 50.0).is_none());
+        }
+    }
+
+    #[test]
+    fn test_fisheye_camera_projections() {
+        // Halfway to the edge of the circle.
+        let equidistant = camera(FisheyeProjection::Equidistant)
+            .get_pixel_ray(125.0, 50.0)
+            .unwrap();
+        let equisolid = camera(FisheyeProjection::Equisolid)
+            .get_pixel_ray(125.0, 50.0)
+            .unwrap();
+
+        assert_relative_eq!(equidistant.direction.z.acos(), PI / 4.0, epsilon = 1e-5);
+        let expected = 2.0 * (0.5 * (PI / 4.0).sin()).asin();
+        assert_relative_eq!(equisolid.direction.z.acos(), expected, epsilon = 1e-5);
+    }
+}
@@ -6,7 +6,7 @@ use std::{
 };
 
 use crate::{
-    primitives::{ray::Ray, Vector, Vertex},
+    primitives::{ray::Ray, Aovs, Vector, Vertex},
     Camera, FrameBuffer, Raytracer,
 };
 
@@ -89,11 +89,19 @@ impl<S: Raytracer + Sync> Camera<S> for FullCamera {
             for x in 0..self.width {
                 let ray = self.get_pixel_ray(x, y);
 
-                let (colour, depth) = scene.trace(&ray, 0);
+                let (colour, depth, aovs) = scene.trace_aovs(&ray);
 
                 let mut fb = fb.lock().unwrap();
                 let _ = fb.plot_pixel(x as i32, y as i32, colour);
                 let _ = fb.plot_depth(x as i32, y as i32, depth);
+                let _ = fb.plot_aovs(
+                    x as i32,
+                    y as i32,
+                    Aovs {
+                        sample_count: 1,
+                        ..aovs
+                    },
+                );
             }
             pb.inc(1);
         });
@@ -1,7 +1,42 @@
+pub mod equirectangular;
+pub mod fisheye;
 pub mod full;
+pub mod orthographic;
 pub mod sampling;
 pub mod simple;
+pub mod thin_lens;
 
+pub use equirectangular::EquirectangularCamera;
+pub use fisheye::{FisheyeCamera, FisheyeProjection};
 pub use full::FullCamera;
+pub use orthographic::OrthographicCamera;
 pub use sampling::SamplingCamera;
 pub use simple::SimpleCamera;
+pub use thin_lens::ThinLensCamera;
+
+use crate::{Camera, FrameBuffer, Raytracer};
+
+/// Any of the cameras, for when the choice is made at runtime (for example by a scene file).
+pub enum SceneCamera {
+    Simple(SimpleCamera),
+    Full(FullCamera),
+    Sampling(SamplingCamera),
+    ThinLens(ThinLensCamera),
+    Orthographic(OrthographicCamera),
+    Fisheye(FisheyeCamera),
+    Equirectangular(EquirectangularCamera),
+}
+
+impl<S: Raytracer + Sync> Camera<S> for SceneCamera {
+    fn render(&mut self, scene: &S, fb: &mut FrameBuffer) {
+        match self {
+            SceneCamera::Simple(camera) => camera.render(scene, fb),
+            SceneCamera::Full(camera) => camera.render(scene, fb),
+            SceneCamera::Sampling(camera) => camera.render(scene, fb),
+            SceneCamera::ThinLens(camera) => camera.render(scene, fb),
+            SceneCamera::Orthographic(camera) => camera.render(scene, fb),
+            SceneCamera::Fisheye(camera) => camera.render(scene, fb),
+            SceneCamera::Equirectangular(camera) => camera.render(scene, fb),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{
+    primitives::{ray::Ray, Vector, Vertex},
+    Camera, FrameBuffer, Raytracer,
+};
+
+use super::sampling::render_supersampled;
+
+/// OrthographicCamera casts parallel rays from a rectangle instead of from a point, so objects
+/// keep their size at any distance and parallel lines stay parallel, as in an architectural
+/// elevation.
+pub struct OrthographicCamera {
+    pub width: u16,
+    pub height: u16,
+    /// Width of the viewed area in world units. Its height follows the image's aspect ratio.
+    pub view_width: f32,
+    /// Number of samples per pixel for anti-aliasing
+    pub num_samples: u32,
+
+    /// Centre of the viewed area.
+    position: Vertex,
+    /// Camera's forward vector.
+    w: Vector,
+    /// Camera's right vector.
+    u: Vector,
+    /// Camera's up vector.
+    v: Vector,
+}
+
+impl OrthographicCamera {
+    pub fn new(
+        view_width: f32,
+        position: Vertex,
+        lookat: Vector,
+        up: Vector,
+        num_samples: u32,
+    ) -> Self {
+        let w = (position.vector - lookat).normalise();
+        let u = w.cross(up).normalise();
+        let v = u.cross(w);
+
+        Self {
+            width: u16::default(),
+            height: u16::default(),
+            view_width,
+            num_samples,
+            position,
+            w,
+            u,
+            v,
+        }
+    }
+
+    fn get_pixel_ray(&self, x: f32, y: f32) -> Ray {
+        // Pixels are square, so both offsets are scaled by the width.
+        let pixel_size = self.view_width / self.width as f32;
+        let x_v = (x - (self.width as f32) / 2.0) * pixel_size;
+        let y_v = ((self.height as f32) / 2.0 - y) * pixel_size;
+
+        Ray::new(
+            self.position + (x_v * self.u + y_v * self.v),
+            self.w.negate(),
+        )
+    }
+}
+
+impl Default for OrthographicCamera {
+    fn default() -> Self {
+        Self::new(
+            2.0,
+            Vertex::default(),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+            16,
+        )
+    }
+}
+
+impl<S: Raytracer + Sync> Camera<S> for OrthographicCamera {
+    fn render(&mut self, scene: &S, fb: &mut FrameBuffer) {
+        self.width = fb.width;
+        self.height = fb.height;
+
+        render_supersampled(scene, fb, self.num_samples, |x, y, _| {
+            Some(self.get_pixel_ray(x, y))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_orthographic_camera_parallel_rays() {
+        let mut camera = OrthographicCamera::new(
+            10.0,
+            Vertex::new(0.0, 0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+            4,
+        );
+        camera.width = 100;
+        camera.height = 50;
+
+        let corner = camera.get_pixel_ray(0.0, 0.0);
+        let centre = camera.get_pixel_ray(50.0, 25.0);
+
+        assert_eq!(corner.direction, centre.direction);
+        assert_relative_eq!(centre.direction.z, 1.0);
+        assert_relative_eq!(centre.position.vector.length(), 0.0);
+
+        // The top left corner, with the height set by the aspect ratio.
+        assert_relative_eq!(corner.position.vector.x.abs(), 5.0);
+        assert_relative_eq!(corner.position.vector.y, 2.5);
+    }
+}
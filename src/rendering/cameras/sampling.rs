@@ -6,12 +6,14 @@ use std::{
 };
 
 use crate::{
-    primitives::{ray::Ray, Vector, Vertex},
+    primitives::{ray::Ray, Aovs, Colour, Vector, Vertex},
+    rendering::PixelFilter,
     sampling::{traits::Sampler, MultiJitterSampler},
     Camera, FrameBuffer, Raytracer,
 };
 
-/// SamplingCamera with anti-aliasing through multiple samples per pixel.
+/// SamplingCamera with anti-aliasing through multiple samples per pixel, combined by the
+/// `pixel_filter` from the config.
 /// Generic over Scene type - no trait objects needed!
 pub struct SamplingCamera {
     pub width: u16,
@@ -84,54 +86,165 @@ impl<S: Raytracer + Sync> Camera<S> for SamplingCamera {
         self.width = fb.width;
         self.height = fb.height;
 
-        let fb = Arc::new(Mutex::new(fb));
-        let num_samples = self.num_samples;
+        render_supersampled(scene, fb, self.num_samples, |x, y, _| {
+            Some(self.get_pixel_ray(x, y))
+        });
+    }
+}
+
+/// Renders the scene with any number of samples per pixel, shared by the supersampling
+/// cameras. `generate_ray` maps continuous image coordinates to a camera ray, and is also given
+/// a sampler of its own for sampling anything else about the ray, such as a point on a lens.
+/// Where it gives no ray, such as outside the image circle of a fisheye lens, the sample is
+/// black.
+///
+/// Each ray is cast at a time spread over the shutter interval of the `motion_blur` config, so
+/// that moving objects are blurred.
+///
+/// Samples are traced in batches of `num_samples` while adaptive sampling keeps going, and
+/// combined by the `pixel_filter` from the config. Depth and output variables are averaged
+/// over the samples within each pixel.
+pub(crate) fn render_supersampled<S, F>(
+    scene: &S,
+    fb: &mut FrameBuffer,
+    num_samples: u32,
+    generate_ray: F,
+) where
+    S: Raytracer + Sync,
+    F: Fn(f32, f32, &mut MultiJitterSampler) -> Option<Ray> + Sync,
+{
+    let (width, height) = (fb.width, fb.height);
+    let fb = Arc::new(Mutex::new(fb));
+    let adaptive = scene.config().adaptive_sampling;
+    let max_samples = if adaptive.enabled {
+        adaptive.max_samples.max(num_samples)
+    } else {
+        num_samples
+    };
 
-        let start_time = Instant::now();
-        let pb = ProgressBar::new(self.height as u64);
+    let start_time = Instant::now();
+    let pb = ProgressBar::new(height as u64);
 
-        (0..self.height).into_par_iter().for_each(|y| {
-            // Create sampler per thread
-            let mut sampler = MultiJitterSampler::new(num_samples, 1.0, scene.config());
+    let filter = PixelFilter::new(&scene.config().pixel_filter);
+    let shutter = scene.config().motion_blur;
 
-            for x in 0..self.width {
-                let mut colour = crate::primitives::Colour::default();
+    (0..height).into_par_iter().for_each(|y| {
+        // Create sampler per thread
+        let new_sampler = || MultiJitterSampler::new(num_samples, 1.0, scene.config());
+        let (mut sampler, mut ray_sampler, mut time_sampler) =
+            (new_sampler(), new_sampler(), new_sampler());
+        let mut samples = Vec::new();
+
+        for x in 0..width {
+            let mut aovs = Aovs::default();
+            let mut depth = 0.0;
+            let mut num_hits = 0;
+            let mut estimate = BrightnessEstimate::default();
+
+            // Sample in batches of `num_samples` for anti-aliasing, until the pixel is
+            // converged or has its maximum number of samples.
+            while num_samples > 0 && estimate.count < max_samples {
+                // The samplers only hold a few sets of positions, so further batches draw new
+                // ones rather than repeat samples the pixel already has.
+                if estimate.count > 0 {
+                    (sampler, ray_sampler, time_sampler) =
+                        (new_sampler(), new_sampler(), new_sampler());
+                }
 
-                // Sample multiple times per pixel for anti-aliasing
-                for _ in 0..num_samples {
+                for _ in 0..num_samples.min(max_samples - estimate.count) {
                     let sample = sampler.sample_unit_square();
-                    let ray = self.get_pixel_ray(x as f32 + sample.x, y as f32 + sample.y);
+                    let (sample_x, sample_y) = (x as f32 + sample.x, y as f32 + sample.y);
+                    let time = shutter.shutter_open
+                        + time_sampler.sample_unit_square().x
+                            * (shutter.shutter_close - shutter.shutter_open);
+                    let (ray_colour, ray_depth, ray_aovs) =
+                        match generate_ray(sample_x, sample_y, &mut ray_sampler) {
+                            Some(ray) => scene.trace_aovs(&ray.with_time(time)),
+                            None => (Colour::default(), 0.0, Aovs::default()),
+                        };
+                    samples.push((sample_x, sample_y, ray_colour));
+                    aovs.accumulate(&ray_aovs);
+                    estimate.add(ray_colour.average());
 
-                    // Start recursion at depth 0
-                    let (ray_colour, _depth) = scene.trace(&ray, 0);
-                    colour += ray_colour;
+                    // Samples that miss have no depth, so leave them out of the average.
+                    if ray_depth > 0.0 {
+                        depth += ray_depth;
+                        num_hits += 1;
+                    }
                 }
 
-                // Average the samples
-                colour /= num_samples as f32;
+                if estimate.has_converged(adaptive.error_threshold) {
+                    break;
+                }
+            }
 
-                let mut fb = fb.lock().unwrap();
-                let _ = fb.plot_pixel(x as i32, y as i32, colour);
-                let _ = fb.plot_depth(x as i32, y as i32, 0.0);
+            // Average the samples within the pixel for the output variables.
+            let aovs = aovs.average(estimate.count);
+            if num_hits > 0 {
+                depth /= num_hits as f32;
             }
-            pb.inc(1);
-        });
 
-        pb.finish();
-        let elapsed_time = start_time.elapsed();
-        let total_seconds = elapsed_time.as_secs();
-        println!(
-            "Completed in {:02}:{:02}.{:03}",
-            total_seconds / 60,
-            total_seconds % 60,
-            elapsed_time.subsec_millis()
-        );
+            let mut fb = fb.lock().unwrap();
+            let _ = fb.plot_depth(x as i32, y as i32, depth);
+            let _ = fb.plot_aovs(x as i32, y as i32, aovs);
+        }
+
+        // Samples can reach pixels in other rows, so add the row's colours together.
+        let mut fb = fb.lock().unwrap();
+        for (sample_x, sample_y, colour) in samples {
+            fb.add_sample(sample_x, sample_y, colour, &filter);
+        }
+        pb.inc(1);
+    });
+    fb.lock().unwrap().resolve_samples();
+
+    pb.finish();
+    let elapsed_time = start_time.elapsed();
+    let total_seconds = elapsed_time.as_secs();
+    println!(
+        "Completed in {:02}:{:02}.{:03}",
+        total_seconds / 60,
+        total_seconds % 60,
+        elapsed_time.subsec_millis()
+    );
+}
+
+/// Running mean and variance of the brightness of a pixel's samples.
+#[derive(Debug, Default)]
+struct BrightnessEstimate {
+    count: u32,
+    sum: f32,
+    sum_squares: f32,
+}
+
+impl BrightnessEstimate {
+    fn add(&mut self, brightness: f32) {
+        self.count += 1;
+        self.sum += brightness;
+        self.sum_squares += brightness * brightness;
+    }
+
+    /// Whether the 95% confidence interval of the mean brightness is within the threshold,
+    /// relative to the brightness. Dark pixels are compared against a brightness of at least
+    /// 0.01, so that noise nobody can see doesn't use up all of their samples.
+    fn has_converged(&self, threshold: f32) -> bool {
+        if self.count < 2 {
+            return false;
+        }
+
+        let n = self.count as f32;
+        let mean = self.sum / n;
+        let variance = ((self.sum_squares - n * mean * mean) / (n - 1.0)).max(0.0);
+        let confidence = 1.96 * (variance / n).sqrt();
+
+        confidence <= threshold * mean.abs().max(0.01)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::RaytracerConfig;
     use approx::assert_relative_eq;
 
     #[test]
@@ -177,4 +290,88 @@ mod tests {
                 || (ray1.direction.y - ray2.direction.y).abs() > 1e-6
         );
     }
+
+    #[test]
+    fn test_brightness_estimate_convergence() {
+        let mut flat = BrightnessEstimate::default();
+        flat.add(0.5);
+        assert!(!flat.has_converged(0.05));
+        for _ in 0..15 {
+            flat.add(0.5);
+        }
+        assert!(flat.has_converged(0.05));
+
+        let mut noisy = BrightnessEstimate::default();
+        for i in 0..16 {
+            noisy.add(if i % 2 == 0 { 0.0 } else { 1.0 });
+        }
+        assert!(!noisy.has_converged(0.05));
+
+        // The interval shrinks with the square root of the number of samples.
+        for i in 0..16 * 1024 {
+            noisy.add(if i % 2 == 0 { 0.0 } else { 1.0 });
+        }
+        assert!(noisy.has_converged(0.05));
+    }
+
+    /// Casts nothing but records the position, lens sample and time of each camera ray.
+    struct RecordingScene {
+        config: RaytracerConfig,
+        rays: Mutex<Vec<(f32, f32, f32)>>,
+    }
+
+    impl Raytracer for RecordingScene {
+        fn trace(&self, ray: &Ray, _recurse_depth: u8) -> (Colour, f32) {
+            let mut rays = self.rays.lock().unwrap();
+            let last = rays.len() - 1;
+            rays[last].2 = ray.time;
+            // Noisy enough that the pixel never converges.
+            (Colour::new(ray.time, ray.time, ray.time, 1.0), 0.0)
+        }
+
+        fn is_occluded(&self, _ray: &Ray, _max_distance: f32) -> bool {
+            false
+        }
+
+        fn config(&self) -> &RaytracerConfig {
+            &self.config
+        }
+    }
+
+    #[test]
+    fn test_adaptive_batches_draw_new_samples() {
+        let mut config = RaytracerConfig::default();
+        config.framebuffer.width = 1;
+        config.framebuffer.height = 1;
+        config.adaptive_sampling.enabled = true;
+        config.adaptive_sampling.max_samples = 64;
+        config.adaptive_sampling.error_threshold = 0.0;
+        config.motion_blur.shutter_close = 1.0;
+        let scene = RecordingScene {
+            config,
+            rays: Mutex::new(Vec::new()),
+        };
+        let mut fb = FrameBuffer::new(&config).unwrap();
+
+        render_supersampled(&scene, &mut fb, 4, |x, y, lens| {
+            let lens = lens.sample_unit_square();
+            scene
+                .rays
+                .lock()
+                .unwrap()
+                .push((x + y, lens.x + lens.y, 0.0));
+            Some(Ray::new(Vertex::default(), Vector::new(0.0, 0.0, 1.0)))
+        });
+
+        // 16 batches of 4, none of which repeats a position, lens sample or time.
+        let rays = scene.rays.into_inner().unwrap();
+        assert_eq!(rays.len(), 64);
+        for (i, a) in rays.iter().enumerate() {
+            for b in &rays[..i] {
+                assert_ne!(a.0, b.0);
+                assert_ne!(a.1, b.1);
+                assert_ne!(a.2, b.2);
+            }
+        }
+    }
 }
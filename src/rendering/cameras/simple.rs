@@ -1,4 +1,7 @@
-use crate::{primitives::ray::Ray, Camera, FrameBuffer, Raytracer};
+use crate::{
+    primitives::{ray::Ray, Aovs},
+    Camera, FrameBuffer, Raytracer,
+};
 use std::io::{self, Write};
 
 /// Simple Camera has a 90 degree field of view along the z axis.
@@ -48,11 +51,18 @@ impl<S: Raytracer + Sync> Camera<S> for SimpleCamera {
             for x in 0..self.width {
                 let ray = self.get_pixel_ray(x, y);
 
-                // Start recursion at depth 0
-                let (colour, depth) = scene.trace(&ray, 0);
+                let (colour, depth, aovs) = scene.trace_aovs(&ray);
 
                 let _ = fb.plot_pixel(x as i32, y as i32, colour);
                 let _ = fb.plot_depth(x as i32, y as i32, depth);
+                let _ = fb.plot_aovs(
+                    x as i32,
+                    y as i32,
+                    Aovs {
+                        sample_count: 1,
+                        ..aovs
+                    },
+                );
             }
 
             print!("#");
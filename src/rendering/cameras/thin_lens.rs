@@ -0,0 +1,241 @@
+use std::f32::consts::PI;
+
+use crate::{
+    primitives::{ray::Ray, Vector, Vertex},
+    sampling::{Point2D, Sampler},
+    Camera, FrameBuffer, Raytracer,
+};
+
+use super::sampling::render_supersampled;
+
+/// ThinLensCamera gives depth of field by tracing rays from points across a lens aperture
+/// instead of a pinhole. Only the plane at the focus distance is sharp, and the blur elsewhere
+/// takes the shape of the aperture: a disk, or a polygon when the aperture has blades.
+pub struct ThinLensCamera {
+    pub width: u16,
+    pub height: u16,
+    /// Field of view. Distance from the camera to the image plane.
+    pub fov: f32,
+    /// Number of samples per pixel, each through a different point of the lens.
+    pub num_samples: u32,
+    /// Radius of the lens aperture. Zero is a pinhole camera.
+    pub aperture_radius: f32,
+    /// Distance along the view direction to the plane in focus.
+    pub focus_distance: f32,
+    /// Number of aperture blades. Below three, the aperture is round.
+    pub blades: u32,
+    /// Rotation of the aperture blades in radians.
+    pub blade_rotation: f32,
+
+    position: Vertex,
+    /// Camera's forward vector.
+    w: Vector,
+    /// Camera's right vector.
+    u: Vector,
+    /// Camera's up vector.
+    v: Vector,
+}
+
+impl ThinLensCamera {
+    pub fn new(
+        fov: f32,
+        position: Vertex,
+        lookat: Vector,
+        up: Vector,
+        num_samples: u32,
+        aperture_radius: f32,
+        focus_distance: f32,
+    ) -> Self {
+        let w = (position.vector - lookat).normalise();
+        let u = w.cross(up).normalise();
+        let v = u.cross(w);
+
+        Self {
+            width: u16::default(),
+            height: u16::default(),
+            fov,
+            num_samples,
+            aperture_radius,
+            focus_distance,
+            blades: 0,
+            blade_rotation: 0.0,
+            position,
+            w,
+            u,
+            v,
+        }
+    }
+
+    /// Gives the aperture the shape of a regular polygon with the given number of blades.
+    pub fn with_blades(mut self, blades: u32, rotation: f32) -> Self {
+        self.blades = blades;
+        self.blade_rotation = rotation;
+        self
+    }
+
+    /// Sets the focus distance so that the given point is sharp.
+    pub fn focus_on(&mut self, point: Vertex) {
+        self.focus_distance = -(point.vector - self.position.vector).dot(self.w);
+    }
+
+    /// Ray through fractional pixel coordinates (x, y) from a point on the lens, given in
+    /// coordinates of the unit disk or aperture polygon.
+    fn get_pixel_ray(&self, x: f32, y: f32, lens: Point2D) -> Ray {
+        let mut x_v = (x + 0.5) - ((self.width as f32) / 2.0);
+        let mut y_v = ((self.height as f32) / 2.0) - (y + 0.5);
+
+        // Normalise.
+        x_v /= self.width as f32;
+        y_v /= self.height as f32;
+
+        // The pinhole ray reaches the image plane at distance fov, so scale it to reach the
+        // focus plane. Every ray through the lens for this pixel meets it there.
+        let direction = x_v * self.u + y_v * self.v - self.fov * self.w;
+        let focus_point = self.position.vector + (self.focus_distance / self.fov) * direction;
+
+        let origin =
+            self.position.vector + self.aperture_radius * (lens.x * self.u + lens.y * self.v);
+
+        Ray::new(
+            Vertex::new(origin.x, origin.y, origin.z, 1.0),
+            (focus_point - origin).normalise(),
+        )
+    }
+
+    /// Samples a point of the aperture, either the unit disk or a regular polygon inscribed in
+    /// it.
+    fn sample_aperture<T: Sampler>(&self, sampler: &mut T) -> Point2D {
+        if self.blades < 3 {
+            return sampler.sample_unit_disk();
+        }
+
+        // Pick a blade's triangle with one coordinate and reuse what's left of it inside the
+        // triangle, so the samples stay stratified.
+        let sample = sampler.sample_unit_square();
+        let blades = self.blades as f32;
+        let scaled = sample.x * blades;
+        let blade = scaled.floor().min(blades - 1.0);
+        let t = scaled - blade;
+
+        let angle = self.blade_rotation + 2.0 * PI * blade / blades;
+        let next_angle = angle + 2.0 * PI / blades;
+
+        // Uniform point in the triangle between the centre and the two corners.
+        let radius = sample.y.sqrt();
+        let (a, b) = (radius * (1.0 - t), radius * t);
+
+        Point2D {
+            x: a * angle.cos() + b * next_angle.cos(),
+            y: a * angle.sin() + b * next_angle.sin(),
+        }
+    }
+}
+
+impl Default for ThinLensCamera {
+    fn default() -> Self {
+        Self::new(
+            0.5,
+            Vertex::default(),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+            16,
+            0.0,
+            1.0,
+        )
+    }
+}
+
+impl<S: Raytracer + Sync> Camera<S> for ThinLensCamera {
+    fn render(&mut self, scene: &S, fb: &mut FrameBuffer) {
+        self.width = fb.width;
+        self.height = fb.height;
+
+        render_supersampled(scene, fb, self.num_samples, |x, y, sampler| {
+            Some(self.get_pixel_ray(x, y, self.sample_aperture(sampler)))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::RaytracerConfig, sampling::MultiJitterSampler};
+    use approx::assert_relative_eq;
+
+    fn camera(aperture_radius: f32) -> ThinLensCamera {
+        let mut camera = ThinLensCamera::new(
+            0.5,
+            Vertex::new(0.0, 0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+            16,
+            aperture_radius,
+            10.0,
+        );
+        camera.width = 100;
+        camera.height = 100;
+        camera
+    }
+
+    #[test]
+    fn test_thin_lens_rays_meet_on_focus_plane() {
+        let camera = camera(0.5);
+
+        let points: Vec<Vector> = [(0.0, 0.0), (1.0, 0.0), (0.0, -1.0), (-0.6, 0.8)]
+            .into_iter()
+            .map(|(x, y)| {
+                let ray = camera.get_pixel_ray(20.0, 70.0, Point2D { x, y });
+                // Distance along the ray to the focus plane, 10 units in front of the camera.
+                let t = (10.0 - ray.position.vector.z) / ray.direction.z;
+                ray.position.vector + t * ray.direction
+            })
+            .collect();
+
+        for point in &points[1..] {
+            assert_relative_eq!(point.x, points[0].x, epsilon = 1e-4);
+            assert_relative_eq!(point.y, points[0].y, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_thin_lens_rays_start_across_aperture() {
+        let camera = camera(0.5);
+
+        let ray = camera.get_pixel_ray(50.0, 50.0, Point2D { x: 1.0, y: 0.0 });
+        assert_relative_eq!(ray.position.vector.length(), 0.5, epsilon = 1e-6);
+        assert_relative_eq!(ray.direction.length(), 1.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_thin_lens_focus_on() {
+        let mut camera = camera(0.5);
+        camera.focus_on(Vertex::new(3.0, -2.0, 7.5, 1.0));
+        assert_relative_eq!(camera.focus_distance, 7.5);
+    }
+
+    #[test]
+    fn test_thin_lens_polygonal_aperture() {
+        let camera = camera(0.5).with_blades(6, 0.0);
+        let mut sampler = MultiJitterSampler::new(64, 1.0, &RaytracerConfig::default());
+
+        // A hexagon's inscribed circle has radius cos(30°).
+        let inner_radius = (PI / 6.0).cos();
+        let mut outside_inner_circle = false;
+
+        for _ in 0..64 {
+            let sample = camera.sample_aperture(&mut sampler);
+            let radius = (sample.x * sample.x + sample.y * sample.y).sqrt();
+            assert!(radius <= 1.0 + 1e-5);
+
+            // Between the inscribed circle and the unit circle, points lie inside the hexagon
+            // only near its corners.
+            if radius > inner_radius {
+                outside_inner_circle = true;
+                let angle = sample.y.atan2(sample.x).rem_euclid(PI / 3.0);
+                let to_edge = inner_radius / (angle - PI / 6.0).cos();
+                assert!(radius <= to_edge + 1e-5);
+            }
+        }
+        assert!(outside_inner_circle);
+    }
+}
@@ -0,0 +1,199 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    config::DenoiserConfig,
+    primitives::{pixel::Pixel, Colour, Vector},
+};
+
+/// B3 spline kernel of the à-trous wavelet transform.
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Edge-avoiding à-trous wavelet denoiser (Dammertz et al., 2010).
+///
+/// Each pass blurs the colour with a 5x5 kernel whose taps are spread further apart every
+/// pass, so large areas are smoothed in few passes. Neighbours only contribute as much as they
+/// resemble the centre pixel in colour, normal, depth and albedo, which keeps edges and
+/// texture sharp. The normal, albedo and depth come from the pixels' AOVs, so the image has to
+/// be rendered with them.
+pub struct Denoiser {
+    config: DenoiserConfig,
+}
+
+impl Denoiser {
+    pub fn new(config: &DenoiserConfig) -> Self {
+        Self { config: *config }
+    }
+
+    /// Denoises the colours of the pixels, which are stored row by row.
+    pub fn apply(&self, pixels: &mut [Pixel], width: usize, height: usize) {
+        let mut colours: Vec<Colour> = pixels.iter().map(|pixel| pixel.colour).collect();
+
+        for iteration in 0..self.config.iterations {
+            let step = 1 << iteration;
+            // Colours get smoother every pass, so their edge stopping gets stricter.
+            let colour_sigma = self.config.colour_sigma / 2.0_f32.powi(iteration as i32);
+
+            colours = (0..pixels.len())
+                .into_par_iter()
+                .map(|index| {
+                    self.filter_pixel(pixels, &colours, width, height, index, step, colour_sigma)
+                })
+                .collect();
+        }
+
+        for (pixel, colour) in pixels.iter_mut().zip(colours) {
+            pixel.colour = colour;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn filter_pixel(
+        &self,
+        pixels: &[Pixel],
+        colours: &[Colour],
+        width: usize,
+        height: usize,
+        index: usize,
+        step: usize,
+        colour_sigma: f32,
+    ) -> Colour {
+        let (x, y) = ((index % width) as isize, (index / width) as isize);
+        let centre = &pixels[index];
+        let centre_colour = colours[index];
+
+        let mut sum = Colour::default();
+        let mut total_weight = 0.0;
+
+        for (j, ky) in KERNEL.iter().enumerate() {
+            for (i, kx) in KERNEL.iter().enumerate() {
+                let qx = x + (i as isize - 2) * step as isize;
+                let qy = y + (j as isize - 2) * step as isize;
+                if qx < 0 || qy < 0 || qx >= width as isize || qy >= height as isize {
+                    continue;
+                }
+
+                let q = qy as usize * width + qx as usize;
+                let neighbour = &pixels[q];
+                let colour = colours[q];
+
+                let colour_distance = colour_distance_squared(centre_colour, colour);
+                let normal_distance = (centre.aovs.normal - neighbour.aovs.normal).len_sqr();
+                let albedo_distance =
+                    colour_distance_squared(centre.aovs.albedo, neighbour.aovs.albedo);
+                let depth_distance = relative_depth_difference(centre.depth, neighbour.depth);
+
+                let weight = kx
+                    * ky
+                    * edge_stop(colour_distance, colour_sigma)
+                    * edge_stop(normal_distance, self.config.normal_sigma)
+                    * edge_stop(albedo_distance, self.config.albedo_sigma)
+                    * edge_stop(depth_distance * depth_distance, self.config.depth_sigma);
+
+                sum += weight * colour;
+                total_weight += weight;
+            }
+        }
+
+        // The centre pixel always has a weight, so this is never zero.
+        let mut colour = sum / total_weight;
+        colour.a = centre_colour.a;
+        colour
+    }
+}
+
+fn colour_distance_squared(a: Colour, b: Colour) -> f32 {
+    Vector::new(a.r - b.r, a.g - b.g, a.b - b.b).len_sqr()
+}
+
+/// Depth difference relative to the centre's depth, so that the same sigma works at any
+/// distance from the camera.
+fn relative_depth_difference(centre: f32, neighbour: f32) -> f32 {
+    (centre - neighbour).abs() / centre.abs().max(1e-4)
+}
+
+/// Weight falling off with the squared distance between two features.
+fn edge_stop(distance_squared: f32, sigma: f32) -> f32 {
+    if sigma <= 0.0 {
+        return 1.0;
+    }
+    (-distance_squared / (sigma * sigma)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::Aovs;
+    use approx::assert_relative_eq;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Noisy image with a dark left half and a bright right half, which face different ways.
+    fn noisy_edge(width: usize, height: usize) -> Vec<Pixel> {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        (0..width * height)
+            .map(|index| {
+                let bright = index % width >= width / 2;
+                let base = if bright { 0.8 } else { 0.2 };
+                let value = base + rng.gen_range(-0.1..0.1);
+
+                let mut pixel = Pixel::new(Colour::new(value, value, value, 1.0), 10.0);
+                pixel.aovs = Aovs {
+                    normal: if bright {
+                        Vector::new(1.0, 0.0, 0.0)
+                    } else {
+                        Vector::new(0.0, 0.0, -1.0)
+                    },
+                    ..Default::default()
+                };
+                pixel
+            })
+            .collect()
+    }
+
+    fn variance(values: impl Iterator<Item = f32> + Clone) -> f32 {
+        let count = values.clone().count() as f32;
+        let mean = values.clone().sum::<f32>() / count;
+        values.map(|value| (value - mean).powi(2)).sum::<f32>() / count
+    }
+
+    #[test]
+    fn test_denoiser_smooths_noise_but_keeps_edges() {
+        let (width, height) = (16, 16);
+        let mut pixels = noisy_edge(width, height);
+        let left = |pixels: &[Pixel]| {
+            let values: Vec<f32> = pixels
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| index % width < width / 2)
+                .map(|(_, pixel)| pixel.colour.r)
+                .collect();
+            values
+        };
+        let noisy_variance = variance(left(&pixels).into_iter());
+
+        Denoiser::new(&DenoiserConfig::default()).apply(&mut pixels, width, height);
+
+        assert!(variance(left(&pixels).into_iter()) < noisy_variance / 4.0);
+
+        // Pixels either side of the edge keep their own brightness.
+        let row = 8 * width;
+        assert_relative_eq!(pixels[row + width / 2 - 1].colour.r, 0.2, epsilon = 0.05);
+        assert_relative_eq!(pixels[row + width / 2].colour.r, 0.8, epsilon = 0.05);
+    }
+
+    #[test]
+    fn test_denoiser_without_iterations_does_nothing() {
+        let (width, height) = (4, 4);
+        let mut pixels = noisy_edge(width, height);
+        let original: Vec<f32> = pixels.iter().map(|pixel| pixel.colour.r).collect();
+
+        let config = DenoiserConfig {
+            iterations: 0,
+            ..Default::default()
+        };
+        Denoiser::new(&config).apply(&mut pixels, width, height);
+
+        let denoised: Vec<f32> = pixels.iter().map(|pixel| pixel.colour.r).collect();
+        assert_eq!(denoised, original);
+    }
+}
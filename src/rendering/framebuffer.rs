@@ -2,9 +2,14 @@ use std::io;
 use thiserror::Error as ThiserrorError;
 
 use crate::{
-    config::RaytracerConfig,
-    primitives::{pixel::Pixel, Colour},
-    utilities::ppm_writer::PPMWriter,
+    config::{DenoiserConfig, RaytracerConfig},
+    primitives::{pixel::Pixel, Aov, Aovs, Colour},
+    rendering::{Denoiser, PixelFilter, ToneMapper},
+    utilities::{
+        exr_writer::{EXRCompression, EXRWriter},
+        image_writer::{BitDepth, FileWriter, ImageWriter},
+        png_writer::PNGWriter,
+    },
 };
 
 #[derive(Debug, ThiserrorError)]
@@ -15,15 +20,21 @@ pub enum FrameBufferError {
     #[error("Pixel out of bounds at coordinates ({x}, {y}).")]
     PixelOutOfBounds { x: i32, y: i32 },
 
+    #[error("Missing channel '{0}' in image file.")]
+    MissingChannel(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] io::Error),
 }
 
-/// Creates a framebuffer with rgba and depth and can write them to a ppm file.
+/// Creates a framebuffer with rgba and depth and can write them to an image file.
 pub struct FrameBuffer {
     pub width: u16,
     pub height: u16,
     framebuffer: Vec<Pixel>,
+    /// Sum of the filter weights of the samples added to each pixel.
+    weights: Vec<f32>,
+    tone_mapper: ToneMapper,
 }
 
 impl FrameBuffer {
@@ -42,11 +53,14 @@ impl FrameBuffer {
         }
 
         let framebuffer = vec![Pixel::default(); (width as usize) * (height as usize)];
+        let weights = vec![0.0; framebuffer.len()];
 
         Ok(Self {
             width,
             height,
             framebuffer,
+            weights,
+            tone_mapper: ToneMapper::new(&config.tone_mapping),
         })
     }
 
@@ -68,6 +82,66 @@ impl FrameBuffer {
         Ok(())
     }
 
+    pub fn plot_aovs(&mut self, x: i32, y: i32, aovs: Aovs) -> Result<(), FrameBufferError> {
+        self.check_bounds(x, y)?;
+
+        let index = (y * (self.width as i32) + x) as usize;
+        self.framebuffer[index].aovs = aovs;
+
+        Ok(())
+    }
+
+    /// Adds a sample at continuous image coordinates to every pixel within the filter's
+    /// radius, weighted by the filter at the distance to the pixel's centre. Pixel (x, y) covers
+    /// [x, x + 1) x [y, y + 1).
+    ///
+    /// The colours hold weighted sums until `resolve_samples` is called.
+    pub fn add_sample(&mut self, x: f32, y: f32, colour: Colour, filter: &PixelFilter) {
+        let radius = filter.radius();
+        let (centre_x, centre_y) = (x - 0.5, y - 0.5);
+
+        let min_x = (centre_x - radius).floor().max(0.0) as i32;
+        let max_x = (centre_x + radius).ceil().min(self.width as f32 - 1.0) as i32;
+        let min_y = (centre_y - radius).floor().max(0.0) as i32;
+        let max_y = (centre_y + radius).ceil().min(self.height as f32 - 1.0) as i32;
+
+        for py in min_y..=max_y {
+            for px in min_x..=max_x {
+                let weight = filter.evaluate(centre_x - px as f32, centre_y - py as f32);
+                if weight == 0.0 {
+                    continue;
+                }
+
+                let index = (py * (self.width as i32) + px) as usize;
+                // Scaling a colour leaves its alpha alone, so weight it separately.
+                let weighted = weight * colour;
+                let pixel = &mut self.framebuffer[index];
+                pixel.colour += Colour::new(weighted.r, weighted.g, weighted.b, weight * colour.a);
+                self.weights[index] += weight;
+            }
+        }
+    }
+
+    /// Divides the colours summed by `add_sample` by their total weight. Negative values, left
+    /// by the negative lobes of some filters, are clamped to zero. Pixels without samples are
+    /// left as they are.
+    pub fn resolve_samples(&mut self) {
+        for (pixel, weight) in self.framebuffer.iter_mut().zip(&mut self.weights) {
+            if *weight == 0.0 {
+                continue;
+            }
+
+            let colour = pixel.colour;
+            pixel.colour = Colour::new(
+                (colour.r / *weight).max(0.0),
+                (colour.g / *weight).max(0.0),
+                (colour.b / *weight).max(0.0),
+                (colour.a / *weight).max(0.0),
+            );
+            *weight = 0.0;
+        }
+    }
+
     pub fn get_pixel(&self, x: i32, y: i32) -> Result<&Pixel, FrameBufferError> {
         self.check_bounds(x, y)?;
 
@@ -75,34 +149,131 @@ impl FrameBuffer {
         Ok(&self.framebuffer[index])
     }
 
-    /// Writes RGB data to a PPM file.
-    pub fn write_rgb_file(&self, filename: &str) -> Result<(), FrameBufferError> {
-        let (min, max) = self.framebuffer.iter().fold(
-            (f32::INFINITY, f32::NEG_INFINITY),
-            |(min, max), pixel| {
-                let pixel_min = pixel.colour.r.min(pixel.colour.g).min(pixel.colour.b);
-                let pixel_max = pixel.colour.r.max(pixel.colour.g).max(pixel.colour.b);
-                (min.min(pixel_min), max.max(pixel_max))
-            },
+    /// Reads a framebuffer back from an OpenEXR file written by `write_exr`.
+    ///
+    /// The `R`, `G` and `B` channels are required. Alpha defaults to 1, depth to 0, and the
+    /// output variables found in the file are restored and returned.
+    pub fn read_exr(
+        filename: &str,
+        config: &RaytracerConfig,
+    ) -> Result<(Self, Vec<Aov>), FrameBufferError> {
+        let image =
+            exr::prelude::read_first_flat_layer_from_file(filename).map_err(io::Error::other)?;
+        let layer = &image.layer_data;
+        let channel = |name: &str| -> Option<Vec<f32>> {
+            layer
+                .channel_data
+                .list
+                .iter()
+                .find(|channel| channel.name.to_string() == name)
+                .map(|channel| channel.sample_data.values_as_f32().collect())
+        };
+        let required = |name: &str| {
+            channel(name).ok_or_else(|| FrameBufferError::MissingChannel(name.to_string()))
+        };
+
+        let width = u16::try_from(layer.size.0).unwrap_or(u16::MAX);
+        let height = u16::try_from(layer.size.1).unwrap_or(u16::MAX);
+        if width > config.framebuffer.max_width || height > config.framebuffer.max_height {
+            return Err(FrameBufferError::DimensionError { width, height });
+        }
+
+        let (r, g, b) = (required("R")?, required("G")?, required("B")?);
+        let a = channel("A");
+        let z = channel("Z");
+
+        let mut framebuffer: Vec<Pixel> = (0..r.len())
+            .map(|i| {
+                let alpha = a.as_ref().map_or(1.0, |a| a[i]);
+                let depth = z.as_ref().map_or(0.0, |z| z[i]);
+                Pixel::new(Colour::new(r[i], g[i], b[i], alpha), depth)
+            })
+            .collect();
+
+        let mut aovs = Vec::new();
+        for aov in Aov::ALL {
+            let channels: Option<Vec<Vec<f32>>> = aov
+                .channels()
+                .iter()
+                .map(|name| channel(&format!("{}.{}", aov.name(), name)))
+                .collect();
+            let Some(channels) = channels else {
+                continue;
+            };
+
+            for (i, pixel) in framebuffer.iter_mut().enumerate() {
+                let mut values = [0.0; 3];
+                for (value, channel) in values.iter_mut().zip(&channels) {
+                    *value = channel[i];
+                }
+                aov.set_values(&mut pixel.aovs, values);
+            }
+            aovs.push(aov);
+        }
+
+        let fb = Self {
+            width,
+            height,
+            weights: vec![0.0; framebuffer.len()],
+            framebuffer,
+            tone_mapper: ToneMapper::new(&config.tone_mapping),
+        };
+        Ok((fb, aovs))
+    }
+
+    /// Removes noise from the colours, guided by the normal and albedo output variables and
+    /// the depth.
+    pub fn denoise(&mut self, config: &DenoiserConfig) {
+        Denoiser::new(config).apply(
+            &mut self.framebuffer,
+            self.width as usize,
+            self.height as usize,
         );
+    }
 
-        println!("min: {}, max: {}", min, max);
+    /// Writes RGB data to an image file, in the format given by its extension.
+    ///
+    /// PPM and PNG images are tone mapped as set in the `tone_mapping` config. PFM, Radiance
+    /// `.hdr` and OpenEXR images store the colours as they are.
+    pub fn write_rgb_file(&self, filename: &str) -> Result<(), FrameBufferError> {
+        let writer = FileWriter::for_file(filename, self.width, self.height)?;
+        if writer.is_high_dynamic_range() {
+            writer.write_file(filename, &self.framebuffer, |pixel| pixel.colour)?;
+        } else {
+            writer.write_file(filename, &self.framebuffer, self.rgb_mapper())?;
+        }
 
-        let diff = if max - min == 0.0 { 1.0 } else { max - min };
+        Ok(())
+    }
 
-        let ppm_writer = PPMWriter::new(self.width, self.height);
-        ppm_writer.write_file(filename, &self.framebuffer, |pixel| {
-            let red = (((pixel.colour.r - min) / diff) * 255.0) as u8;
-            let green = (((pixel.colour.g - min) / diff) * 255.0) as u8;
-            let blue = (((pixel.colour.b - min) / diff) * 255.0) as u8;
-            (red, green, blue)
-        })?;
+    /// Writes RGB data to a PNG file with 8 or 16 bits per channel, and optionally the alpha of
+    /// each pixel's colour.
+    pub fn write_png(
+        &self,
+        filename: &str,
+        bit_depth: BitDepth,
+        alpha: bool,
+    ) -> Result<(), FrameBufferError> {
+        let writer = PNGWriter::new(self.width, self.height)
+            .with_bit_depth(bit_depth)
+            .with_alpha(alpha);
+        writer.write_file(filename, &self.framebuffer, self.rgb_mapper())?;
 
         Ok(())
     }
 
-    /// Writes depth data to a PPM file.
+    /// Writes depth data to an image file, in the format given by its extension.
+    ///
+    /// Depth is normalised by its range in the image for formats that can't store it as it is.
     pub fn write_depth_file(&self, filename: &str) -> Result<(), FrameBufferError> {
+        let writer = FileWriter::for_file(filename, self.width, self.height)?;
+        if writer.is_high_dynamic_range() {
+            writer.write_file(filename, &self.framebuffer, |pixel| {
+                Colour::new(pixel.depth, pixel.depth, pixel.depth, 1.0)
+            })?;
+            return Ok(());
+        }
+
         let (min, max) = self
             .framebuffer
             .iter()
@@ -111,15 +282,120 @@ impl FrameBuffer {
             });
         let diff = if max - min == 0.0 { 1.0 } else { max - min };
 
-        let ppm_writer = PPMWriter::new(self.width, self.height);
-        ppm_writer.write_file(filename, &self.framebuffer, |pixel| {
-            let depth = (((pixel.depth - min) / diff) * 255.0) as u8;
-            (depth, depth, depth)
+        writer.write_file(filename, &self.framebuffer, |pixel| {
+            let depth = (pixel.depth - min) / diff;
+            Colour::new(depth, depth, depth, 1.0)
+        })?;
+
+        Ok(())
+    }
+
+    /// Writes the colour and depth of every pixel to a single OpenEXR file, as the `R`, `G`,
+    /// `B`, `A` and `Z` channels, with the given output variables as extra layers. The channels
+    /// of a layer are named after it, such as `normal.X` or `albedo.R`.
+    pub fn write_exr(
+        &self,
+        filename: &str,
+        aovs: &[Aov],
+        compression: EXRCompression,
+    ) -> Result<(), FrameBufferError> {
+        let channel = |name: &str, value: fn(&Pixel) -> f32| {
+            (
+                name.to_string(),
+                self.framebuffer.iter().map(value).collect(),
+            )
+        };
+
+        let mut channels = vec![
+            channel("R", |pixel| pixel.colour.r),
+            channel("G", |pixel| pixel.colour.g),
+            channel("B", |pixel| pixel.colour.b),
+            channel("A", |pixel| pixel.colour.a),
+            channel("Z", |pixel| pixel.depth),
+        ];
+
+        for aov in aovs {
+            for (index, name) in aov.channels().iter().enumerate() {
+                channels.push((
+                    format!("{}.{}", aov.name(), name),
+                    self.framebuffer
+                        .iter()
+                        .map(|pixel| aov.values(&pixel.aovs)[index])
+                        .collect(),
+                ));
+            }
+        }
+
+        EXRWriter::new(self.width, self.height)
+            .with_compression(compression)
+            .write_channels(filename, channels)?;
+
+        Ok(())
+    }
+
+    /// Writes an output variable to an image file, in the format given by its extension.
+    ///
+    /// PFM, Radiance `.hdr` and OpenEXR images store the values as they are, with ids written
+    /// to every channel and -1 where there is no id. Other formats get a viewable image: normals
+    /// are mapped from [-1, 1], positions are normalised by their range in the image, lighting
+    /// is tone mapped, ids are shown in false colour and sample counts as a heatmap scaled to
+    /// the largest count.
+    pub fn write_aov_file(&self, aov: Aov, filename: &str) -> Result<(), FrameBufferError> {
+        let writer = FileWriter::for_file(filename, self.width, self.height)?;
+
+        if writer.is_high_dynamic_range() {
+            writer.write_file(filename, &self.framebuffer, |pixel| {
+                let values = aov.values(&pixel.aovs);
+                match aov.channels().len() {
+                    1 => Colour::new(values[0], values[0], values[0], 1.0),
+                    _ => Colour::new(values[0], values[1], values[2], 1.0),
+                }
+            })?;
+            return Ok(());
+        }
+
+        let (min, max) = self
+            .framebuffer
+            .iter()
+            .flat_map(|pixel| aov.values(&pixel.aovs))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+        let diff = if max - min == 0.0 { 1.0 } else { max - min };
+
+        writer.write_file(filename, &self.framebuffer, |pixel| {
+            let aovs = &pixel.aovs;
+            match aov {
+                Aov::Normal => Colour::new(
+                    aovs.normal.x * 0.5 + 0.5,
+                    aovs.normal.y * 0.5 + 0.5,
+                    aovs.normal.z * 0.5 + 0.5,
+                    1.0,
+                ),
+                Aov::Albedo => aovs.albedo,
+                Aov::ObjectId => id_colour(aovs.object_id),
+                Aov::MaterialId => id_colour(aovs.material_id),
+                Aov::Position => Colour::new(
+                    (aovs.position.x - min) / diff,
+                    (aovs.position.y - min) / diff,
+                    (aovs.position.z - min) / diff,
+                    1.0,
+                ),
+                Aov::Direct => self.tone_mapper.apply(aovs.direct),
+                Aov::Indirect => self.tone_mapper.apply(aovs.indirect),
+                Aov::Photon => self.tone_mapper.apply(aovs.photon),
+                Aov::SampleCount => heat_colour(aovs.sample_count as f32 / max.max(1.0)),
+            }
         })?;
 
         Ok(())
     }
 
+    /// Maps the colours of the framebuffer onto [0.0, 1.0] through the tone mapping pipeline.
+    fn rgb_mapper(&self) -> impl Fn(&Pixel) -> Colour + '_ {
+        |pixel| self.tone_mapper.apply(pixel.colour)
+    }
+
     /// Checks if the given coordinates are within the bounds of the framebuffer.
     fn check_bounds(&self, x: i32, y: i32) -> Result<(), FrameBufferError> {
         if x < 0 || x >= (self.width as i32) || y < 0 || y >= (self.height as i32) {
@@ -130,9 +406,46 @@ impl FrameBuffer {
     }
 }
 
+/// Distinct colour for each id, spreading the hues by the golden ratio. Black for no id.
+fn id_colour(id: Option<u32>) -> Colour {
+    let Some(id) = id else {
+        return Colour::new(0.0, 0.0, 0.0, 1.0);
+    };
+
+    let hue = (id as f32 * 0.618_034).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+
+    Colour::new(r, g, b, 1.0)
+}
+
+/// Maps [0, 1] from blue through cyan, green and yellow to red.
+fn heat_colour(t: f32) -> Colour {
+    let t = t.clamp(0.0, 1.0) * 4.0;
+    let x = t.fract();
+    let (r, g, b) = match t as u32 {
+        0 => (0.0, x, 1.0),
+        1 => (0.0, 1.0, 1.0 - x),
+        2 => (x, 1.0, 0.0),
+        3 => (1.0, 1.0 - x, 0.0),
+        _ => (1.0, 0.0, 0.0),
+    };
+
+    Colour::new(r, g, b, 1.0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::primitives::Vector;
+    use approx::assert_relative_eq;
 
     fn create_test_config(width: u16, height: u16) -> RaytracerConfig {
         let mut config = RaytracerConfig::default();
@@ -193,6 +506,47 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_framebuffer_box_filtered_samples() {
+        let config = create_test_config(2, 1);
+        let mut fb = FrameBuffer::new(&config).unwrap();
+        let filter = PixelFilter::new(&Default::default());
+
+        fb.add_sample(0.25, 0.5, Colour::new(1.0, 0.0, 0.0, 1.0), &filter);
+        fb.add_sample(0.75, 0.5, Colour::new(0.0, 1.0, 0.0, 1.0), &filter);
+        // On the edge between the pixels, so only in the second.
+        fb.add_sample(1.0, 0.5, Colour::new(0.0, 0.0, 1.0, 1.0), &filter);
+        fb.resolve_samples();
+
+        assert_eq!(
+            fb.get_pixel(0, 0).unwrap().colour,
+            Colour::new(0.5, 0.5, 0.0, 1.0)
+        );
+        assert_eq!(
+            fb.get_pixel(1, 0).unwrap().colour,
+            Colour::new(0.0, 0.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_framebuffer_tent_filtered_samples() {
+        let config = create_test_config(3, 1);
+        let mut fb = FrameBuffer::new(&config).unwrap();
+        let filter = PixelFilter::Tent { radius: 1.0 };
+
+        // The second sample is 0.6 pixels from the centre of the first pixel, so it adds to
+        // it with a weight of 0.4.
+        fb.add_sample(0.5, 0.5, Colour::new(1.0, 1.0, 1.0, 1.0), &filter);
+        fb.add_sample(1.1, 0.5, Colour::new(0.0, 0.0, 0.0, 1.0), &filter);
+        fb.resolve_samples();
+
+        assert_relative_eq!(fb.get_pixel(0, 0).unwrap().colour.r, 1.0 / 1.4);
+        assert_relative_eq!(fb.get_pixel(1, 0).unwrap().colour.r, 0.0);
+        assert_relative_eq!(fb.get_pixel(1, 0).unwrap().colour.a, 1.0);
+        // Out of reach of both samples.
+        assert_eq!(fb.get_pixel(2, 0).unwrap().colour, Colour::default());
+    }
+
     #[test]
     fn test_framebuffer_get_pixel() {
         let config = create_test_config(10, 10);
@@ -207,4 +561,247 @@ mod tests {
         assert_eq!(pixel.colour.g, 0.2);
         assert_eq!(pixel.depth, 5.5);
     }
+
+    fn temp_file(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("raytracer_framebuffer_{name}"))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn decode_png(filename: &str) -> (png::OutputInfo, Vec<u8>) {
+        let decoder = png::Decoder::new(std::fs::File::open(filename).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        data.truncate(info.buffer_size());
+        (info, data)
+    }
+
+    #[test]
+    fn test_framebuffer_write_png() {
+        let config = create_test_config(2, 1);
+        let mut fb = FrameBuffer::new(&config).unwrap();
+        fb.plot_pixel(0, 0, Colour::new(0.0, 0.5, 1.0, 0.0))
+            .unwrap();
+        fb.plot_pixel(1, 0, Colour::new(1.0, 1.0, 1.0, 1.0))
+            .unwrap();
+
+        let filename = temp_file("rgb.png");
+        fb.write_rgb_file(&filename).unwrap();
+
+        let (info, data) = decode_png(&filename);
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        // 0.5 is sRGB encoded by default.
+        assert_eq!(data, vec![0, 188, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_framebuffer_write_png_16_bit_with_alpha() {
+        let config = create_test_config(1, 1);
+        let mut fb = FrameBuffer::new(&config).unwrap();
+        fb.plot_pixel(0, 0, Colour::new(0.0, 0.0, 1.0, 0.5))
+            .unwrap();
+
+        let filename = temp_file("rgba16.png");
+        fb.write_png(&filename, BitDepth::Sixteen, true).unwrap();
+
+        let (info, data) = decode_png(&filename);
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+        assert_eq!(data, vec![0, 0, 0, 0, 255, 255, 128, 0]);
+    }
+
+    #[test]
+    fn test_framebuffer_unsupported_format() {
+        let config = create_test_config(1, 1);
+        let fb = FrameBuffer::new(&config).unwrap();
+
+        let result = fb.write_rgb_file(&temp_file("image.bmp"));
+        assert!(matches!(result, Err(FrameBufferError::IoError(_))));
+    }
+
+    fn hdr_test_framebuffer() -> FrameBuffer {
+        let config = create_test_config(2, 2);
+        let mut fb = FrameBuffer::new(&config).unwrap();
+        fb.plot_pixel(0, 0, Colour::new(12.5, -0.5, 0.25, 1.0))
+            .unwrap();
+        fb.plot_pixel(1, 1, Colour::new(1.0, 2.0, 3.0, 0.5))
+            .unwrap();
+        fb.plot_depth(1, 1, 42.0).unwrap();
+        fb
+    }
+
+    #[test]
+    fn test_framebuffer_write_pfm() {
+        let filename = temp_file("rgb.pfm");
+        hdr_test_framebuffer().write_rgb_file(&filename).unwrap();
+
+        let bytes = std::fs::read(&filename).unwrap();
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+
+        let floats: Vec<f32> = bytes[header.len()..]
+            .chunks(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(floats.len(), 2 * 2 * 3);
+        // Rows go from the bottom up, so the top left pixel comes after the bottom row.
+        assert_eq!(&floats[3..6], &[1.0, 2.0, 3.0]);
+        assert_eq!(&floats[6..9], &[12.5, -0.5, 0.25]);
+    }
+
+    #[test]
+    fn test_framebuffer_write_exr() {
+        for compression in [EXRCompression::Uncompressed, EXRCompression::ZIP] {
+            let filename = temp_file(&format!("{compression:?}.exr"));
+            hdr_test_framebuffer()
+                .write_exr(&filename, &[], compression)
+                .unwrap();
+
+            let image = exr::prelude::read_first_flat_layer_from_file(&filename).unwrap();
+            let channels = &image.layer_data.channel_data.list;
+            let channel = |name: &str| {
+                let channel = channels
+                    .iter()
+                    .find(|channel| channel.name.to_string() == name)
+                    .unwrap();
+                channel.sample_data.values_as_f32().collect::<Vec<_>>()
+            };
+
+            assert_eq!(channel("R"), vec![12.5, 0.0, 0.0, 1.0]);
+            assert_eq!(channel("G"), vec![-0.5, 0.0, 0.0, 2.0]);
+            assert_eq!(channel("A"), vec![1.0, 0.0, 0.0, 0.5]);
+            assert_eq!(channel("Z"), vec![0.0, 0.0, 0.0, 42.0]);
+        }
+    }
+
+    #[test]
+    fn test_framebuffer_write_hdr_depth() {
+        let filename = temp_file("depth.hdr");
+        hdr_test_framebuffer().write_depth_file(&filename).unwrap();
+
+        let bytes = std::fs::read(&filename).unwrap();
+        // 42 = 0.65625 * 2^6.
+        assert_eq!(&bytes[bytes.len() - 4..], &[168, 168, 168, 134]);
+    }
+
+    #[test]
+    fn test_framebuffer_write_aov_layers() {
+        let mut fb = hdr_test_framebuffer();
+        let aovs = Aovs {
+            normal: Vector::new(0.0, 1.0, 0.0),
+            object_id: Some(7),
+            ..Default::default()
+        };
+        fb.plot_aovs(1, 0, aovs).unwrap();
+
+        let filename = temp_file("aovs.exr");
+        fb.write_exr(
+            &filename,
+            &[Aov::Normal, Aov::ObjectId],
+            EXRCompression::ZIP,
+        )
+        .unwrap();
+
+        let image = exr::prelude::read_first_flat_layer_from_file(&filename).unwrap();
+        let channels = &image.layer_data.channel_data.list;
+        let channel = |name: &str| {
+            let channel = channels
+                .iter()
+                .find(|channel| channel.name.to_string() == name)
+                .unwrap();
+            channel.sample_data.values_as_f32().collect::<Vec<_>>()
+        };
+
+        assert_eq!(channels.len(), 5 + 3 + 1);
+        assert_eq!(channel("normal.Y"), vec![0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(channel("object_id.id"), vec![-1.0, 7.0, -1.0, -1.0]);
+    }
+
+    #[test]
+    fn test_framebuffer_read_exr() {
+        let mut fb = hdr_test_framebuffer();
+        let aovs = Aovs {
+            normal: Vector::new(0.0, 1.0, 0.0),
+            albedo: Colour::new(0.5, 0.25, 0.0, 1.0),
+            ..Default::default()
+        };
+        fb.plot_aovs(1, 0, aovs).unwrap();
+
+        let filename = temp_file("read.exr");
+        fb.write_exr(&filename, &[Aov::Normal, Aov::Albedo], EXRCompression::ZIP)
+            .unwrap();
+
+        let config = create_test_config(2, 2);
+        let (read, layers) = FrameBuffer::read_exr(&filename, &config).unwrap();
+
+        assert_eq!((read.width, read.height), (2, 2));
+        assert_eq!(layers, vec![Aov::Normal, Aov::Albedo]);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let (expected, pixel) = (fb.get_pixel(x, y).unwrap(), read.get_pixel(x, y).unwrap());
+            assert_eq!(pixel.colour, expected.colour);
+            assert_eq!(pixel.depth, expected.depth);
+            assert_eq!(pixel.aovs.normal, expected.aovs.normal);
+            // Albedo layers only store RGB.
+            assert_eq!(pixel.aovs.albedo.r, expected.aovs.albedo.r);
+            assert_eq!(pixel.aovs.albedo.g, expected.aovs.albedo.g);
+        }
+    }
+
+    #[test]
+    fn test_framebuffer_read_exr_missing_channel() {
+        let filename = temp_file("depth_only.exr");
+        EXRWriter::new(1, 1)
+            .write_channels(&filename, vec![("Z".to_string(), vec![1.0])])
+            .unwrap();
+
+        let result = FrameBuffer::read_exr(&filename, &create_test_config(1, 1));
+        assert!(matches!(result, Err(FrameBufferError::MissingChannel(name)) if name == "R"));
+    }
+
+    #[test]
+    fn test_framebuffer_write_aov_file() {
+        let mut fb = FrameBuffer::new(&create_test_config(2, 1)).unwrap();
+        let aovs = Aovs {
+            normal: Vector::new(0.0, 0.0, -1.0),
+            object_id: Some(0),
+            ..Default::default()
+        };
+        fb.plot_aovs(0, 0, aovs).unwrap();
+
+        let filename = temp_file("normal.png");
+        fb.write_aov_file(Aov::Normal, &filename).unwrap();
+        let (_, data) = decode_png(&filename);
+        assert_eq!(&data[..3], &[128, 128, 0]);
+
+        let filename = temp_file("object_id.png");
+        fb.write_aov_file(Aov::ObjectId, &filename).unwrap();
+        let (_, data) = decode_png(&filename);
+        assert_eq!(data, vec![255, 0, 0, 0, 0, 0]);
+
+        fb.plot_aovs(
+            0,
+            0,
+            Aovs {
+                sample_count: 64,
+                ..aovs
+            },
+        )
+        .unwrap();
+        fb.plot_aovs(
+            1,
+            0,
+            Aovs {
+                sample_count: 16,
+                ..aovs
+            },
+        )
+        .unwrap();
+        let filename = temp_file("sample_count.png");
+        fb.write_aov_file(Aov::SampleCount, &filename).unwrap();
+        let (_, data) = decode_png(&filename);
+        assert_eq!(data, vec![255, 0, 0, 0, 255, 255]);
+    }
 }
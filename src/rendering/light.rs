@@ -1,6 +1,19 @@
-use crate::primitives::{Colour, Vector, Vertex};
+use std::{f32::consts::PI, sync::Arc};
 
-#[derive(Clone)]
+use crate::{
+    geometry::SceneObject,
+    primitives::{Colour, Vector, Vertex},
+    sampling::{Distribution1D, Distribution2D, Point2D, Sampler},
+    shading::Material,
+    utilities::{hdr_reader::HdrImage, ies_reader::IesProfile},
+};
+
+/// Resolution of the grid over the sphere of directions that photons from an IES light are
+/// distributed by.
+const IES_GRID_WIDTH: usize = 180;
+const IES_GRID_HEIGHT: usize = 90;
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Light {
     Directional {
         direction: Vector,
@@ -9,7 +22,94 @@ pub enum Light {
     Point {
         position: Vertex,
         intensity: Colour,
+        /// Whether the light falls off with the square of the distance, from its intensity at a
+        /// distance of one.
+        inverse_square: bool,
     },
+    /// Point light shining in a cone around its direction. It is at full intensity within the
+    /// inner angle, and fades out smoothly by the outer angle. Angles are in radians from the
+    /// direction.
+    Spot {
+        position: Vertex,
+        direction: Vector,
+        inner_angle: f32,
+        outer_angle: f32,
+        intensity: Colour,
+        inverse_square: bool,
+    },
+    /// Point light whose intensity in each direction is scaled by the candela distribution of
+    /// an IES profile, relative to its brightest direction. The profile's nadir points along
+    /// `direction`, and `rotation` turns it about that in radians.
+    Ies {
+        position: Vertex,
+        direction: Vector,
+        rotation: f32,
+        intensity: Colour,
+        inverse_square: bool,
+        profile: Arc<IesProfile>,
+        /// Photon emission, over the profile's vertical and horizontal angles.
+        distribution: Arc<Distribution2D>,
+    },
+    /// Parallelogram spanned by two edges from a corner, shining from the side that
+    /// `edge_u × edge_v` points to.
+    Rectangle {
+        corner: Vertex,
+        edge_u: Vector,
+        edge_v: Vector,
+        intensity: Colour,
+        /// Shadow rays per shaded point. Must be a square number.
+        samples: u32,
+    },
+    /// Disc shining from the side its normal points to.
+    Disc {
+        center: Vertex,
+        normal: Vector,
+        radius: f32,
+        intensity: Colour,
+        /// Shadow rays per shaded point. Must be a square number.
+        samples: u32,
+    },
+    /// Sphere shining in all directions.
+    Sphere {
+        center: Vertex,
+        radius: f32,
+        intensity: Colour,
+        /// Shadow rays per shaded point. Must be a square number.
+        samples: u32,
+    },
+    /// Triangles shining from both sides, such as the surface of an emissive mesh.
+    Mesh {
+        triangles: Arc<Vec<[Vertex; 3]>>,
+        /// Triangles are picked in proportion to their area.
+        areas: Arc<Distribution1D>,
+        center: Vertex,
+        intensity: Colour,
+        /// Shadow rays per shaded point. Must be a square number.
+        samples: u32,
+    },
+    /// Light arriving from every direction, from an equirectangular map of the radiance around
+    /// the scene, laid out as seen by an `EquirectangularCamera` looking along +z. The map is
+    /// turned about the vertical axis by `rotation` radians, and scaled by the intensity.
+    Environment {
+        map: Arc<HdrImage>,
+        /// Directions are sampled in proportion to the luminance of the map.
+        distribution: Arc<Distribution2D>,
+        rotation: f32,
+        intensity: Colour,
+        /// Shadow rays per shaded point. Must be a square number.
+        samples: u32,
+    },
+}
+
+/// Light reaching a surface point from a point on a light.
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+    /// Point on the light, or none for a directional light.
+    pub position: Option<Vertex>,
+    /// Normalised direction from the light to the surface.
+    pub direction: Vector,
+    /// This sample's share of the light's intensity.
+    pub intensity: Colour,
 }
 
 impl Light {
@@ -24,40 +124,692 @@ impl Light {
         Self::Point {
             position,
             intensity,
+            inverse_square: false,
         }
     }
 
-    pub fn get_direction(&self, surface: Vertex) -> (Option<Vertex>, Vector, bool) {
-        match self {
-            Light::Directional { direction, .. } => (None, *direction, true),
-            Light::Point { position, .. } => (
-                Some(*position),
-                (surface.vector - position.vector).normalise(),
-                true,
+    pub fn new_spot(
+        position: Vertex,
+        direction: Vector,
+        inner_angle: f32,
+        outer_angle: f32,
+        intensity: Colour,
+    ) -> Self {
+        Self::Spot {
+            position,
+            direction: direction.normalise(),
+            inner_angle,
+            outer_angle,
+            intensity,
+            inverse_square: false,
+        }
+    }
+
+    pub fn new_ies(
+        position: Vertex,
+        direction: Vector,
+        rotation: f32,
+        profile: IesProfile,
+        intensity: Colour,
+    ) -> Self {
+        // Weighted by the solid angle of each cell of the grid, which shrinks towards the poles.
+        let function: Vec<f32> = (0..IES_GRID_HEIGHT * IES_GRID_WIDTH)
+            .map(|index| {
+                let row = index / IES_GRID_WIDTH;
+                let vertical = (row as f32 + 0.5) / IES_GRID_HEIGHT as f32;
+                let horizontal = ((index % IES_GRID_WIDTH) as f32 + 0.5) / IES_GRID_WIDTH as f32;
+                profile.relative_intensity(vertical * 180.0, horizontal * 360.0) * ies_row_band(row)
+            })
+            .collect();
+
+        Self::Ies {
+            position,
+            direction: direction.normalise(),
+            rotation,
+            intensity,
+            inverse_square: false,
+            profile: Arc::new(profile),
+            distribution: Arc::new(Distribution2D::new(&function, IES_GRID_WIDTH)),
+        }
+    }
+
+    /// Makes a point, spot or IES light fall off with the square of the distance.
+    pub fn with_inverse_square(mut self) -> Self {
+        if let Light::Point { inverse_square, .. }
+        | Light::Spot { inverse_square, .. }
+        | Light::Ies { inverse_square, .. } = &mut self
+        {
+            *inverse_square = true;
+        }
+        self
+    }
+
+    pub fn new_rectangle(
+        corner: Vertex,
+        edge_u: Vector,
+        edge_v: Vector,
+        intensity: Colour,
+        samples: u32,
+    ) -> Self {
+        Self::Rectangle {
+            corner,
+            edge_u,
+            edge_v,
+            intensity,
+            samples,
+        }
+    }
+
+    pub fn new_disc(
+        center: Vertex,
+        normal: Vector,
+        radius: f32,
+        intensity: Colour,
+        samples: u32,
+    ) -> Self {
+        Self::Disc {
+            center,
+            normal: normal.normalise(),
+            radius,
+            intensity,
+            samples,
+        }
+    }
+
+    pub fn new_sphere(center: Vertex, radius: f32, intensity: Colour, samples: u32) -> Self {
+        Self::Sphere {
+            center,
+            radius,
+            intensity,
+            samples,
+        }
+    }
+
+    /// Returns none if there are no triangles.
+    pub fn new_mesh(triangles: Vec<[Vertex; 3]>, intensity: Colour, samples: u32) -> Option<Self> {
+        let areas: Vec<f32> = triangles
+            .iter()
+            .map(|[a, b, c]| (b.vector - a.vector).cross(c.vector - a.vector).length() / 2.0)
+            .collect();
+        let total_area: f32 = areas.iter().sum();
+        if total_area <= 0.0 {
+            return None;
+        }
+
+        // Centroid of the surface, with each triangle weighted by its area.
+        let center =
+            triangles
+                .iter()
+                .zip(&areas)
+                .fold(Vector::default(), |sum, ([a, b, c], area)| {
+                    sum + (area / (3.0 * total_area)) * (a.vector + b.vector + c.vector)
+                });
+
+        Some(Self::Mesh {
+            triangles: Arc::new(triangles),
+            areas: Arc::new(Distribution1D::new(areas)),
+            center: Vertex::new(center.x, center.y, center.z, 1.0),
+            intensity,
+            samples,
+        })
+    }
+
+    /// Light given off by an object with an emissive material, as placed at the given time, or
+    /// none if the object isn't emissive or its surface can't be sampled. Its intensity is the
+    /// material's radiance.
+    pub fn from_emissive_object(object: &SceneObject, time: f32) -> Option<Self> {
+        let Material::Emissive(material) = object.material() else {
+            return None;
+        };
+
+        match object {
+            SceneObject::Sphere(sphere) if sphere.geometry.is_round() => Some(Self::new_sphere(
+                sphere.geometry.center,
+                sphere.geometry.radius,
+                material.radiance,
+                material.samples,
+            )),
+            _ => Self::new_mesh(
+                object.surface_triangles(time)?,
+                material.radiance,
+                material.samples,
             ),
         }
     }
 
+    pub fn new_environment(map: HdrImage, rotation: f32, intensity: Colour, samples: u32) -> Self {
+        // Weighted by the solid angle of each row, which shrinks towards the poles.
+        let function: Vec<f32> = map
+            .pixels()
+            .iter()
+            .enumerate()
+            .map(|(index, pixel)| {
+                let row = index / map.width();
+                let polar = (row as f32 + 0.5) / map.height() as f32 * PI;
+                pixel.luminance().max(0.0) * polar.sin()
+            })
+            .collect();
+        let distribution = Distribution2D::new(&function, map.width());
+
+        Self::Environment {
+            map: Arc::new(map),
+            distribution: Arc::new(distribution),
+            rotation,
+            intensity,
+            samples,
+        }
+    }
+
+    /// Direction from the center of the light to the surface, and whether the surface is on the
+    /// side that the light shines to and within the cone of a spotlight. Environment lights
+    /// shine from every direction, so have none.
+    pub fn get_direction(&self, surface: Vertex) -> Option<(Option<Vertex>, Vector, bool)> {
+        match self {
+            Light::Directional { direction, .. } => Some((None, *direction, true)),
+            Light::Environment { .. } => None,
+            _ => {
+                let position = self.get_position().unwrap();
+                let direction = (surface.vector - position.vector).normalise();
+                Some((Some(position), direction, self.emission(direction) > 0.0))
+            }
+        }
+    }
+
     pub fn get_intensity(&self) -> Colour {
         match self {
-            Light::Directional { intensity, .. } => *intensity,
-            Light::Point { intensity, .. } => *intensity,
+            Light::Directional { intensity, .. }
+            | Light::Point { intensity, .. }
+            | Light::Spot { intensity, .. }
+            | Light::Ies { intensity, .. }
+            | Light::Rectangle { intensity, .. }
+            | Light::Disc { intensity, .. }
+            | Light::Sphere { intensity, .. }
+            | Light::Mesh { intensity, .. }
+            | Light::Environment { intensity, .. } => *intensity,
         }
     }
 
+    /// Position of the light, or the center of an area light.
     pub fn get_position(&self) -> Option<Vertex> {
         match self {
-            Light::Directional { .. } => None,
-            Light::Point { position, .. } => Some(*position),
+            Light::Directional { .. } | Light::Environment { .. } => None,
+            Light::Point { position, .. }
+            | Light::Spot { position, .. }
+            | Light::Ies { position, .. } => Some(*position),
+            Light::Rectangle {
+                corner,
+                edge_u,
+                edge_v,
+                ..
+            } => Some(*corner + 0.5 * (*edge_u + *edge_v)),
+            Light::Disc { center, .. }
+            | Light::Sphere { center, .. }
+            | Light::Mesh { center, .. } => Some(*center),
+        }
+    }
+
+    /// Fraction of the intensity leaving the light in the given direction. Flat lights only
+    /// shine from one side, spotlights fade out towards the edge of their cone, and IES lights
+    /// follow their profile.
+    pub fn emission(&self, direction: Vector) -> f32 {
+        match self {
+            Light::Ies { profile, .. } => {
+                let (vertical, horizontal) = self.profile_angles(direction);
+                profile.relative_intensity(vertical.to_degrees(), horizontal.to_degrees())
+            }
+            Light::Spot {
+                direction: axis,
+                inner_angle,
+                outer_angle,
+                ..
+            } => {
+                let cosine = direction.dot(*axis);
+                let (cos_inner, cos_outer) = (inner_angle.cos(), outer_angle.cos());
+                if cosine >= cos_inner {
+                    1.0
+                } else if cosine <= cos_outer {
+                    0.0
+                } else {
+                    // Smoothstep between the edges of the cones.
+                    let t = (cosine - cos_outer) / (cos_inner - cos_outer);
+                    t * t * (3.0 - 2.0 * t)
+                }
+            }
+            _ => match self.normal() {
+                Some(normal) if direction.dot(normal) <= 0.0 => 0.0,
+                _ => 1.0,
+            },
+        }
+    }
+
+    /// Inverse-square falloff of a point or spot light at a surface, if it has any.
+    fn attenuation(&self, surface: Vertex) -> f32 {
+        match self {
+            Light::Point {
+                position,
+                inverse_square: true,
+                ..
+            }
+            | Light::Spot {
+                position,
+                inverse_square: true,
+                ..
+            }
+            | Light::Ies {
+                position,
+                inverse_square: true,
+                ..
+            } => 1.0 / (surface.vector - position.vector).len_sqr(),
+            _ => 1.0,
+        }
+    }
+
+    /// Vertical and horizontal angles of a direction in an IES light's profile, in radians.
+    fn profile_angles(&self, direction: Vector) -> (f32, f32) {
+        let Light::Ies {
+            direction: nadir,
+            rotation,
+            ..
+        } = self
+        else {
+            return (0.0, 0.0);
+        };
+        let (u, v) = orthonormal_basis(*nadir);
+        let vertical = direction.dot(*nadir).clamp(-1.0, 1.0).acos();
+        let horizontal = direction.dot(v).atan2(direction.dot(u)) - rotation;
+        (vertical, horizontal)
+    }
+
+    /// Direction at the given angles of an IES light's profile, in radians.
+    fn profile_direction(&self, vertical: f32, horizontal: f32) -> Vector {
+        let Light::Ies {
+            direction: nadir,
+            rotation,
+            ..
+        } = self
+        else {
+            return Vector::default();
+        };
+        let (u, v) = orthonormal_basis(*nadir);
+        let horizontal = horizontal + rotation;
+        vertical.sin() * horizontal.cos() * u
+            + vertical.sin() * horizontal.sin() * v
+            + vertical.cos() * *nadir
+    }
+
+    /// Radiance arriving from far away in a direction, from an environment light's map. Other
+    /// lights can't be seen by rays that miss the scene, and give black.
+    pub fn radiance(&self, direction: Vector) -> Colour {
+        let Light::Environment { map, intensity, .. } = self else {
+            return Colour::default();
+        };
+        let point = self.map_point(direction.normalise());
+        let x = ((point.x * map.width() as f32) as usize).min(map.width() - 1);
+        let y = ((point.y * map.height() as f32) as usize).min(map.height() - 1);
+        *intensity * map.get_pixel(x, y)
+    }
+
+    /// Position in an environment light's map of a direction, across the map and down it, each
+    /// in [0, 1).
+    fn map_point(&self, direction: Vector) -> Point2D {
+        let rotation = match self {
+            Light::Environment { rotation, .. } => *rotation,
+            _ => 0.0,
+        };
+        let longitude = direction.x.atan2(direction.z) - rotation;
+        Point2D {
+            x: (longitude / (2.0 * PI) + 0.5).rem_euclid(1.0),
+            y: direction.y.clamp(-1.0, 1.0).acos() / PI,
+        }
+    }
+
+    /// Direction of a position in an environment light's map, the inverse of `map_point`.
+    fn map_direction(&self, point: Point2D) -> Vector {
+        let rotation = match self {
+            Light::Environment { rotation, .. } => *rotation,
+            _ => 0.0,
+        };
+        let longitude = (point.x - 0.5) * 2.0 * PI + rotation;
+        let polar = point.y * PI;
+        Vector::new(
+            polar.sin() * longitude.sin(),
+            polar.cos(),
+            polar.sin() * longitude.cos(),
+        )
+    }
+
+    /// Samples a direction towards an environment light in proportion to the luminance of its
+    /// map, returning the direction and its density over the sphere of directions.
+    fn sample_environment<S: Sampler>(&self, sampler: &mut S) -> (Vector, f32) {
+        let Light::Environment { distribution, .. } = self else {
+            return (Vector::default(), 0.0);
+        };
+        let (point, pdf) = distribution.sample(sampler.sample_unit_square());
+        // The map spans 2π across and π down.
+        let sin_polar = (point.y * PI).sin();
+        let density = if sin_polar > 0.0 {
+            pdf / (2.0 * PI * PI * sin_polar)
+        } else {
+            0.0
+        };
+        (self.map_direction(point), density)
+    }
+
+    /// Normal of the side that a flat light shines from.
+    fn normal(&self) -> Option<Vector> {
+        match self {
+            Light::Rectangle { edge_u, edge_v, .. } => Some(edge_u.cross(*edge_v).normalise()),
+            Light::Disc { normal, .. } => Some(*normal),
+            _ => None,
+        }
+    }
+
+    /// Number of shadow rays traced towards the light from each shaded point.
+    pub fn num_samples(&self) -> u32 {
+        match self {
+            Light::Directional { .. }
+            | Light::Point { .. }
+            | Light::Spot { .. }
+            | Light::Ies { .. } => 1,
+            Light::Rectangle { samples, .. }
+            | Light::Disc { samples, .. }
+            | Light::Sphere { samples, .. }
+            | Light::Mesh { samples, .. }
+            | Light::Environment { samples, .. } => *samples,
+        }
+    }
+
+    /// Whether the light has a surface that it's sampled across.
+    fn is_area_light(&self) -> bool {
+        matches!(
+            self,
+            Light::Rectangle { .. }
+                | Light::Disc { .. }
+                | Light::Sphere { .. }
+                | Light::Mesh { .. }
+        )
+    }
+
+    /// Samples the light as seen from a surface point, splitting its intensity between the
+    /// samples and passing each to `visit`. Area lights are sampled at stratified points across
+    /// their surface, which gives soft shadows, and take `num_samples` points from the sampler.
+    /// Samples from the back of a flat light or outside the cone of a spotlight are left out.
+    pub fn illuminate<S: Sampler>(
+        &self,
+        surface: Vertex,
+        sampler: &mut S,
+        mut visit: impl FnMut(LightSample),
+    ) {
+        if let Light::Environment { samples, .. } = self {
+            // Directions are importance sampled from the map, and weighted by their density.
+            for _ in 0..*samples {
+                let (towards, density) = self.sample_environment(sampler);
+                if density > 0.0 {
+                    visit(LightSample {
+                        position: None,
+                        direction: towards.negate(),
+                        intensity: self.radiance(towards) / (density * *samples as f32),
+                    });
+                }
+            }
+            return;
+        }
+
+        let Some((position, direction, is_lit)) = self.get_direction(surface) else {
+            return;
+        };
+        if !self.is_area_light() {
+            if is_lit {
+                let falloff = self.emission(direction) * self.attenuation(surface);
+                visit(LightSample {
+                    position,
+                    direction,
+                    intensity: falloff * self.get_intensity(),
+                });
+            }
+            return;
+        }
+
+        let num_samples = self.num_samples();
+        let share = self.get_intensity() / num_samples as f32;
+
+        for _ in 0..num_samples {
+            let (point, normal) = match self {
+                // Only the half facing the surface is visible, so sample it uniformly as seen
+                // from the surface, as a disc.
+                Light::Sphere { center, radius, .. } => {
+                    let towards = (surface.vector - center.vector).normalise();
+                    let (u, v) = orthonormal_basis(towards);
+                    let disc = sampler.sample_unit_disk();
+                    let height = (1.0 - disc.x * disc.x - disc.y * disc.y).max(0.0).sqrt();
+                    let point = *center + *radius * (disc.x * u + disc.y * v + height * towards);
+                    (point, towards)
+                }
+                _ => self.sample_surface(sampler),
+            };
+
+            let direction = (surface.vector - point.vector).normalise();
+            // Flat surfaces appear smaller at grazing angles. Meshes shine from both sides of
+            // each triangle.
+            let cosine = match self {
+                Light::Sphere { .. } => 1.0,
+                Light::Mesh { .. } => direction.dot(normal).abs(),
+                _ => direction.dot(normal),
+            };
+            if cosine > 0.0 {
+                visit(LightSample {
+                    position: Some(point),
+                    direction,
+                    intensity: cosine * share,
+                });
+            }
+        }
+    }
+
+    /// Point on the surface of an area light, uniformly distributed over its area, and the
+    /// surface normal there.
+    fn sample_surface<S: Sampler>(&self, sampler: &mut S) -> (Vertex, Vector) {
+        match self {
+            Light::Rectangle {
+                corner,
+                edge_u,
+                edge_v,
+                ..
+            } => {
+                let sample = sampler.sample_unit_square();
+                let point = *corner + (sample.x * *edge_u + sample.y * *edge_v);
+                (point, edge_u.cross(*edge_v).normalise())
+            }
+            Light::Disc {
+                center,
+                normal,
+                radius,
+                ..
+            } => {
+                let (u, v) = orthonormal_basis(*normal);
+                let sample = sampler.sample_unit_disk();
+                (*center + *radius * (sample.x * u + sample.y * v), *normal)
+            }
+            Light::Sphere { center, radius, .. } => {
+                let sample = sampler.sample_unit_square();
+                let z = 1.0 - 2.0 * sample.x;
+                let r = (1.0 - z * z).max(0.0).sqrt();
+                let phi = 2.0 * PI * sample.y;
+                let normal = Vector::new(r * phi.cos(), r * phi.sin(), z);
+                (*center + *radius * normal, normal)
+            }
+            Light::Mesh {
+                triangles, areas, ..
+            } => {
+                // The position within the chosen triangle's share of the distribution is
+                // uniform, and used as one of the coordinates in the triangle.
+                let sample = sampler.sample_unit_square();
+                let (x, _, index) = areas.sample(sample.x);
+                let offset = (x * areas.len() as f32 - index as f32).clamp(0.0, 1.0);
+
+                let [a, b, c] = triangles[index];
+                let root = offset.sqrt();
+                let (u, v) = (1.0 - root, sample.y * root);
+                let (ab, ac) = (b.vector - a.vector, c.vector - a.vector);
+                (a + (u * ab + v * ac), ab.cross(ac).normalise())
+            }
+            _ => (self.get_position().unwrap_or_default(), Vector::default()),
+        }
+    }
+
+    /// Point that photons aimed at a target leave the light from, or none for a directional or
+    /// environment light.
+    pub fn sample_position<S: Sampler>(&self, sampler: &mut S) -> Option<Vertex> {
+        match self {
+            Light::Directional { .. } | Light::Environment { .. } => None,
+            Light::Point { position, .. }
+            | Light::Spot { position, .. }
+            | Light::Ies { position, .. } => Some(*position),
+            _ => Some(self.sample_surface(sampler).0),
+        }
+    }
+
+    /// Origin and direction of a photon leaving the light, and its power, which is the light's
+    /// intensity for a photon from a point light. None for a directional light. Point lights
+    /// emit in all directions, spotlights within their cone, IES lights in proportion to their
+    /// profile, and area lights from across their surface with a cosine-weighted distribution
+    /// around its normal. Environment lights send photons in from across a disc facing each
+    /// direction, covering the scene's bounding sphere.
+    pub fn emit_photon<S: Sampler>(
+        &self,
+        sampler: &mut S,
+        scene_bounds: (Vertex, f32),
+    ) -> Option<(Vertex, Vector, Colour)> {
+        let intensity = self.get_intensity();
+
+        if let Light::Environment { .. } = self {
+            let (towards, density) = self.sample_environment(sampler);
+            if density <= 0.0 {
+                return None;
+            }
+            let (center, radius) = scene_bounds;
+            let (u, v) = orthonormal_basis(towards);
+            let disc = sampler.sample_unit_disk();
+            let origin = center + radius * (towards + disc.x * u + disc.y * v);
+
+            // Radiance through the disc, relative to the 4π steradians of a point light.
+            let power = radius * radius / (4.0 * density);
+            return Some((origin, towards.negate(), power * self.radiance(towards)));
+        }
+
+        if self.is_area_light() {
+            let (origin, mut normal) = self.sample_surface(sampler);
+            // Meshes shine from both sides of each triangle.
+            if matches!(self, Light::Mesh { .. }) && rand::random::<bool>() {
+                normal = normal.negate();
+            }
+
+            // The hemisphere samples are around the y axis.
+            let sample = sampler.sample_hemisphere();
+            let (u, v) = orthonormal_basis(normal);
+            return Some((
+                origin,
+                sample.x * u + sample.y * normal + sample.z * v,
+                intensity,
+            ));
+        }
+
+        let origin = self.sample_position(sampler)?;
+        match self {
+            Light::Point { .. } => {
+                let sample = sampler.sample_hemisphere();
+                // Project samples onto a sphere, so that the photons are emitted in all
+                // directions.
+                let sign = if rand::random::<f32>() > 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                };
+                let direction = Vector::new(sample.x, sample.y * sign, sample.z);
+                Some((origin, direction, intensity))
+            }
+            Light::Spot {
+                direction: axis,
+                outer_angle,
+                ..
+            } => {
+                // Uniform over the solid angle of the outer cone, which holds this fraction of
+                // the photons a point light would emit over the whole sphere.
+                let sample = sampler.sample_unit_square();
+                let cos_outer = outer_angle.cos();
+                let cos_theta = 1.0 - sample.x * (1.0 - cos_outer);
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * sample.y;
+
+                let (u, v) = orthonormal_basis(*axis);
+                let direction =
+                    sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * *axis;
+                let fraction = (1.0 - cos_outer) / 2.0;
+                Some((
+                    origin,
+                    direction,
+                    fraction * self.emission(direction) * intensity,
+                ))
+            }
+            Light::Ies { distribution, .. } => {
+                let (point, pdf) = distribution.sample(sampler.sample_unit_square());
+
+                // Uniform over the solid angle of the chosen cell, so that the density of
+                // directions is `pdf` spread over the cell's share of the sphere.
+                let row_position = point.y * IES_GRID_HEIGHT as f32;
+                let row = (row_position as usize).min(IES_GRID_HEIGHT - 1);
+                let top = (row as f32 * PI / IES_GRID_HEIGHT as f32).cos();
+                let cos_vertical = top - (row_position - row as f32) * ies_row_band(row);
+                let vertical = cos_vertical.clamp(-1.0, 1.0).acos();
+                let direction = self.profile_direction(vertical, point.x * 2.0 * PI);
+
+                // Weighted by the profile over the density of directions, relative to the
+                // uniform density of a point light's photons.
+                let power = if pdf > 0.0 {
+                    self.emission(direction) * IES_GRID_HEIGHT as f32 * ies_row_band(row)
+                        / (2.0 * pdf)
+                } else {
+                    0.0
+                };
+                Some((origin, direction, power * intensity))
+            }
+            _ => None,
         }
     }
 }
 
+/// Difference in the cosine of the vertical angle across a row of an IES light's grid, which
+/// its solid angle is proportional to.
+fn ies_row_band(row: usize) -> f32 {
+    let step = PI / IES_GRID_HEIGHT as f32;
+    (row as f32 * step).cos() - ((row + 1) as f32 * step).cos()
+}
+
+/// Two unit vectors perpendicular to each other and to the given normal.
+fn orthonormal_basis(normal: Vector) -> (Vector, Vector) {
+    let helper = if normal.x.abs() > 0.9 {
+        Vector::new(0.0, 1.0, 0.0)
+    } else {
+        Vector::new(1.0, 0.0, 0.0)
+    };
+    let u = normal.cross(helper).normalise();
+    (u, normal.cross(u))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{config::RaytracerConfig, sampling::MultiJitterSampler};
     use approx::assert_relative_eq;
 
+    fn illuminate(light: &Light, surface: Vertex) -> Vec<LightSample> {
+        let mut sampler =
+            MultiJitterSampler::new(light.num_samples(), 1.0, &RaytracerConfig::default());
+        let mut samples = Vec::new();
+        light.illuminate(surface, &mut sampler, |sample| samples.push(sample));
+        samples
+    }
+
     #[test]
     fn test_directional_light_normalizes_direction() {
         let direction = Vector::new(3.0, -4.0, 0.0);
@@ -65,7 +817,7 @@ mod tests {
         let light = Light::new_directional(direction, intensity);
 
         let surface = Vertex::new(0.0, 0.0, 0.0, 1.0);
-        let (pos, dir, lit) = light.get_direction(surface);
+        let (pos, dir, lit) = light.get_direction(surface).unwrap();
 
         assert!(pos.is_none());
         assert!(lit);
@@ -79,7 +831,7 @@ mod tests {
         let light = Light::new_point(position, intensity);
 
         let surface = Vertex::new(0.0, 0.0, 0.0, 1.0);
-        let (light_pos, dir, lit) = light.get_direction(surface);
+        let (light_pos, dir, lit) = light.get_direction(surface).unwrap();
 
         assert!(light_pos.is_some());
         assert!(lit);
@@ -97,8 +849,8 @@ mod tests {
         let surface1 = Vertex::new(0.0, 0.0, 0.0, 1.0);
         let surface2 = Vertex::new(100.0, 200.0, 300.0, 1.0);
 
-        let (_, dir1, _) = light.get_direction(surface1);
-        let (_, dir2, _) = light.get_direction(surface2);
+        let (_, dir1, _) = light.get_direction(surface1).unwrap();
+        let (_, dir2, _) = light.get_direction(surface2).unwrap();
 
         assert_relative_eq!(dir1.x, dir2.x, epsilon = 1e-6);
         assert_relative_eq!(dir1.y, dir2.y, epsilon = 1e-6);
@@ -114,10 +866,248 @@ mod tests {
         let surface1 = Vertex::new(5.0, 0.0, 0.0, 1.0);
         let surface2 = Vertex::new(-5.0, 0.0, 0.0, 1.0);
 
-        let (_, dir1, _) = light.get_direction(surface1);
-        let (_, dir2, _) = light.get_direction(surface2);
+        let (_, dir1, _) = light.get_direction(surface1).unwrap();
+        let (_, dir2, _) = light.get_direction(surface2).unwrap();
 
         // Directions should be different
         assert!(dir1.x != dir2.x);
     }
+
+    #[test]
+    fn test_rectangle_light_samples_lie_on_rectangle() {
+        let intensity = Colour::new(1.0, 1.0, 1.0, 1.0);
+        let light = Light::new_rectangle(
+            Vertex::new(-1.0, 10.0, -1.0, 1.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 2.0),
+            intensity,
+            16,
+        );
+
+        let samples = illuminate(&light, Vertex::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(samples.len(), 16);
+
+        let mut total = 0.0;
+        for sample in &samples {
+            let position = sample.position.unwrap();
+            assert_relative_eq!(position.vector.y, 10.0);
+            assert!(position.vector.x.abs() <= 1.0 && position.vector.z.abs() <= 1.0);
+            assert!(sample.direction.y < 0.0);
+            total += sample.intensity.r;
+        }
+        // Seen from far below, the rectangle's intensity is split evenly between the samples.
+        assert_relative_eq!(total, 1.0, epsilon = 0.02);
+    }
+
+    #[test]
+    fn test_flat_area_lights_are_one_sided() {
+        let config = RaytracerConfig::default();
+        let intensity = Colour::new(1.0, 1.0, 1.0, 1.0);
+        let light = Light::new_disc(
+            Vertex::new(0.0, 10.0, 0.0, 1.0),
+            Vector::new(0.0, -1.0, 0.0),
+            1.0,
+            intensity,
+            4,
+        );
+
+        assert_eq!(illuminate(&light, Vertex::new(0.0, 0.0, 0.0, 1.0)).len(), 4);
+        assert!(illuminate(&light, Vertex::new(0.0, 20.0, 0.0, 1.0)).is_empty());
+        assert!(matches!(
+            light.get_direction(Vertex::new(0.0, 20.0, 0.0, 1.0)),
+            Some((.., false))
+        ));
+
+        // Photons leave from the disc on its lit side.
+        let mut sampler = MultiJitterSampler::new(16, 1.0, &config);
+        for _ in 0..16 {
+            let (origin, direction, _) = light
+                .emit_photon(&mut sampler, (Vertex::default(), 0.0))
+                .unwrap();
+            assert_relative_eq!(origin.vector.y, 10.0);
+            assert!(direction.y <= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_sphere_light_samples_face_surface() {
+        let center = Vertex::new(0.0, 5.0, 0.0, 1.0);
+        let light = Light::new_sphere(center, 2.0, Colour::new(1.0, 1.0, 1.0, 1.0), 9);
+
+        let samples = illuminate(&light, Vertex::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(samples.len(), 9);
+        for sample in samples {
+            let position = sample.position.unwrap();
+            assert_relative_eq!(
+                (position.vector - center.vector).length(),
+                2.0,
+                epsilon = 1e-4
+            );
+            assert!(position.vector.y <= 5.0);
+        }
+    }
+
+    #[test]
+    fn test_mesh_light_samples_lie_on_triangles() {
+        // A unit square at y = 10 split into two triangles.
+        let corners = [
+            Vertex::new(0.0, 10.0, 0.0, 1.0),
+            Vertex::new(1.0, 10.0, 0.0, 1.0),
+            Vertex::new(1.0, 10.0, 1.0, 1.0),
+            Vertex::new(0.0, 10.0, 1.0, 1.0),
+        ];
+        let triangles = vec![
+            [corners[0], corners[1], corners[2]],
+            [corners[0], corners[2], corners[3]],
+        ];
+        let light = Light::new_mesh(triangles, Colour::new(1.0, 1.0, 1.0, 1.0), 16).unwrap();
+
+        assert_relative_eq!(light.get_position().unwrap().vector.x, 0.5);
+        assert_relative_eq!(light.get_position().unwrap().vector.z, 0.5);
+        let samples = illuminate(&light, Vertex::new(0.5, 0.0, 0.5, 1.0));
+        assert_eq!(samples.len(), 16);
+        for sample in &samples {
+            let position = sample.position.unwrap();
+            assert_relative_eq!(position.vector.y, 10.0);
+            assert!((0.0..=1.0).contains(&position.vector.x));
+            assert!((0.0..=1.0).contains(&position.vector.z));
+        }
+        // Mesh surfaces have no outside, so they light both sides.
+        assert_eq!(
+            illuminate(&light, Vertex::new(0.5, 20.0, 0.5, 1.0)).len(),
+            16
+        );
+
+        let flat = [corners[0], corners[1], corners[1]];
+        assert!(Light::new_mesh(vec![flat], Colour::default(), 1).is_none());
+    }
+
+    #[test]
+    fn test_spot_light_cone_falloff() {
+        let config = RaytracerConfig::default();
+        let intensity = Colour::new(1.0, 1.0, 1.0, 1.0);
+        let light = Light::new_spot(
+            Vertex::new(0.0, 10.0, 0.0, 1.0),
+            Vector::new(0.0, -1.0, 0.0),
+            20f32.to_radians(),
+            40f32.to_radians(),
+            intensity,
+        );
+
+        let lit_at = |x: f32| illuminate(&light, Vertex::new(x, 0.0, 0.0, 1.0));
+        // Within the inner cone, between the cones (30 degrees) and outside the outer cone.
+        assert_relative_eq!(lit_at(1.0)[0].intensity.r, 1.0);
+        let fading = lit_at(10.0 * 30f32.to_radians().tan())[0].intensity.r;
+        assert!(fading > 0.0 && fading < 1.0);
+        assert!(lit_at(10.0).is_empty());
+        assert!(matches!(
+            light.get_direction(Vertex::new(10.0, 0.0, 0.0, 1.0)),
+            Some((.., false))
+        ));
+
+        // Photons stay within the outer cone.
+        let mut sampler = MultiJitterSampler::new(16, 1.0, &config);
+        for _ in 0..16 {
+            let (_, direction, power) = light
+                .emit_photon(&mut sampler, (Vertex::default(), 0.0))
+                .unwrap();
+            assert!(direction.y <= -(40f32.to_radians().cos()) + 1e-5);
+            assert!(power.r <= (1.0 - 40f32.to_radians().cos()) / 2.0);
+        }
+    }
+
+    #[test]
+    fn test_inverse_square_attenuation() {
+        let intensity = Colour::new(1.0, 1.0, 1.0, 1.0);
+        let light = Light::new_point(Vertex::new(0.0, 2.0, 0.0, 1.0), intensity);
+        let attenuated = light.clone().with_inverse_square();
+        let surface = Vertex::new(0.0, 0.0, 0.0, 1.0);
+
+        assert_relative_eq!(illuminate(&light, surface)[0].intensity.r, 1.0);
+        assert_relative_eq!(illuminate(&attenuated, surface)[0].intensity.r, 0.25);
+        // Directional lights are too far away to attenuate.
+        let directional = Light::new_directional(Vector::new(0.0, -1.0, 0.0), intensity);
+        assert_eq!(directional.clone().with_inverse_square(), directional);
+    }
+
+    #[test]
+    fn test_ies_light_follows_profile() {
+        let config = RaytracerConfig::default();
+        let profile =
+            IesProfile::parse("TILT=NONE\n1 -1 1 3 1 1 1 0 0 0\n1 1 0\n0 45 90\n0\n100 50 0\n")
+                .unwrap();
+        let light = Light::new_ies(
+            Vertex::new(0.0, 10.0, 0.0, 1.0),
+            Vector::new(0.0, -1.0, 0.0),
+            0.0,
+            profile,
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+        );
+
+        // Straight down, at 45 degrees, at 22.5 degrees and to the side.
+        let lit_at = |x: f32| illuminate(&light, Vertex::new(x, 0.0, 0.0, 1.0));
+        assert_relative_eq!(lit_at(0.0)[0].intensity.r, 1.0);
+        assert_relative_eq!(lit_at(10.0)[0].intensity.r, 0.5, epsilon = 1e-5);
+        let between = lit_at(10.0 * 22.5f32.to_radians().tan())[0].intensity.r;
+        assert_relative_eq!(between, 0.75, epsilon = 1e-5);
+        assert!(matches!(
+            light.get_direction(Vertex::new(0.0, 20.0, 0.0, 1.0)),
+            Some((.., false))
+        ));
+
+        // Photons go where the profile is bright, weighted so that on average they carry the
+        // light's average intensity over the sphere, `1/2 - 1/π` of the peak here.
+        let mut sampler = MultiJitterSampler::new(256, 1.0, &config);
+        let mut total_power = 0.0;
+        for _ in 0..256 {
+            let (_, direction, power) = light
+                .emit_photon(&mut sampler, (Vertex::default(), 0.0))
+                .unwrap();
+            assert!(direction.y < 0.0);
+            total_power += power.r;
+        }
+        assert_relative_eq!(total_power / 256.0, 0.5 - 1.0 / PI, epsilon = 0.005);
+    }
+
+    #[test]
+    fn test_environment_light_samples_bright_texels() {
+        let config = RaytracerConfig::default();
+        // Only the texel covering directions above the horizon, with x and z positive, is lit.
+        let mut pixels = vec![Colour::new(0.0, 0.0, 0.0, 1.0); 8];
+        pixels[2] = Colour::new(5.0, 5.0, 5.0, 1.0);
+        let map = || HdrImage::new(4, 2, pixels.clone());
+        let light = Light::new_environment(map(), 0.0, Colour::new(1.0, 1.0, 1.0, 1.0), 16);
+
+        assert_relative_eq!(light.radiance(Vector::new(1.0, 1.0, 1.0)).r, 5.0);
+        assert_relative_eq!(light.radiance(Vector::new(0.0, -1.0, 0.0)).r, 0.0);
+        // Turning the map a quarter turn moves the lit texel with it.
+        let rotated = Light::new_environment(map(), PI / 2.0, Colour::new(1.0, 1.0, 1.0, 1.0), 1);
+        assert_relative_eq!(rotated.radiance(Vector::new(1.0, 1.0, 1.0)).r, 0.0);
+        assert_relative_eq!(rotated.radiance(Vector::new(1.0, 1.0, -1.0)).r, 5.0);
+
+        // Light arrives from every direction, so there is no single one to give.
+        let surface = Vertex::new(0.0, 0.0, 0.0, 1.0);
+        assert!(light.get_direction(surface).is_none());
+        let samples = illuminate(&light, surface);
+        assert_eq!(samples.len(), 16);
+        let mut total = 0.0;
+        for sample in &samples {
+            assert!(sample.position.is_none());
+            let towards = sample.direction.negate();
+            assert!(towards.x >= 0.0 && towards.y >= 0.0 && towards.z >= 0.0);
+            total += sample.intensity.r;
+        }
+        // The samples add up to the radiance over the texel's quarter of the upper hemisphere.
+        assert_relative_eq!(total, 5.0 * PI / 2.0, epsilon = 0.2);
+
+        // Photons come in from the lit directions, from outside the scene's bounding sphere.
+        let mut sampler = MultiJitterSampler::new(16, 1.0, &config);
+        let bounds = (Vertex::new(0.0, 0.0, 0.0, 1.0), 2.0);
+        for _ in 0..16 {
+            let (origin, direction, power) = light.emit_photon(&mut sampler, bounds).unwrap();
+            assert!(origin.vector.length() >= 2.0 - 1e-4);
+            assert!(direction.y <= 0.0);
+            assert!(power.r > 0.0);
+        }
+    }
 }
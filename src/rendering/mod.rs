@@ -1,10 +1,16 @@
 pub mod cameras;
+pub mod denoiser;
 pub mod framebuffer;
 pub mod light;
+pub mod pixel_filter;
 pub mod raytracer;
+pub mod tone_mapping;
 pub mod traits;
 
+pub use denoiser::Denoiser;
 pub use framebuffer::FrameBuffer;
 pub use light::Light;
+pub use pixel_filter::PixelFilter;
 pub use raytracer::{Raytracer, RenderContext};
+pub use tone_mapping::ToneMapper;
 pub use traits::Camera;
@@ -0,0 +1,162 @@
+use std::f32::consts::PI;
+
+use crate::config::{PixelFilterConfig, PixelFilterKind};
+
+/// Reconstruction filter that weights how much a sample contributes to each pixel near it.
+///
+/// Filters are separable: the weight is the product of the filter at the horizontal and
+/// vertical distances from the sample to the pixel centre. Mitchell-Netravali and Lanczos have
+/// negative lobes, which sharpen the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PixelFilter {
+    Box { radius: f32 },
+    Tent { radius: f32 },
+    Gaussian { radius: f32, alpha: f32 },
+    Mitchell { radius: f32, b: f32, c: f32 },
+    Lanczos { radius: f32 },
+}
+
+impl PixelFilter {
+    pub fn new(config: &PixelFilterConfig) -> Self {
+        let radius = |default: f32| config.radius.unwrap_or(default);
+
+        match config.filter {
+            PixelFilterKind::Box => PixelFilter::Box {
+                radius: radius(0.5),
+            },
+            PixelFilterKind::Tent => PixelFilter::Tent {
+                radius: radius(1.0),
+            },
+            PixelFilterKind::Gaussian => PixelFilter::Gaussian {
+                radius: radius(1.5),
+                alpha: config.gaussian_alpha,
+            },
+            PixelFilterKind::Mitchell => PixelFilter::Mitchell {
+                radius: radius(2.0),
+                b: config.mitchell_b,
+                c: config.mitchell_c,
+            },
+            PixelFilterKind::Lanczos => PixelFilter::Lanczos {
+                radius: radius(3.0),
+            },
+        }
+    }
+
+    /// Distance in pixels beyond which the filter is zero.
+    pub fn radius(&self) -> f32 {
+        match *self {
+            PixelFilter::Box { radius }
+            | PixelFilter::Tent { radius }
+            | PixelFilter::Gaussian { radius, .. }
+            | PixelFilter::Mitchell { radius, .. }
+            | PixelFilter::Lanczos { radius } => radius,
+        }
+    }
+
+    /// Weight of a sample at offset (x, y) in pixels from a pixel centre.
+    pub fn evaluate(&self, x: f32, y: f32) -> f32 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f32) -> f32 {
+        // Half-open, so that a box filter of radius 0.5 covers exactly one pixel.
+        if x < -self.radius() || x >= self.radius() {
+            return 0.0;
+        }
+        let x = x.abs();
+
+        match *self {
+            PixelFilter::Box { .. } => 1.0,
+            PixelFilter::Tent { radius } => radius - x,
+            PixelFilter::Gaussian { radius, alpha } => {
+                // Shifted down so the filter reaches zero at its radius.
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            PixelFilter::Mitchell { radius, b, c } => mitchell(2.0 * x / radius, b, c),
+            PixelFilter::Lanczos { radius } => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+/// Mitchell-Netravali cubic over [0, 2].
+fn mitchell(x: f32, b: f32, c: f32) -> f32 {
+    let (x2, x3) = (x * x, x * x * x);
+
+    let value = if x < 1.0 {
+        (12.0 - 9.0 * b - 6.0 * c) * x3 + (-18.0 + 12.0 * b + 6.0 * c) * x2 + (6.0 - 2.0 * b)
+    } else {
+        (-b - 6.0 * c) * x3
+            + (6.0 * b + 30.0 * c) * x2
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c)
+    };
+
+    value / 6.0
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+    (PI * x).sin() / (PI * x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn filter(kind: PixelFilterKind) -> PixelFilter {
+        PixelFilter::new(&PixelFilterConfig {
+            filter: kind,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_pixel_filter_default_radius() {
+        assert_eq!(filter(PixelFilterKind::Box).radius(), 0.5);
+        assert_eq!(filter(PixelFilterKind::Lanczos).radius(), 3.0);
+
+        let config = PixelFilterConfig {
+            filter: PixelFilterKind::Tent,
+            radius: Some(2.5),
+            ..Default::default()
+        };
+        assert_eq!(PixelFilter::new(&config).radius(), 2.5);
+    }
+
+    #[test]
+    fn test_pixel_filter_weights() {
+        let box_filter = filter(PixelFilterKind::Box);
+        assert_eq!(box_filter.evaluate(0.2, -0.4), 1.0);
+        assert_eq!(box_filter.evaluate(-0.5, 0.0), 1.0);
+        assert_eq!(box_filter.evaluate(0.5, 0.0), 0.0);
+
+        let tent = filter(PixelFilterKind::Tent);
+        assert_relative_eq!(tent.evaluate(0.5, 0.0), 0.5);
+
+        for kind in [
+            PixelFilterKind::Tent,
+            PixelFilterKind::Gaussian,
+            PixelFilterKind::Mitchell,
+            PixelFilterKind::Lanczos,
+        ] {
+            let filter = filter(kind);
+            let radius = filter.radius();
+
+            // Highest at the centre, symmetric and zero at the radius.
+            assert!(filter.evaluate(0.0, 0.0) > filter.evaluate(0.3, 0.0));
+            assert_relative_eq!(filter.evaluate(0.7, 0.2), filter.evaluate(-0.7, -0.2));
+            assert_relative_eq!(filter.evaluate(radius, 0.0), 0.0, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_pixel_filter_negative_lobes() {
+        // With their default radii, both sharpening filters are negative 1.5 pixels away.
+        assert!(filter(PixelFilterKind::Mitchell).evaluate(1.5, 0.0) < 0.0);
+        assert!(filter(PixelFilterKind::Lanczos).evaluate(1.5, 0.0) < 0.0);
+        assert!(filter(PixelFilterKind::Gaussian).evaluate(1.4, 0.0) > 0.0);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     config::RaytracerConfig,
-    primitives::{ray::Ray, Colour},
+    primitives::{ray::Ray, Aovs, Colour},
 };
 
 /// RenderContext provides all necessary data for shading calculations.
@@ -29,6 +29,12 @@ pub trait Raytracer: Sync {
     /// Trace a ray and return its colour and distance
     fn trace(&self, ray: &Ray, recurse_depth: u8) -> (Colour, f32);
 
+    /// Trace a camera ray, also capturing the output variables at its first hit
+    fn trace_aovs(&self, ray: &Ray) -> (Colour, f32, Aovs) {
+        let (colour, depth) = self.trace(ray, 0);
+        (colour, depth, Aovs::default())
+    }
+
     /// Test if a ray is occluded up to a certain distance
     fn is_occluded(&self, ray: &Ray, max_distance: f32) -> bool;
 
@@ -0,0 +1,127 @@
+use crate::{
+    config::{ColourEncoding, ToneMapOperator, ToneMappingConfig},
+    primitives::Colour,
+};
+
+/// Post-process stage that turns linear radiance into display values in [0.0, 1.0].
+///
+/// The colour is scaled by the exposure, compressed into [0.0, 1.0] by the tone mapping
+/// operator and then encoded for display. Every image goes through the same curve, so the
+/// brightness of renders can be compared. Operators work on each channel separately and alpha
+/// is left untouched.
+#[derive(Clone, Copy, Debug)]
+pub struct ToneMapper {
+    scale: f32,
+    config: ToneMappingConfig,
+}
+
+impl ToneMapper {
+    pub fn new(config: &ToneMappingConfig) -> Self {
+        Self {
+            scale: 2.0_f32.powf(config.exposure),
+            config: *config,
+        }
+    }
+
+    pub fn apply(&self, colour: Colour) -> Colour {
+        let map = |value: f32| self.encode(self.tone_map(value * self.scale));
+        Colour::new(map(colour.r), map(colour.g), map(colour.b), colour.a)
+    }
+
+    fn tone_map(&self, value: f32) -> f32 {
+        let value = value.max(0.0);
+
+        let mapped = match self.config.operator {
+            ToneMapOperator::Clamp => value,
+            ToneMapOperator::Reinhard => value / (1.0 + value),
+            ToneMapOperator::ExtendedReinhard => {
+                let white_squared = self.config.white_point * self.config.white_point;
+                value * (1.0 + value / white_squared) / (1.0 + value)
+            }
+            // Krzysztof Narkowicz's fit of the ACES reference rendering transform.
+            ToneMapOperator::AcesFilmic => {
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+        };
+
+        mapped.clamp(0.0, 1.0)
+    }
+
+    fn encode(&self, value: f32) -> f32 {
+        match self.config.encoding {
+            ColourEncoding::Linear => value,
+            ColourEncoding::Srgb => {
+                if value <= 0.003_130_8 {
+                    12.92 * value
+                } else {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            ColourEncoding::Gamma => value.powf(1.0 / self.config.gamma),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn tone_mapper(operator: ToneMapOperator, encoding: ColourEncoding) -> ToneMapper {
+        ToneMapper::new(&ToneMappingConfig {
+            operator,
+            encoding,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_tone_mapping_operators() {
+        let grey = Colour::new(0.5, 1.0, 4.0, 1.0);
+
+        let clamp = tone_mapper(ToneMapOperator::Clamp, ColourEncoding::Linear).apply(grey);
+        assert_eq!((clamp.r, clamp.g, clamp.b), (0.5, 1.0, 1.0));
+
+        let reinhard = tone_mapper(ToneMapOperator::Reinhard, ColourEncoding::Linear).apply(grey);
+        assert_relative_eq!(reinhard.r, 1.0 / 3.0);
+        assert_relative_eq!(reinhard.b, 0.8);
+
+        // The white point maps to exactly 1.
+        let extended =
+            tone_mapper(ToneMapOperator::ExtendedReinhard, ColourEncoding::Linear).apply(grey);
+        assert_relative_eq!(extended.b, 1.0);
+        assert!(extended.g > reinhard.g);
+
+        let aces = tone_mapper(ToneMapOperator::AcesFilmic, ColourEncoding::Linear).apply(grey);
+        assert_relative_eq!(aces.g, 0.8037, epsilon = 1e-4);
+        assert_eq!(aces.a, 1.0);
+    }
+
+    #[test]
+    fn test_tone_mapping_exposure_and_encoding() {
+        let config = ToneMappingConfig {
+            exposure: 1.0,
+            operator: ToneMapOperator::Clamp,
+            encoding: ColourEncoding::Linear,
+            ..Default::default()
+        };
+        let brighter = ToneMapper::new(&config).apply(Colour::new(0.25, 0.0, -1.0, 0.0));
+        assert_relative_eq!(brighter.r, 0.5);
+        assert_eq!(brighter.b, 0.0);
+
+        let srgb = tone_mapper(ToneMapOperator::Clamp, ColourEncoding::Srgb);
+        assert_relative_eq!(
+            srgb.apply(Colour::new(0.5, 0.0, 1.0, 1.0)).r,
+            0.7354,
+            epsilon = 1e-4
+        );
+        assert_relative_eq!(srgb.apply(Colour::new(0.5, 0.0, 1.0, 1.0)).b, 1.0);
+
+        let gamma = tone_mapper(ToneMapOperator::Clamp, ColourEncoding::Gamma);
+        assert_relative_eq!(
+            gamma.apply(Colour::new(0.5, 0.0, 0.0, 1.0)).r,
+            0.7297,
+            epsilon = 1e-4
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use crate::sampling::Point2D;
+
+/// Piecewise constant distribution over [0, 1), split into equal intervals weighted by a
+/// non-negative function.
+///
+/// Pharr, M., Jakob, W. and Humphreys, G. (2016) Physically Based Rendering, 3rd edn.
+/// Section 13.3, Sampling Random Variables.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution1D {
+    function: Vec<f32>,
+    /// Running integral of the function, normalised to end at one.
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    /// The function is treated as uniform if it is zero everywhere.
+    pub fn new(function: Vec<f32>) -> Self {
+        let count = function.len() as f32;
+        let mut cdf = Vec::with_capacity(function.len() + 1);
+        cdf.push(0.0);
+        for value in &function {
+            cdf.push(cdf.last().unwrap() + value.max(0.0) / count);
+        }
+
+        let integral = *cdf.last().unwrap();
+        if integral > 0.0 {
+            cdf.iter_mut().for_each(|value| *value /= integral);
+        } else {
+            for (i, value) in cdf.iter_mut().enumerate() {
+                *value = i as f32 / count;
+            }
+        }
+
+        Self {
+            function,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.function.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.function.is_empty()
+    }
+
+    /// Integral of the function over [0, 1).
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Maps a uniform sample in [0, 1) to the distribution, returning the point, its density
+    /// and the interval it is in.
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        let index = (self.cdf.partition_point(|value| *value <= u) - 1).min(self.len() - 1);
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            (u - self.cdf[index]) / width
+        } else {
+            0.0
+        };
+        let x = ((index as f32 + offset) / self.len() as f32).min(1.0 - f32::EPSILON);
+
+        (x, self.pdf(x), index)
+    }
+
+    /// Density of the distribution at a point in [0, 1).
+    pub fn pdf(&self, x: f32) -> f32 {
+        let index = ((x * self.len() as f32) as usize).min(self.len() - 1);
+        if self.integral > 0.0 {
+            self.function[index].max(0.0) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Piecewise constant distribution over the unit square, from a function given on a grid. It
+/// is sampled by picking a row from the marginal distribution, then a column within the row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// Takes the function's values row by row, with `width` values in each row.
+    pub fn new(function: &[f32], width: usize) -> Self {
+        let rows: Vec<Distribution1D> = function
+            .chunks(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(Distribution1D::integral).collect());
+
+        Self { rows, marginal }
+    }
+
+    /// Integral of the function over the unit square.
+    pub fn integral(&self) -> f32 {
+        self.marginal.integral()
+    }
+
+    /// Maps a uniform sample to the distribution, returning the point (x along the rows, y
+    /// across them) and its density.
+    pub fn sample(&self, sample: Point2D) -> (Point2D, f32) {
+        let (y, row_pdf, row) = self.marginal.sample(sample.y);
+        let (x, column_pdf, _) = self.rows[row].sample(sample.x);
+
+        (Point2D { x, y }, row_pdf * column_pdf)
+    }
+
+    /// Density of the distribution at a point in the unit square.
+    pub fn pdf(&self, point: Point2D) -> f32 {
+        let row = ((point.y * self.rows.len() as f32) as usize).min(self.rows.len() - 1);
+        self.marginal.pdf(point.y) * self.rows[row].pdf(point.x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_distribution_1d_follows_function() {
+        let distribution = Distribution1D::new(vec![1.0, 3.0, 0.0, 0.0]);
+
+        assert_relative_eq!(distribution.integral(), 1.0);
+        // A quarter of the samples fall in the first interval and the rest in the second.
+        let (x, pdf, index) = distribution.sample(0.125);
+        assert_relative_eq!(x, 0.125);
+        assert_relative_eq!(pdf, 1.0);
+        assert_eq!(index, 0);
+        let (x, pdf, index) = distribution.sample(0.625);
+        assert_relative_eq!(x, 0.375);
+        assert_relative_eq!(pdf, 3.0);
+        assert_eq!(index, 1);
+        assert_relative_eq!(distribution.pdf(0.8), 0.0);
+
+        let uniform = Distribution1D::new(vec![0.0, 0.0]);
+        assert_relative_eq!(uniform.sample(0.75).0, 0.75);
+        assert_relative_eq!(uniform.pdf(0.75), 1.0);
+    }
+
+    #[test]
+    fn test_distribution_2d_follows_function() {
+        // Only the bottom-right cell of a 2x2 grid has any weight.
+        let distribution = Distribution2D::new(&[0.0, 0.0, 0.0, 2.0], 2);
+
+        assert_relative_eq!(distribution.integral(), 0.5);
+        for sample in [(0.0, 0.0), (0.5, 0.5), (0.99, 0.99)] {
+            let (point, pdf) = distribution.sample(Point2D {
+                x: sample.0,
+                y: sample.1,
+            });
+            assert!(point.x >= 0.5 && point.y >= 0.5);
+            assert_relative_eq!(pdf, 4.0);
+            assert_relative_eq!(distribution.pdf(point), 4.0);
+        }
+    }
+}
@@ -1,5 +1,7 @@
+pub mod distribution;
 pub mod multi_jitter;
 pub mod traits;
 
+pub use distribution::{Distribution1D, Distribution2D};
 pub use multi_jitter::MultiJitterSampler;
 pub use traits::{Point2D, Sampler};
@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use rand::Rng;
 
 use crate::{
@@ -18,6 +20,11 @@ pub struct MultiJitterSampler {
     jump: u32,
 }
 
+thread_local! {
+    /// Samplers kept by each thread for reuse across calls.
+    static CACHED: RefCell<Vec<MultiJitterSampler>> = const { RefCell::new(Vec::new()) };
+}
+
 impl MultiJitterSampler {
     /// The distribution is controlled by the exponent `e` (how sparse/dense the vectors should be).
     /// The larger the value, the more vectors will be distributed towards the top of the hemisphere.
@@ -45,6 +52,31 @@ impl MultiJitterSampler {
         }
     }
 
+    /// Runs `f` with a sampler for `num_samples` samples (and `e` of 1) that the current thread
+    /// keeps between calls, so that code run for every shaded point doesn't generate a new
+    /// pattern each time. Each call starts on a randomly chosen set.
+    pub fn with_cached<R>(
+        num_samples: u32,
+        config: &RaytracerConfig,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let num_sets = config.sampler.num_sets;
+
+        // Taken out while in use, so that a nested call makes its own.
+        let cached = CACHED.with_borrow_mut(|samplers| {
+            let index = samplers.iter().position(|sampler| {
+                sampler.num_samples == num_samples && sampler.num_sets == num_sets
+            })?;
+            Some(samplers.swap_remove(index))
+        });
+        let mut sampler = cached.unwrap_or_else(|| Self::new(num_samples, 1.0, config));
+        sampler.count = 0;
+
+        let result = f(&mut sampler);
+        CACHED.with_borrow_mut(|samplers| samplers.push(sampler));
+        result
+    }
+
     /// Multi-jittered sampling technique to generate a set of
     /// sample points that are evenly distributed within a unit square.
     ///
@@ -185,6 +217,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sample_unit_disk_in_bounds() {
+        let config = get_test_config();
+        let mut sampler = MultiJitterSampler::new(16, 1.0, &config);
+
+        for _ in 0..16 {
+            let sample = sampler.sample_unit_disk();
+            assert!(sample.x * sample.x + sample.y * sample.y <= 1.0 + 1e-6);
+        }
+    }
+
     #[test]
     fn test_sample_hemisphere_normalized() {
         let config = get_test_config();
@@ -196,4 +239,30 @@ mod tests {
             assert!(length_sq > 0.0);
         }
     }
+
+    #[test]
+    fn test_cached_sampler_is_reused() {
+        let config = get_test_config();
+        let pattern = |sampler: &mut MultiJitterSampler| sampler.samples.as_ptr();
+
+        let first = MultiJitterSampler::with_cached(9, &config, |sampler| {
+            sampler.sample_unit_square();
+            pattern(sampler)
+        });
+        let again = MultiJitterSampler::with_cached(9, &config, |sampler| {
+            assert_eq!(sampler.count, 0);
+            pattern(sampler)
+        });
+        assert_eq!(first, again);
+
+        // A nested call can't share the sampler in use.
+        MultiJitterSampler::with_cached(9, &config, |sampler| {
+            let nested = MultiJitterSampler::with_cached(9, &config, pattern);
+            assert_ne!(nested, pattern(sampler));
+        });
+        assert_eq!(
+            MultiJitterSampler::with_cached(16, &config, |s| s.num_samples),
+            16
+        );
+    }
 }
@@ -14,6 +14,30 @@ pub trait Sampler: Send {
 
     fn sample_hemisphere(&mut self) -> Vector;
 
+    /// Maps the next unit square sample onto the unit disk, keeping the stratification.
+    ///
+    /// Shirley, P. and Chiu, K. (1997) A Low Distortion Map Between Disk and Square.
+    /// Journal of Graphics Tools, 2(3), 45-52.
+    fn sample_unit_disk(&mut self) -> Point2D {
+        let sample = self.sample_unit_square();
+        let (a, b) = (2.0 * sample.x - 1.0, 2.0 * sample.y - 1.0);
+
+        if a == 0.0 && b == 0.0 {
+            return Point2D { x: 0.0, y: 0.0 };
+        }
+
+        let (r, phi) = if a.abs() > b.abs() {
+            (a, (PI / 4.0) * (b / a))
+        } else {
+            (b, (PI / 2.0) - (PI / 4.0) * (a / b))
+        };
+
+        Point2D {
+            x: r * phi.cos(),
+            y: r * phi.sin(),
+        }
+    }
+
     /// Converts 2D sample points into 3D vectors that are distributed over a hemisphere.
     ///
     /// Suffern, K. (2016) Ray Tracing from the Ground Up. CRC Press.
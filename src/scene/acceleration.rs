@@ -0,0 +1,203 @@
+use std::sync::OnceLock;
+
+use crate::{
+    geometry::{
+        traits::{Bounded, Intersection},
+        BVH,
+    },
+    primitives::{ray::Ray, Hit, AABB},
+    SceneObject,
+};
+
+/// Acceleration structure answering ray queries against the objects of a scene.
+///
+/// The bounding volume hierarchy is built from the objects' bounds on first use. With the
+/// hierarchy disabled every query falls back to testing each object in turn, which is useful
+/// for checking that both paths agree.
+#[derive(Debug, Default)]
+pub struct Accelerator {
+    use_bvh: bool,
+    bvh: OnceLock<BVH>,
+}
+
+impl Accelerator {
+    pub fn new(use_bvh: bool) -> Self {
+        Self {
+            use_bvh,
+            bvh: OnceLock::new(),
+        }
+    }
+
+    /// Discards the hierarchy so that it is rebuilt on the next query.
+    /// Must be called whenever objects are added or moved.
+    pub fn invalidate(&mut self) {
+        self.bvh = OnceLock::new();
+    }
+
+    fn bvh(&self, objects: &[SceneObject]) -> &BVH {
+        self.bvh.get_or_init(|| {
+            let bounds: Vec<Option<AABB>> =
+                objects.iter().map(|object| object.bounding_box()).collect();
+
+            BVH::new(&bounds)
+        })
+    }
+
+    /// Finds the nearest first hit (with positive distance) of any object that `accept` allows.
+    /// Returns the hit and the index of the object.
+    pub fn find_hit<F>(&self, objects: &[SceneObject], ray: &Ray, accept: F) -> Option<(Hit, usize)>
+    where
+        F: Fn(&Hit) -> bool,
+    {
+        let mut nearest_hit: Option<(Hit, usize)> = None;
+
+        let mut intersect = |index: usize, max_distance: f32| {
+            let hit = objects[index].closest_hit(ray, 0.0, max_distance)?;
+
+            if accept(&hit) {
+                nearest_hit = Some((hit, index));
+                Some(hit.distance)
+            } else {
+                None
+            }
+        };
+
+        if self.use_bvh {
+            self.bvh(objects)
+                .closest_hit(ray, f32::INFINITY, &mut intersect);
+        } else {
+            let mut max_distance = f32::INFINITY;
+            for index in 0..objects.len() {
+                if let Some(distance) = intersect(index, max_distance) {
+                    max_distance = distance;
+                }
+            }
+        }
+
+        nearest_hit
+    }
+
+    /// Test if any object is hit by the ray closer than `max_distance`.
+    pub fn is_occluded(&self, objects: &[SceneObject], ray: &Ray, max_distance: f32) -> bool {
+        let occludes = |index: usize| objects[index].any_hit(ray, 0.0, max_distance);
+
+        if self.use_bvh {
+            self.bvh(objects).any_hit(ray, max_distance, occludes)
+        } else {
+            (0..objects.len()).any(occludes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::{
+        geometry::{Instance, Plane, Sphere},
+        primitives::{AnimatedTransform, Colour, Transform, Vector, Vertex},
+        Material,
+    };
+
+    fn test_objects() -> Vec<SceneObject> {
+        let material =
+            Material::phong(Colour::default(), Colour::default(), Colour::default(), 1.0);
+
+        let mut objects: Vec<SceneObject> = (0..10)
+            .flat_map(|x| (0..10).map(move |y| (x, y)))
+            .map(|(x, y)| {
+                Sphere::new(
+                    Vertex::new(3.0 * x as f32, 3.0 * y as f32, 10.0 + x as f32, 1.0),
+                    1.0,
+                    material,
+                )
+                .into()
+            })
+            .collect();
+        objects.push(Plane::new(0.0, 0.0, -1.0, 50.0, material).into());
+
+        objects
+    }
+
+    #[test]
+    fn test_bvh_matches_linear_scan() {
+        let objects = test_objects();
+        let bvh = Accelerator::new(true);
+        let linear = Accelerator::new(false);
+
+        for x in 0..30 {
+            for y in 0..30 {
+                let ray = Ray::new(
+                    Vertex::new(x as f32, y as f32, 0.0, 1.0),
+                    Vector::new(0.01 * x as f32, -0.01 * y as f32, 1.0).normalise(),
+                );
+
+                let bvh_hit = bvh.find_hit(&objects, &ray, |_| true);
+                let linear_hit = linear.find_hit(&objects, &ray, |_| true);
+                assert_eq!(
+                    bvh_hit.map(|(hit, index)| (hit.distance, index)),
+                    linear_hit.map(|(hit, index)| (hit.distance, index))
+                );
+
+                for max_distance in [5.0, 10.0, 20.0, f32::INFINITY] {
+                    assert_eq!(
+                        bvh.is_occluded(&objects, &ray, max_distance),
+                        linear.is_occluded(&objects, &ray, max_distance)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_hit_respects_accept() {
+        let objects = test_objects();
+        let accelerator = Accelerator::new(true);
+
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+
+        let (hit, index) = accelerator.find_hit(&objects, &ray, |_| true).unwrap();
+        assert_eq!(index, 0);
+
+        // Rejecting the sphere leaves the plane behind it.
+        let (far_hit, far_index) = accelerator
+            .find_hit(&objects, &ray, |h| h.distance > hit.distance)
+            .unwrap();
+        assert_eq!(far_index, objects.len() - 1);
+        assert!(far_hit.distance > hit.distance);
+    }
+
+    #[test]
+    fn test_bvh_finds_rotating_object_between_keyframes() {
+        let material =
+            Material::phong(Colour::default(), Colour::default(), Colour::default(), 1.0);
+        let sphere = Sphere::new(Vertex::new(10.0, 0.0, 0.0, 1.0), 0.1, material);
+        let (sin, cos) = 170.0_f32.to_radians().sin_cos();
+        let motion = AnimatedTransform::between(
+            0.0,
+            Transform::identity(),
+            1.0,
+            Transform::new([
+                [cos, -sin, 0.0, 0.0],
+                [sin, cos, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+        );
+        let objects = vec![Instance::animated(Arc::new(sphere.into()), motion, material).into()];
+
+        // Grazing the top of the sphere as it swings over the y axis.
+        let ray = Ray::new(
+            Vertex::new(-20.0, 10.09, 0.0, 1.0),
+            Vector::new(1.0, 0.0, 0.0),
+        )
+        .with_time(90.0 / 170.0);
+        assert!(Accelerator::new(false)
+            .find_hit(&objects, &ray, |_| true)
+            .is_some());
+        assert!(Accelerator::new(true)
+            .find_hit(&objects, &ray, |_| true)
+            .is_some());
+    }
+}
@@ -0,0 +1,134 @@
+use serde::Deserialize;
+
+use crate::{config::MotionBlurConfig, primitives::Interpolation};
+
+/// Frames of an animation, read from the `[animation]` section of a scene file.
+///
+/// Frame `n` is at time `start_time + n / frame_rate`, and its shutter stays open for the
+/// fraction `shutter` of a frame from then, blurring anything that moves meanwhile.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timeline {
+    pub frames: u32,
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: f32,
+    #[serde(default)]
+    pub start_time: f32,
+    #[serde(default = "default_shutter")]
+    pub shutter: f32,
+    /// Used for every keyframed value in the scene.
+    #[serde(default)]
+    pub interpolation: Interpolation,
+}
+
+fn default_frame_rate() -> f32 {
+    24.0
+}
+
+fn default_shutter() -> f32 {
+    0.5
+}
+
+impl Timeline {
+    pub fn frame_time(&self, frame: u32) -> f32 {
+        self.start_time + frame as f32 / self.frame_rate
+    }
+
+    /// Shutter interval of a frame.
+    pub fn motion_blur(&self, frame: u32) -> MotionBlurConfig {
+        let time = self.frame_time(frame);
+        MotionBlurConfig {
+            shutter_open: time,
+            shutter_close: time + self.shutter / self.frame_rate,
+        }
+    }
+}
+
+/// A value given at keyframes, such as a position or a colour.
+#[derive(Debug)]
+pub struct Track {
+    /// Sorted by time.
+    keyframes: Vec<(f32, [f32; 3])>,
+    interpolation: Interpolation,
+}
+
+impl Track {
+    /// Takes keyframes in any order, or none if the value isn't animated.
+    pub fn new(mut keyframes: Vec<(f32, [f32; 3])>, interpolation: Interpolation) -> Option<Self> {
+        if keyframes.is_empty() {
+            return None;
+        }
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Some(Self {
+            keyframes,
+            interpolation,
+        })
+    }
+
+    /// The value at the given time, holding still before the first and after the last keyframe.
+    pub fn at(&self, time: f32) -> [f32; 3] {
+        let last = self.keyframes.len() - 1;
+        if time <= self.keyframes[0].0 {
+            return self.keyframes[0].1;
+        }
+        if time >= self.keyframes[last].0 {
+            return self.keyframes[last].1;
+        }
+
+        let next = self
+            .keyframes
+            .partition_point(|(key_time, _)| *key_time <= time);
+        let keys = [
+            self.keyframes[(next - 1).saturating_sub(1)],
+            self.keyframes[next - 1],
+            self.keyframes[next],
+            self.keyframes[(next + 1).min(last)],
+        ];
+        let times = keys.map(|(key_time, _)| key_time);
+        let t = (time - times[1]) / (times[2] - times[1]);
+
+        std::array::from_fn(|i| {
+            self.interpolation
+                .blend(times, keys.map(|(_, value)| value[i]), t)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_timeline_frames() {
+        let timeline: Timeline = toml::from_str("frames = 48\nstart_time = 1.0").unwrap();
+
+        assert_relative_eq!(timeline.frame_time(12), 1.5);
+        let shutter = timeline.motion_blur(12);
+        assert_relative_eq!(shutter.shutter_open, 1.5);
+        assert_relative_eq!(shutter.shutter_close, 1.5 + 0.5 / 24.0);
+    }
+
+    #[test]
+    fn test_track_interpolation() {
+        let keyframes = vec![
+            (2.0, [4.0, 0.0, 0.0]),
+            (0.0, [0.0, 0.0, 0.0]),
+            (1.0, [1.0, 2.0, 0.0]),
+        ];
+        let linear = Track::new(keyframes.clone(), Interpolation::Linear).unwrap();
+        let spline = Track::new(keyframes, Interpolation::Spline).unwrap();
+
+        assert_eq!(linear.at(-1.0), [0.0, 0.0, 0.0]);
+        assert_eq!(linear.at(5.0), [4.0, 0.0, 0.0]);
+        assert_relative_eq!(linear.at(1.5)[0], 2.5);
+        assert_relative_eq!(linear.at(1.5)[1], 1.0);
+
+        // The spline passes through the keyframes and rounds off the peak between them.
+        assert_relative_eq!(spline.at(1.0)[1], 2.0);
+        assert!(spline.at(1.25)[1] > linear.at(1.25)[1]);
+
+        assert!(Track::new(Vec::new(), Interpolation::Linear).is_none());
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
     config::RaytracerConfig,
-    geometry::traits::Intersection,
-    primitives::{ray::Ray, Colour, Hit, Vector, Vertex},
+    primitives::{ray::Ray, Aovs, Colour, Hit, Vector, Vertex},
+    sampling::MultiJitterSampler,
+    scene::{acceleration::Accelerator, material_ids::MaterialIds},
     shading::traits::Shader,
     Light, Material, Raytracer, SceneBuilder, SceneObject,
 };
@@ -9,7 +10,11 @@ use crate::{
 pub struct Scene<'a> {
     pub objects: Vec<SceneObject>,
     pub lights: Vec<Light>,
+    /// Lights given off by emissive objects.
+    pub emitters: Vec<Light>,
     pub config: &'a RaytracerConfig,
+    accelerator: Accelerator,
+    material_ids: MaterialIds,
 }
 
 impl<'a> Scene<'a> {
@@ -17,24 +22,48 @@ impl<'a> Scene<'a> {
         Self {
             objects: Vec::new(),
             lights: Vec::new(),
+            emitters: Vec::new(),
             config,
+            accelerator: Accelerator::new(config.acceleration.use_bvh),
+            material_ids: MaterialIds::default(),
         }
     }
 
-    /// Trace and determine the nearest ray's hit in front of the camera.
-    /// Returns the hit and a reference to the material.
-    fn find_hit(&self, ray: &Ray) -> Option<(Hit, &Material)> {
-        let mut nearest_hit: Option<(Hit, &Material)> = None;
-
-        for object in &self.objects {
-            if let Some(hit) = object.first_hit(ray) {
-                if nearest_hit.is_none() || hit.distance < nearest_hit.unwrap().0.distance {
-                    nearest_hit = Some((hit, object.material()));
-                }
-            }
+    /// Switches to the config of another frame of an animation. Emissive objects that move
+    /// between the shutter intervals of the two frames take their light with them.
+    pub fn set_config(&mut self, config: &'a RaytracerConfig) {
+        let (old, new) = (self.config.motion_blur, config.motion_blur);
+        let start = old.shutter_open.min(new.shutter_open);
+        let end = old.shutter_close.max(new.shutter_close);
+
+        if old != new
+            && self
+                .objects
+                .iter()
+                .any(|object| object.moves_between(start, end))
+        {
+            self.emitters = self
+                .objects
+                .iter()
+                .filter_map(|object| Light::from_emissive_object(object, new.shutter_open))
+                .collect();
+        }
+        self.config = config;
+    }
+
+    /// Light seen by a ray that leaves the scene, from any environment lights.
+    fn background(&self, direction: Vector) -> Colour {
+        let mut colour = Colour::default();
+        for light in &self.lights {
+            colour += light.radiance(direction);
         }
+        colour
+    }
 
-        nearest_hit
+    /// Trace and determine the nearest ray's hit in front of the camera.
+    /// Returns the hit and the index of the object.
+    fn find_hit(&self, ray: &Ray) -> Option<(Hit, usize)> {
+        self.accelerator.find_hit(&self.objects, ray, |_| true)
     }
 
     /// Determine if a hit point is in shadow.
@@ -43,6 +72,7 @@ impl<'a> Scene<'a> {
         hit_position: Vertex,
         light_position: Option<Vertex>,
         light_direction: Vector,
+        time: f32,
     ) -> bool {
         let rounding_error = self.config.objects.rounding_error;
         let to_light_direction = light_direction.negate();
@@ -51,33 +81,44 @@ impl<'a> Scene<'a> {
         let shadow_ray = Ray::new(
             hit_position + rounding_error * to_light_direction,
             to_light_direction,
-        );
+        )
+        .with_time(time);
 
+        // Stop short of the light, so that the surface of an area light doesn't shadow itself.
         let shadow_limit = light_position
-            .map(|light_position| (light_position.vector - shadow_ray.position.vector).length())
+            .map(|light_position| {
+                (light_position.vector - shadow_ray.position.vector).length() - rounding_error
+            })
             .unwrap_or(f32::INFINITY);
 
         self.is_occluded(&shadow_ray, shadow_limit)
     }
 
-    /// Compute contribution of all lights to the hit point.
-    fn compute_lighting(&self, hit: &Hit, material: &Material) -> Colour {
+    /// Compute contribution of all lights to the hit point, as seen at the given time.
+    fn compute_lighting(&self, hit: &Hit, material: &Material, time: f32) -> Colour {
         let mut colour = Colour::new(0.0, 0.0, 0.0, 0.0);
 
-        for light in &self.lights {
-            let viewer_direction = (-hit.position.vector).normalise();
-            let (light_position, light_direction, is_lit) = light.get_direction(hit.position);
-
-            // Skip lights that are facing the wrong direction.
-            if light_direction.dot(hit.normal) > 0.0 {
-                continue;
-            }
-
-            if is_lit && !self.is_point_in_shadow(hit.position, light_position, light_direction) {
-                let intensity = light.get_intensity();
-                colour += intensity
-                    * material.shade_light(self, &viewer_direction, &light_direction, hit);
-            }
+        let viewer_direction = (-hit.position.vector).normalise();
+        for light in self.lights.iter().chain(&self.emitters) {
+            // Area lights are sampled at several points, which softens the edges of shadows.
+            MultiJitterSampler::with_cached(light.num_samples(), self.config, |sampler| {
+                light.illuminate(hit.position, sampler, |sample| {
+                    // Skip lights that are facing the wrong direction.
+                    if sample.direction.dot(hit.normal) > 0.0 {
+                        return;
+                    }
+
+                    if !self.is_point_in_shadow(
+                        hit.position,
+                        sample.position,
+                        sample.direction,
+                        time,
+                    ) {
+                        colour += sample.intensity
+                            * material.shade_light(self, &viewer_direction, &sample.direction, hit);
+                    }
+                });
+            });
         }
 
         colour
@@ -94,29 +135,58 @@ impl<'a> Raytracer for Scene<'a> {
         let mut colour = Colour::new(0.0, 0.0, 0.0, 0.0);
         let mut depth = 0.0;
 
-        if let Some((hit, material)) = self.find_hit(ray) {
+        if let Some((hit, index)) = self.find_hit(ray) {
+            let material = self.objects[index].material();
             depth = hit.distance;
 
             // Compute direct material contribution (ambient/emission).
             colour += material.shade_ambient(self, ray, &hit, recurse_depth);
 
             // Calculate contributions from lights.
-            colour += self.compute_lighting(&hit, material);
+            colour += self.compute_lighting(&hit, material, ray.time);
+        } else {
+            colour = self.background(ray.direction);
         }
 
         (colour, depth)
     }
 
-    fn is_occluded(&self, ray: &Ray, max_distance: f32) -> bool {
-        for object in &self.objects {
-            if let Some(hit) = object.first_hit(ray) {
-                if 0.0 < hit.distance && hit.distance < max_distance {
-                    return true;
-                }
-            }
+    fn trace_aovs(&self, ray: &Ray) -> (Colour, f32, Aovs) {
+        if self.config.camera.raytrace_recurse == 0 {
+            return (Colour::default(), 0.0, Aovs::default());
         }
+        let Some((hit, index)) = self.find_hit(ray) else {
+            let background = self.background(ray.direction);
+            let aovs = Aovs {
+                indirect: background,
+                ..Default::default()
+            };
+            return (background, 0.0, aovs);
+        };
+        let material = self.objects[index].material();
+
+        let indirect = material.shade_ambient(self, ray, &hit, 0);
+        let direct = self.compute_lighting(&hit, material, ray.time);
+
+        let aovs = Aovs {
+            normal: hit.normal,
+            albedo: material.albedo(),
+            object_id: Some(index as u32),
+            material_id: self.material_ids.get(index),
+            position: hit.position.vector,
+            direct,
+            indirect,
+            photon: Colour::default(),
+            // Set by the camera.
+            ..Default::default()
+        };
+
+        (indirect + direct, hit.distance, aovs)
+    }
 
-        false
+    fn is_occluded(&self, ray: &Ray, max_distance: f32) -> bool {
+        self.accelerator
+            .is_occluded(&self.objects, ray, max_distance)
     }
 
     fn config(&self) -> &RaytracerConfig {
@@ -126,13 +196,30 @@ impl<'a> Raytracer for Scene<'a> {
 
 impl<'a> SceneBuilder for Scene<'a> {
     fn add_object(&mut self, object: SceneObject) {
+        let time = self.config.motion_blur.shutter_open;
+        self.emitters
+            .extend(Light::from_emissive_object(&object, time));
+        self.material_ids.push(object.material());
         self.objects.push(object);
+        self.accelerator.invalidate();
     }
 
     fn add_light(&mut self, light: Light) {
         self.lights.push(light);
     }
 
+    fn light(&self, index: usize) -> &Light {
+        &self.lights[index]
+    }
+
+    fn set_light(&mut self, index: usize, light: Light) {
+        self.lights[index] = light;
+    }
+
+    fn num_lights(&self) -> usize {
+        self.lights.len()
+    }
+
     fn config(&self) -> &RaytracerConfig {
         self.config
     }
@@ -141,7 +228,7 @@ impl<'a> SceneBuilder for Scene<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::geometry::sphere::Sphere;
+    use crate::{geometry::sphere::Sphere, utilities::hdr_reader::HdrImage};
 
     fn test_config() -> RaytracerConfig {
         RaytracerConfig::default()
@@ -214,4 +301,195 @@ mod tests {
         assert!(scene.is_occluded(&ray, 10.0));
         assert!(!scene.is_occluded(&ray, 2.0)); // Too short to reach sphere
     }
+
+    #[test]
+    fn test_scene_trace_aovs() {
+        let config = test_config();
+        let mut scene = Scene::new(&config);
+
+        let red = Material::phong(
+            Colour::new(0.1, 0.0, 0.0, 1.0),
+            Colour::new(0.8, 0.0, 0.0, 1.0),
+            Colour::default(),
+            1.0,
+        );
+        let mirror = Material::reflective(1.0);
+        scene.add_object(Sphere::new(Vertex::new(5.0, 0.0, 5.0, 1.0), 1.0, red).into());
+        scene.add_object(Sphere::new(Vertex::new(0.0, 0.0, 5.0, 1.0), 1.0, red).into());
+        scene.add_object(Sphere::new(Vertex::new(-5.0, 0.0, 5.0, 1.0), 1.0, mirror).into());
+        scene.add_light(Light::new_directional(
+            Vector::new(0.0, 0.0, 1.0),
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+        ));
+
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        let (colour, depth, aovs) = scene.trace_aovs(&ray);
+
+        // The beauty is the same as a plain trace, split into direct and indirect light.
+        let (traced_colour, traced_depth) = scene.trace(&ray, 0);
+        assert_eq!(colour, traced_colour);
+        assert_eq!(depth, traced_depth);
+        assert_eq!(aovs.indirect, Colour::new(0.1, 0.0, 0.0, 1.0));
+        assert_eq!(aovs.direct + aovs.indirect, colour);
+
+        assert_eq!(aovs.normal, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(aovs.position, Vector::new(0.0, 0.0, 4.0));
+        assert_eq!(aovs.albedo, Colour::new(0.8, 0.0, 0.0, 1.0));
+        assert_eq!(aovs.object_id, Some(1));
+        assert_eq!(aovs.material_id, Some(0));
+
+        let miss = Ray::new(Vertex::new(0.0, 10.0, 0.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(scene.trace_aovs(&miss).2.object_id, None);
+    }
+
+    #[test]
+    fn test_scene_area_light_soft_shadow() {
+        let config = test_config();
+        let white = Material::phong(
+            Colour::default(),
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+            Colour::default(),
+            1.0,
+        );
+        let light = Light::new_rectangle(
+            Vertex::new(-1.0, 10.0, 0.0, 1.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 2.0),
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+            16,
+        );
+        let hit = Hit::new(
+            1.0,
+            true,
+            Vertex::new(0.0, 0.0, 1.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let mut open_scene = Scene::new(&config);
+        open_scene.add_light(light.clone());
+        let unoccluded = open_scene.compute_lighting(&hit, &white, 0.0);
+
+        // An occluder covering about half of the light leaves the point in partial shadow.
+        let mut scene = Scene::new(&config);
+        scene.add_light(light);
+        scene.add_object(Sphere::new(Vertex::new(-1.0, 5.0, 1.0, 1.0), 1.0, white).into());
+        let penumbra = scene.compute_lighting(&hit, &white, 0.0);
+
+        assert!(unoccluded.r > 0.0);
+        assert!(penumbra.r > 0.2 * unoccluded.r && penumbra.r < 0.8 * unoccluded.r);
+    }
+
+    #[test]
+    fn test_scene_emissive_object_lights_scene() {
+        let config = test_config();
+        let white = Material::phong(
+            Colour::default(),
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+            Colour::default(),
+            1.0,
+        );
+        let glow = Colour::new(2.0, 2.0, 2.0, 1.0);
+        let mut scene = Scene::new(&config);
+        scene.add_object(Sphere::new(Vertex::new(0.0, 5.0, 1.0, 1.0), 1.0, white).into());
+        scene.add_object(
+            Sphere::new(
+                Vertex::new(0.0, 5.0, 10.0, 1.0),
+                1.0,
+                Material::emissive(glow, 4),
+            )
+            .into(),
+        );
+
+        assert!(scene.lights.is_empty());
+        assert_eq!(scene.emitters.len(), 1);
+
+        // The emitter is seen at its own radiance.
+        let ray = Ray::new(Vertex::new(0.0, 5.0, 8.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(scene.trace(&ray, 0).0.r, 2.0);
+
+        // And lights a point facing it.
+        let hit = Hit::new(
+            1.0,
+            true,
+            Vertex::new(0.0, 5.0, 6.0, 1.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        assert!(scene.compute_lighting(&hit, &white, 0.0).r > 0.0);
+    }
+
+    #[test]
+    fn test_scene_emitters_move_with_their_objects() {
+        use crate::{
+            geometry::instance::Instance,
+            primitives::{AnimatedTransform, Transform},
+        };
+        use approx::assert_relative_eq;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let mut later = config;
+        later.motion_blur.shutter_open = 1.0;
+        later.motion_blur.shutter_close = 1.0;
+
+        let lamp = Material::emissive(Colour::new(2.0, 2.0, 2.0, 1.0), 4);
+        let sphere: SceneObject = Sphere::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0, lamp).into();
+        let moved = Transform::new([
+            [1.0, 0.0, 0.0, 10.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let motion = AnimatedTransform::between(0.0, Transform::identity(), 1.0, moved);
+
+        let mut scene = Scene::new(&config);
+        scene.add_object(Instance::animated(Arc::new(sphere), motion, lamp).into());
+        let center = |scene: &Scene| match scene.emitters[..] {
+            [Light::Mesh { center, .. }] => center.vector.x,
+            _ => panic!("expected a mesh light"),
+        };
+        assert_relative_eq!(center(&scene), 0.0, epsilon = 1e-3);
+
+        scene.set_config(&later);
+        assert_relative_eq!(center(&scene), 10.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_scene_environment_light() {
+        let config = test_config();
+        let white = Material::phong(
+            Colour::default(),
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+            Colour::default(),
+            1.0,
+        );
+        // Blue above the horizon and dark below.
+        let pixels = vec![
+            Colour::new(0.0, 0.0, 1.0, 1.0),
+            Colour::new(0.0, 0.0, 1.0, 1.0),
+            Colour::new(0.0, 0.0, 0.0, 1.0),
+            Colour::new(0.0, 0.0, 0.0, 1.0),
+        ];
+        let mut scene = Scene::new(&config);
+        scene.add_light(Light::new_environment(
+            HdrImage::new(2, 2, pixels),
+            0.0,
+            Colour::new(2.0, 2.0, 2.0, 1.0),
+            16,
+        ));
+
+        // Rays that miss everything see the map.
+        let up = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(scene.trace(&up, 0).0.b, 2.0);
+        let (colour, _, aovs) = scene.trace_aovs(&up);
+        assert_eq!(colour.b, 2.0);
+        assert_eq!(aovs.indirect, colour);
+
+        // A surface facing the sky is lit by it, and one facing the ground isn't.
+        let facing = |normal: Vector| {
+            let hit = Hit::new(1.0, true, Vertex::new(0.0, 0.0, 0.0, 1.0), normal);
+            scene.compute_lighting(&hit, &white, 0.0)
+        };
+        assert!(facing(Vector::new(0.0, 1.0, 0.0)).b > 0.0);
+        assert_eq!(facing(Vector::new(0.0, -1.0, 0.0)).b, 0.0);
+    }
 }
@@ -0,0 +1,2242 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::Deserialize;
+use thiserror::Error as ThiserrorError;
+
+use crate::{
+    config::RaytracerConfig,
+    geometry::{
+        csg::Mode, quadratic::QuadraticCoefficients, traits::Transformable, Instance, Plane,
+        PolyMesh, Quadratic, Sphere, CSG,
+    },
+    primitives::{AnimatedTransform, Colour, Interpolation, Transform, Vector, Vertex, AABB},
+    rendering::cameras::{
+        EquirectangularCamera, FisheyeCamera, FisheyeProjection, FullCamera, OrthographicCamera,
+        SamplingCamera, SceneCamera, SimpleCamera, ThinLensCamera,
+    },
+    scene::animation::{Timeline, Track},
+    utilities::{
+        cornell_box::setup_cornell_box,
+        hdr_reader::{HdrError, HdrImage},
+        ies_reader::{IesError, IesProfile},
+    },
+    Light, Material, SceneBuilder, SceneObject,
+};
+
+#[derive(Debug, ThiserrorError)]
+pub enum SceneFileError {
+    #[error("Could not read scene file {}: {source}", path.display())]
+    ReadError { path: PathBuf, source: io::Error },
+
+    #[error("Invalid scene file: {0}")]
+    ParseError(#[from] toml::de::Error),
+
+    #[error("Invalid config override: {0}")]
+    ConfigError(String),
+
+    #[error("{context}: unknown material '{name}'.")]
+    UnknownMaterial { context: String, name: String },
+
+    #[error("{context}: no material given.")]
+    MissingMaterial { context: String },
+
+    #[error("{context}: could not load mesh {}: {source}", path.display())]
+    MeshError {
+        context: String,
+        path: PathBuf,
+        source: io::Error,
+    },
+
+    #[error("{context}: could not load IES profile {}: {source}", path.display())]
+    ProfileError {
+        context: String,
+        path: PathBuf,
+        source: IesError,
+    },
+
+    #[error("{context}: could not load environment map {}: {source}", path.display())]
+    EnvironmentMapError {
+        context: String,
+        path: PathBuf,
+        source: HdrError,
+    },
+
+    #[error("{context}: {message}")]
+    InvalidValue { context: String, message: String },
+
+    #[error("Scene file has no [camera] section.")]
+    MissingCamera,
+}
+
+/// Declarative description of a scene, read from a TOML file.
+///
+/// ```toml
+/// [config]                       # Overrides for any `RaytracerConfig` value.
+/// camera.num_camera_ray_samples = 4
+///
+/// [camera]
+/// type = "thin_lens"             # simple, full, sampling, thin_lens, orthographic, fisheye
+///                                # or equirectangular.
+/// position = [0.0, 7.0, 0.0]
+/// lookat = [0.0, -5.0, 20.0]
+/// aperture = 0.2                 # thin_lens: aperture, and optionally focus_distance or
+/// focus_point = [0.0, 0.0, 10.0] # focus_point, blades and blade_rotation.
+///                                # orthographic: view_width.
+///                                # fisheye: field_of_view, and projection (equidistant or
+///                                # equisolid).
+///
+/// [materials.glass]
+/// type = "global"                # phong, global, reflective, transparent,
+///                                # ambient_occlusion or emissive.
+/// reflect = [1.0, 1.0, 1.0]
+/// refract = [1.0, 1.0, 1.0]
+/// ior = 1.52
+///
+/// [[objects]]
+/// type = "sphere"                # sphere, plane, quadratic, polymesh or csg.
+/// center = [0.0, 0.0, 10.0]
+/// radius = 3.0
+/// material = "glass"
+/// transform = [{ scale = [2.0, 1.0, 1.0] }, { rotate_y = 45.0 }]
+///
+/// [[lights]]
+/// type = "point"                 # directional, point, spot, ies, rectangle, disc, sphere or
+///                                # environment.
+/// position = [0.0, 10.0, 0.0]    # directional: direction.
+///                                # spot: position, direction, inner_angle and outer_angle.
+///                                # ies: position and profile, and optionally direction and
+///                                # rotation.
+///                                # rectangle: corner, edge_u and edge_v.
+///                                # disc: center, normal and radius.
+///                                # sphere: center and radius.
+///                                # environment: map, and optionally rotation.
+///                                # Area and environment lights also take samples.
+/// intensity = [1.0, 1.0, 1.0]    # Optional for environment lights.
+/// inverse_square = true          # point, spot and ies only.
+/// keyframes = [{ time = 1.0, position = [5.0, 10.0, 0.0] }]
+///
+/// [animation]                    # Frames rendered with `--animate`.
+/// frames = 48
+/// frame_rate = 24.0
+/// interpolation = "spline"       # linear or spline.
+/// ```
+///
+/// Paths in the file are relative to the file itself.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    #[serde(default)]
+    pub config: toml::Table,
+    pub camera: Option<CameraDescription>,
+    /// Adds the walls and light of the Cornell box, sized by the `cornell_box` config.
+    #[serde(default)]
+    pub cornell_box: bool,
+    #[serde(default)]
+    pub materials: HashMap<String, MaterialDescription>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription>,
+    #[serde(default)]
+    pub lights: Vec<LightDescription>,
+    /// Frames to render as an animation, and how keyframes are interpolated.
+    pub animation: Option<Timeline>,
+
+    /// Directory that relative paths are resolved against.
+    #[serde(skip)]
+    base_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CameraDescription {
+    Simple {
+        #[serde(default = "default_fov")]
+        fov: f32,
+    },
+    Full {
+        #[serde(default = "default_fov")]
+        fov: f32,
+        position: [f32; 3],
+        lookat: [f32; 3],
+        #[serde(default = "default_up")]
+        up: [f32; 3],
+        #[serde(default)]
+        keyframes: Vec<CameraKeyframe>,
+    },
+    Sampling {
+        #[serde(default = "default_fov")]
+        fov: f32,
+        position: [f32; 3],
+        lookat: [f32; 3],
+        #[serde(default = "default_up")]
+        up: [f32; 3],
+        #[serde(default)]
+        keyframes: Vec<CameraKeyframe>,
+        /// Defaults to `camera.num_camera_ray_samples` from the config.
+        samples: Option<u32>,
+    },
+    ThinLens {
+        #[serde(default = "default_fov")]
+        fov: f32,
+        position: [f32; 3],
+        lookat: [f32; 3],
+        #[serde(default = "default_up")]
+        up: [f32; 3],
+        #[serde(default)]
+        keyframes: Vec<CameraKeyframe>,
+        /// Defaults to `camera.num_camera_ray_samples` from the config.
+        samples: Option<u32>,
+        aperture: f32,
+        /// Distance to the plane in focus. Without it, the camera focuses on `focus_point`.
+        focus_distance: Option<f32>,
+        /// Point to focus on, defaulting to `lookat`.
+        focus_point: Option<[f32; 3]>,
+        /// Number of aperture blades, for a polygonal aperture.
+        #[serde(default)]
+        blades: u32,
+        /// Rotation of the aperture blades in degrees.
+        #[serde(default)]
+        blade_rotation: f32,
+    },
+    Orthographic {
+        position: [f32; 3],
+        lookat: [f32; 3],
+        #[serde(default = "default_up")]
+        up: [f32; 3],
+        #[serde(default)]
+        keyframes: Vec<CameraKeyframe>,
+        /// Width of the viewed area in world units.
+        view_width: f32,
+        /// Defaults to `camera.num_camera_ray_samples` from the config.
+        samples: Option<u32>,
+    },
+    Fisheye {
+        position: [f32; 3],
+        lookat: [f32; 3],
+        #[serde(default = "default_up")]
+        up: [f32; 3],
+        #[serde(default)]
+        keyframes: Vec<CameraKeyframe>,
+        /// Angle across the image circle in degrees.
+        #[serde(default = "default_fisheye_field_of_view")]
+        field_of_view: f32,
+        #[serde(default)]
+        projection: FisheyeProjection,
+        /// Defaults to `camera.num_camera_ray_samples` from the config.
+        samples: Option<u32>,
+    },
+    Equirectangular {
+        position: [f32; 3],
+        lookat: [f32; 3],
+        #[serde(default = "default_up")]
+        up: [f32; 3],
+        #[serde(default)]
+        keyframes: Vec<CameraKeyframe>,
+        /// Defaults to `camera.num_camera_ray_samples` from the config.
+        samples: Option<u32>,
+    },
+}
+
+impl CameraDescription {
+    /// Samples per pixel of the cameras that take several.
+    pub fn samples_mut(&mut self) -> Option<&mut Option<u32>> {
+        match self {
+            CameraDescription::Sampling { samples, .. }
+            | CameraDescription::ThinLens { samples, .. }
+            | CameraDescription::Orthographic { samples, .. }
+            | CameraDescription::Fisheye { samples, .. }
+            | CameraDescription::Equirectangular { samples, .. } => Some(samples),
+            _ => None,
+        }
+    }
+
+    /// Position, look-at point and keyframes of the cameras that have them.
+    fn view_mut(&mut self) -> Option<(&mut [f32; 3], &mut [f32; 3], &[CameraKeyframe])> {
+        match self {
+            CameraDescription::Simple { .. } => None,
+            CameraDescription::Full {
+                position,
+                lookat,
+                keyframes,
+                ..
+            }
+            | CameraDescription::Sampling {
+                position,
+                lookat,
+                keyframes,
+                ..
+            }
+            | CameraDescription::ThinLens {
+                position,
+                lookat,
+                keyframes,
+                ..
+            }
+            | CameraDescription::Orthographic {
+                position,
+                lookat,
+                keyframes,
+                ..
+            }
+            | CameraDescription::Fisheye {
+                position,
+                lookat,
+                keyframes,
+                ..
+            }
+            | CameraDescription::Equirectangular {
+                position,
+                lookat,
+                keyframes,
+                ..
+            } => Some((position, lookat, keyframes)),
+        }
+    }
+}
+
+/// Camera placement at a point in time. Values left out follow the other keyframes, or stay
+/// as given by the camera.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraKeyframe {
+    pub time: f32,
+    pub position: Option<[f32; 3]>,
+    pub lookat: Option<[f32; 3]>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialDescription {
+    Phong {
+        ambient: [f32; 3],
+        diffuse: [f32; 3],
+        specular: [f32; 3],
+        power: f32,
+    },
+    Global {
+        reflect: [f32; 3],
+        refract: [f32; 3],
+        ior: f32,
+    },
+    Reflective {
+        reflectivity: f32,
+    },
+    Transparent {
+        transparency: f32,
+        ior: f32,
+    },
+    AmbientOcclusion {
+        ambient: [f32; 3],
+        samples: u32,
+        min_ambient: f32,
+    },
+    /// Gives off light. Spheres and meshes with it also light the scene, sampled with
+    /// `samples` shadow rays per shaded point.
+    Emissive {
+        radiance: [f32; 3],
+        #[serde(default = "default_light_samples")]
+        samples: u32,
+    },
+}
+
+/// Objects refer to materials by name. The children of a CSG object may leave out the material,
+/// in which case they share their parent's.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObjectDescription {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: Option<String>,
+        #[serde(default)]
+        transform: Vec<TransformStep>,
+        #[serde(default)]
+        motion: Vec<MotionKeyframe>,
+    },
+    /// Plane with the equation `ax + by + cz + d = 0`, given as `[a, b, c, d]`.
+    Plane {
+        equation: [f32; 4],
+        material: Option<String>,
+        #[serde(default)]
+        transform: Vec<TransformStep>,
+        #[serde(default)]
+        motion: Vec<MotionKeyframe>,
+    },
+    Quadratic {
+        coefficients: QuadraticCoefficients,
+        /// Box the surface is clipped to.
+        bounds: Option<BoundsDescription>,
+        material: Option<String>,
+        #[serde(default)]
+        transform: Vec<TransformStep>,
+        #[serde(default)]
+        motion: Vec<MotionKeyframe>,
+    },
+    Polymesh {
+        path: PathBuf,
+        #[serde(default)]
+        smooth: bool,
+        material: Option<String>,
+        #[serde(default)]
+        transform: Vec<TransformStep>,
+        #[serde(default)]
+        motion: Vec<MotionKeyframe>,
+    },
+    Csg {
+        mode: CsgMode,
+        left: Box<ObjectDescription>,
+        right: Box<ObjectDescription>,
+        material: Option<String>,
+        #[serde(default)]
+        transform: Vec<TransformStep>,
+        #[serde(default)]
+        motion: Vec<MotionKeyframe>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoundsDescription {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsgMode {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Placement of a moving object at a point in time, applied after its static transform.
+/// Between keyframes, the placement is interpolated.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MotionKeyframe {
+    pub time: f32,
+    #[serde(default)]
+    pub transform: Vec<TransformStep>,
+}
+
+/// One step of an object's transform. Steps are applied in the order they are listed.
+/// Rotations are in degrees.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformStep {
+    Translate([f32; 3]),
+    Scale([f32; 3]),
+    RotateX(f32),
+    RotateY(f32),
+    RotateZ(f32),
+    /// Row-major 4x4 matrix.
+    Matrix([[f32; 4]; 4]),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum LightDescription {
+    Directional {
+        direction: [f32; 3],
+        intensity: [f32; 3],
+        #[serde(default)]
+        keyframes: Vec<LightKeyframe>,
+    },
+    Point {
+        position: [f32; 3],
+        intensity: [f32; 3],
+        /// Falls off with the square of the distance, from `intensity` at a distance of one.
+        #[serde(default)]
+        inverse_square: bool,
+        #[serde(default)]
+        keyframes: Vec<LightKeyframe>,
+    },
+    /// Cone angles are in degrees from `direction`. The light fades out between them.
+    Spot {
+        position: [f32; 3],
+        direction: [f32; 3],
+        inner_angle: f32,
+        outer_angle: f32,
+        intensity: [f32; 3],
+        #[serde(default)]
+        inverse_square: bool,
+        #[serde(default)]
+        keyframes: Vec<LightKeyframe>,
+    },
+    /// Point light shaped by the candela distribution of an IES LM-63 file, relative to its
+    /// brightest direction. The profile's nadir points along `direction`, turned about it by
+    /// `rotation` degrees.
+    Ies {
+        position: [f32; 3],
+        /// Relative to the scene file.
+        profile: PathBuf,
+        #[serde(default = "default_ies_direction")]
+        direction: [f32; 3],
+        #[serde(default)]
+        rotation: f32,
+        intensity: [f32; 3],
+        #[serde(default)]
+        inverse_square: bool,
+        #[serde(default)]
+        keyframes: Vec<LightKeyframe>,
+    },
+    /// Shines from the side that `edge_u × edge_v` points to.
+    Rectangle {
+        corner: [f32; 3],
+        edge_u: [f32; 3],
+        edge_v: [f32; 3],
+        intensity: [f32; 3],
+        /// Shadow rays per shaded point. Must be a square number.
+        #[serde(default = "default_light_samples")]
+        samples: u32,
+        #[serde(default)]
+        keyframes: Vec<LightKeyframe>,
+    },
+    Disc {
+        center: [f32; 3],
+        normal: [f32; 3],
+        radius: f32,
+        intensity: [f32; 3],
+        #[serde(default = "default_light_samples")]
+        samples: u32,
+        #[serde(default)]
+        keyframes: Vec<LightKeyframe>,
+    },
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        intensity: [f32; 3],
+        #[serde(default = "default_light_samples")]
+        samples: u32,
+        #[serde(default)]
+        keyframes: Vec<LightKeyframe>,
+    },
+    /// Light from every direction, from an equirectangular `.hdr` or PFM map turned about the
+    /// vertical axis by `rotation` degrees.
+    Environment {
+        /// Relative to the scene file.
+        map: PathBuf,
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "default_environment_intensity")]
+        intensity: [f32; 3],
+        #[serde(default = "default_light_samples")]
+        samples: u32,
+        #[serde(default)]
+        keyframes: Vec<LightKeyframe>,
+    },
+}
+
+impl LightDescription {
+    /// Intensity as given, before any keyframes.
+    fn intensity_and_keyframes(&self) -> ([f32; 3], &[LightKeyframe]) {
+        match self {
+            LightDescription::Directional {
+                intensity,
+                keyframes,
+                ..
+            }
+            | LightDescription::Point {
+                intensity,
+                keyframes,
+                ..
+            }
+            | LightDescription::Spot {
+                intensity,
+                keyframes,
+                ..
+            }
+            | LightDescription::Ies {
+                intensity,
+                keyframes,
+                ..
+            }
+            | LightDescription::Rectangle {
+                intensity,
+                keyframes,
+                ..
+            }
+            | LightDescription::Disc {
+                intensity,
+                keyframes,
+                ..
+            }
+            | LightDescription::Sphere {
+                intensity,
+                keyframes,
+                ..
+            }
+            | LightDescription::Environment {
+                intensity,
+                keyframes,
+                ..
+            } => (*intensity, keyframes),
+        }
+    }
+
+    fn keyframes(&self) -> &[LightKeyframe] {
+        self.intensity_and_keyframes().1
+    }
+}
+
+/// Light at a point in time. Values left out follow the other keyframes, or stay as given by
+/// the light.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightKeyframe {
+    pub time: f32,
+    /// The corner of a rectangle light, or the center of other area lights. Not for directional
+    /// lights.
+    pub position: Option<[f32; 3]>,
+    /// Only for directional, spot and IES lights.
+    pub direction: Option<[f32; 3]>,
+    pub intensity: Option<[f32; 3]>,
+}
+
+fn default_ies_direction() -> [f32; 3] {
+    [0.0, -1.0, 0.0]
+}
+
+fn default_environment_intensity() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_light_samples() -> u32 {
+    16
+}
+
+fn default_fov() -> f32 {
+    0.5
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_fisheye_field_of_view() -> f32 {
+    180.0
+}
+
+fn vector([x, y, z]: [f32; 3]) -> Vector {
+    Vector::new(x, y, z)
+}
+
+fn vertex([x, y, z]: [f32; 3]) -> Vertex {
+    Vertex::new(x, y, z, 1.0)
+}
+
+fn colour([r, g, b]: [f32; 3]) -> Colour {
+    Colour::new(r, g, b, 1.0)
+}
+
+fn is_square(n: u32) -> bool {
+    ((n as f64).sqrt() as u32).pow(2) == n
+}
+
+/// Samples per pixel of a camera, defaulting to the config's and checked to be square.
+fn camera_samples(samples: Option<u32>, config: &RaytracerConfig) -> Result<u32, SceneFileError> {
+    let samples = samples.unwrap_or(config.camera.num_camera_ray_samples);
+    if !is_square(samples) {
+        return Err(SceneFileError::InvalidValue {
+            context: "camera".to_string(),
+            message: format!("samples must be a square number, got {samples}."),
+        });
+    }
+    Ok(samples)
+}
+
+impl SceneDescription {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SceneFileError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| SceneFileError::ReadError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let base_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Self::from_toml_str(&contents, base_dir)
+    }
+
+    /// Parses a scene, resolving relative paths against `base_dir`.
+    pub fn from_toml_str(
+        contents: &str,
+        base_dir: impl Into<PathBuf>,
+    ) -> Result<Self, SceneFileError> {
+        let mut description: SceneDescription = toml::from_str(contents)?;
+        description.base_dir = base_dir.into();
+        Ok(description)
+    }
+
+    /// Adds a config override given as `key = value`, such as `camera.raytrace_recurse = 3`,
+    /// on top of the scene's own `[config]` overrides.
+    pub fn set_config(&mut self, assignment: &str) -> Result<(), SceneFileError> {
+        let overrides: toml::Table = toml::from_str(assignment).map_err(|error| {
+            SceneFileError::ConfigError(format!(
+                "'{assignment}' is not a valid assignment: {error}"
+            ))
+        })?;
+        merge_tables(&mut self.config, overrides);
+        Ok(())
+    }
+
+    /// Applies the `[config]` overrides of the scene to `base`, and checks that the sample
+    /// counts are square numbers.
+    pub fn config(&self, base: &RaytracerConfig) -> Result<RaytracerConfig, SceneFileError> {
+        let mut merged = toml::Table::try_from(base)
+            .map_err(|error| SceneFileError::ConfigError(error.to_string()))?;
+        merge_overrides(&mut merged, &self.config, "")?;
+
+        let config: RaytracerConfig = toml::Value::Table(merged)
+            .try_into()
+            .map_err(|error: toml::de::Error| SceneFileError::ConfigError(error.to_string()))?;
+
+        for (key, samples) in [
+            (
+                "camera.num_camera_ray_samples",
+                config.camera.num_camera_ray_samples,
+            ),
+            (
+                "photon_mapping.num_photons",
+                config.photon_mapping.num_photons,
+            ),
+        ] {
+            if !is_square(samples) {
+                return Err(SceneFileError::ConfigError(format!(
+                    "{key} must be a square number, got {samples}."
+                )));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Builds the camera, placed as it is when the shutter opens.
+    pub fn camera(&self, config: &RaytracerConfig) -> Result<SceneCamera, SceneFileError> {
+        let mut description = self.camera.clone().ok_or(SceneFileError::MissingCamera)?;
+        if let Some((position, lookat, keyframes)) = description.view_mut() {
+            let time = config.motion_blur.shutter_open;
+            if let Some(track) = self.track(keyframes, |key| key.position.map(|v| (key.time, v))) {
+                *position = track.at(time);
+            }
+            if let Some(track) = self.track(keyframes, |key| key.lookat.map(|v| (key.time, v))) {
+                *lookat = track.at(time);
+            }
+        }
+
+        let camera = match &description {
+            CameraDescription::Simple { fov } => SceneCamera::Simple(SimpleCamera::new(*fov)),
+            CameraDescription::Full {
+                fov,
+                position,
+                lookat,
+                up,
+                ..
+            } => SceneCamera::Full(FullCamera::new(
+                *fov,
+                vertex(*position),
+                vector(*lookat),
+                vector(*up),
+            )),
+            CameraDescription::Sampling {
+                fov,
+                position,
+                lookat,
+                up,
+                samples,
+                ..
+            } => {
+                let samples = camera_samples(*samples, config)?;
+
+                SceneCamera::Sampling(SamplingCamera::new(
+                    *fov,
+                    vertex(*position),
+                    vector(*lookat),
+                    vector(*up),
+                    samples,
+                ))
+            }
+            CameraDescription::ThinLens {
+                fov,
+                position,
+                lookat,
+                up,
+                samples,
+                aperture,
+                focus_distance,
+                focus_point,
+                blades,
+                blade_rotation,
+                ..
+            } => {
+                let samples = camera_samples(*samples, config)?;
+                if *aperture < 0.0 {
+                    return Err(SceneFileError::InvalidValue {
+                        context: "camera".to_string(),
+                        message: format!("aperture must not be negative, got {aperture}."),
+                    });
+                }
+
+                let mut camera = ThinLensCamera::new(
+                    *fov,
+                    vertex(*position),
+                    vector(*lookat),
+                    vector(*up),
+                    samples,
+                    *aperture,
+                    focus_distance.unwrap_or_default(),
+                )
+                .with_blades(*blades, blade_rotation.to_radians());
+                if focus_distance.is_none() {
+                    camera.focus_on(vertex(focus_point.unwrap_or(*lookat)));
+                }
+
+                SceneCamera::ThinLens(camera)
+            }
+            CameraDescription::Orthographic {
+                position,
+                lookat,
+                up,
+                view_width,
+                samples,
+                ..
+            } => {
+                if *view_width <= 0.0 {
+                    return Err(SceneFileError::InvalidValue {
+                        context: "camera".to_string(),
+                        message: format!("view_width must be positive, got {view_width}."),
+                    });
+                }
+
+                SceneCamera::Orthographic(OrthographicCamera::new(
+                    *view_width,
+                    vertex(*position),
+                    vector(*lookat),
+                    vector(*up),
+                    camera_samples(*samples, config)?,
+                ))
+            }
+            CameraDescription::Fisheye {
+                position,
+                lookat,
+                up,
+                field_of_view,
+                projection,
+                samples,
+                ..
+            } => {
+                if *field_of_view <= 0.0 || *field_of_view > 360.0 {
+                    return Err(SceneFileError::InvalidValue {
+                        context: "camera".to_string(),
+                        message: format!(
+                            "field_of_view must be between 0 and 360 degrees, got {field_of_view}."
+                        ),
+                    });
+                }
+
+                SceneCamera::Fisheye(FisheyeCamera::new(
+                    field_of_view.to_radians(),
+                    *projection,
+                    vertex(*position),
+                    vector(*lookat),
+                    vector(*up),
+                    camera_samples(*samples, config)?,
+                ))
+            }
+            CameraDescription::Equirectangular {
+                position,
+                lookat,
+                up,
+                samples,
+                ..
+            } => SceneCamera::Equirectangular(EquirectangularCamera::new(
+                vertex(*position),
+                vector(*lookat),
+                vector(*up),
+                camera_samples(*samples, config)?,
+            )),
+        };
+
+        Ok(camera)
+    }
+
+    /// Adds the objects and lights of the scene.
+    pub fn populate<S: SceneBuilder>(&self, scene: &mut S) -> Result<(), SceneFileError> {
+        let materials = self.materials()?;
+
+        if self.cornell_box {
+            setup_cornell_box(scene);
+        }
+
+        for (index, object) in self.objects.iter().enumerate() {
+            let context = format!("objects[{index}]");
+            scene.add_object(self.build_object(object, &materials, None, &context)?);
+        }
+
+        let time = scene.config().motion_blur.shutter_open;
+        for (index, light) in self.lights.iter().enumerate() {
+            scene.add_light(self.build_light(light, None, time, &format!("lights[{index}]"))?);
+        }
+
+        Ok(())
+    }
+
+    /// Moves the keyframed lights of a populated scene to where they are when the shutter of
+    /// the scene's config opens, for the next frame of an animation. IES profiles and
+    /// environment maps are kept from when the scene was populated.
+    pub fn update_lights<S: SceneBuilder>(&self, scene: &mut S) -> Result<(), SceneFileError> {
+        let time = scene.config().motion_blur.shutter_open;
+        // The scene's own lights are added last.
+        let first = scene.num_lights() - self.lights.len();
+
+        for (index, light) in self.lights.iter().enumerate() {
+            if light.keyframes().is_empty() {
+                continue;
+            }
+            let previous = scene.light(first + index);
+            let light =
+                self.build_light(light, Some(previous), time, &format!("lights[{index}]"))?;
+            scene.set_light(first + index, light);
+        }
+
+        Ok(())
+    }
+
+    /// Builds a light as it is at `time`. Updating a `previous` build of it shares its IES
+    /// profile or environment map rather than reading the file again.
+    fn build_light(
+        &self,
+        description: &LightDescription,
+        previous: Option<&Light>,
+        time: f32,
+        context: &str,
+    ) -> Result<Light, SceneFileError> {
+        let (mut intensity, keyframes) = description.intensity_and_keyframes();
+        if let Some(track) = self.track(keyframes, |key| key.intensity.map(|v| (key.time, v))) {
+            intensity = track.at(time);
+        }
+
+        let invalid = |message: &str| SceneFileError::InvalidValue {
+            context: context.to_string(),
+            message: message.to_string(),
+        };
+
+        let has_direction = matches!(
+            description,
+            LightDescription::Directional { .. }
+                | LightDescription::Spot { .. }
+                | LightDescription::Ies { .. }
+        );
+        let positionless = match description {
+            LightDescription::Directional { .. } => Some("directional"),
+            LightDescription::Environment { .. } => Some("environment"),
+            _ => None,
+        };
+        if let Some(kind) = positionless {
+            if keyframes.iter().any(|key| key.position.is_some()) {
+                return Err(invalid(&format!(
+                    "{kind} lights have no position to keyframe."
+                )));
+            }
+        }
+        if !has_direction && keyframes.iter().any(|key| key.direction.is_some()) {
+            return Err(invalid(
+                "only directional, spot and IES lights have a direction to keyframe.",
+            ));
+        }
+
+        let position = |position: &[f32; 3]| {
+            self.track(keyframes, |key| key.position.map(|v| (key.time, v)))
+                .map_or(*position, |track| track.at(time))
+        };
+        let direction = |direction: &[f32; 3]| {
+            self.track(keyframes, |key| key.direction.map(|v| (key.time, v)))
+                .map_or(*direction, |track| track.at(time))
+        };
+        let (samples, radius) = match description {
+            LightDescription::Rectangle { samples, .. }
+            | LightDescription::Environment { samples, .. } => (*samples, None),
+            LightDescription::Disc {
+                samples, radius, ..
+            }
+            | LightDescription::Sphere {
+                samples, radius, ..
+            } => (*samples, Some(*radius)),
+            _ => (1, None),
+        };
+        if !is_square(samples) {
+            return Err(invalid(&format!(
+                "samples must be a square number, got {samples}."
+            )));
+        }
+        if radius.is_some_and(|radius| radius <= 0.0) {
+            return Err(invalid("radius must be positive."));
+        }
+
+        let light = match description {
+            LightDescription::Directional {
+                direction: towards, ..
+            } => Light::new_directional(vector(direction(towards)), colour(intensity)),
+            LightDescription::Point {
+                position: point, ..
+            } => Light::new_point(vertex(position(point)), colour(intensity)),
+            LightDescription::Spot {
+                position: point,
+                direction: towards,
+                inner_angle,
+                outer_angle,
+                ..
+            } => {
+                if !(0.0 <= *inner_angle && inner_angle <= outer_angle && *outer_angle <= 180.0) {
+                    return Err(invalid(
+                        "spot light angles must satisfy 0 <= inner_angle <= outer_angle <= 180.",
+                    ));
+                }
+                Light::new_spot(
+                    vertex(position(point)),
+                    vector(direction(towards)),
+                    inner_angle.to_radians(),
+                    outer_angle.to_radians(),
+                    colour(intensity),
+                )
+            }
+            LightDescription::Ies {
+                position: point,
+                profile,
+                direction: towards,
+                rotation,
+                ..
+            } => match previous {
+                Some(Light::Ies {
+                    profile,
+                    distribution,
+                    ..
+                }) => Light::Ies {
+                    position: vertex(position(point)),
+                    direction: vector(direction(towards)).normalise(),
+                    rotation: rotation.to_radians(),
+                    intensity: colour(intensity),
+                    inverse_square: false,
+                    profile: Arc::clone(profile),
+                    distribution: Arc::clone(distribution),
+                },
+                _ => {
+                    let path = self.base_dir.join(profile);
+                    let profile = IesProfile::from_file(&path).map_err(|source| {
+                        SceneFileError::ProfileError {
+                            context: context.to_string(),
+                            path: path.clone(),
+                            source,
+                        }
+                    })?;
+                    Light::new_ies(
+                        vertex(position(point)),
+                        vector(direction(towards)),
+                        rotation.to_radians(),
+                        profile,
+                        colour(intensity),
+                    )
+                }
+            },
+            LightDescription::Rectangle {
+                corner,
+                edge_u,
+                edge_v,
+                samples,
+                ..
+            } => {
+                if vector(*edge_u).cross(vector(*edge_v)).length() <= 0.0 {
+                    return Err(invalid(
+                        "the edges of a rectangle light must not be parallel.",
+                    ));
+                }
+                Light::new_rectangle(
+                    vertex(position(corner)),
+                    vector(*edge_u),
+                    vector(*edge_v),
+                    colour(intensity),
+                    *samples,
+                )
+            }
+            LightDescription::Disc {
+                center,
+                normal,
+                radius,
+                samples,
+                ..
+            } => Light::new_disc(
+                vertex(position(center)),
+                vector(*normal),
+                *radius,
+                colour(intensity),
+                *samples,
+            ),
+            LightDescription::Sphere {
+                center,
+                radius,
+                samples,
+                ..
+            } => Light::new_sphere(
+                vertex(position(center)),
+                *radius,
+                colour(intensity),
+                *samples,
+            ),
+            LightDescription::Environment {
+                map,
+                rotation,
+                samples,
+                ..
+            } => match previous {
+                Some(Light::Environment {
+                    map, distribution, ..
+                }) => Light::Environment {
+                    map: Arc::clone(map),
+                    distribution: Arc::clone(distribution),
+                    rotation: rotation.to_radians(),
+                    intensity: colour(intensity),
+                    samples: *samples,
+                },
+                _ => {
+                    let path = self.base_dir.join(map);
+                    let map = HdrImage::from_file(&path).map_err(|source| {
+                        SceneFileError::EnvironmentMapError {
+                            context: context.to_string(),
+                            path: path.clone(),
+                            source,
+                        }
+                    })?;
+                    Light::new_environment(map, rotation.to_radians(), colour(intensity), *samples)
+                }
+            },
+        };
+
+        match description {
+            LightDescription::Point {
+                inverse_square: true,
+                ..
+            }
+            | LightDescription::Spot {
+                inverse_square: true,
+                ..
+            }
+            | LightDescription::Ies {
+                inverse_square: true,
+                ..
+            } => Ok(light.with_inverse_square()),
+            _ => Ok(light),
+        }
+    }
+
+    /// How keyframed values are interpolated.
+    fn interpolation(&self) -> Interpolation {
+        self.animation
+            .as_ref()
+            .map(|timeline| timeline.interpolation)
+            .unwrap_or_default()
+    }
+
+    /// Track through the keyframes that give a value, if any do.
+    fn track<K>(
+        &self,
+        keyframes: &[K],
+        value: impl Fn(&K) -> Option<(f32, [f32; 3])>,
+    ) -> Option<Track> {
+        Track::new(
+            keyframes.iter().filter_map(value).collect(),
+            self.interpolation(),
+        )
+    }
+
+    fn materials(&self) -> Result<HashMap<&str, Material>, SceneFileError> {
+        self.materials
+            .iter()
+            .map(|(name, description)| {
+                if let MaterialDescription::AmbientOcclusion { samples, .. }
+                | MaterialDescription::Emissive { samples, .. } = *description
+                {
+                    if !is_square(samples) {
+                        return Err(SceneFileError::InvalidValue {
+                            context: format!("materials.{name}"),
+                            message: format!("samples must be a square number, got {samples}."),
+                        });
+                    }
+                }
+
+                let material = match *description {
+                    MaterialDescription::Phong {
+                        ambient,
+                        diffuse,
+                        specular,
+                        power,
+                    } => Material::phong(colour(ambient), colour(diffuse), colour(specular), power),
+                    MaterialDescription::Global {
+                        reflect,
+                        refract,
+                        ior,
+                    } => Material::global(colour(reflect), colour(refract), ior),
+                    MaterialDescription::Reflective { reflectivity } => {
+                        Material::reflective(reflectivity)
+                    }
+                    MaterialDescription::Transparent { transparency, ior } => {
+                        Material::transparent(transparency, ior)
+                    }
+                    MaterialDescription::AmbientOcclusion {
+                        ambient,
+                        samples,
+                        min_ambient,
+                    } => Material::ambient_occlusion(colour(ambient), samples, min_ambient),
+                    MaterialDescription::Emissive { radiance, samples } => {
+                        Material::emissive(colour(radiance), samples)
+                    }
+                };
+
+                Ok((name.as_str(), material))
+            })
+            .collect()
+    }
+
+    fn build_object(
+        &self,
+        description: &ObjectDescription,
+        materials: &HashMap<&str, Material>,
+        parent_material: Option<Material>,
+        context: &str,
+    ) -> Result<SceneObject, SceneFileError> {
+        let (material_name, steps, motion) = match description {
+            ObjectDescription::Sphere {
+                material,
+                transform,
+                motion,
+                ..
+            }
+            | ObjectDescription::Plane {
+                material,
+                transform,
+                motion,
+                ..
+            }
+            | ObjectDescription::Quadratic {
+                material,
+                transform,
+                motion,
+                ..
+            }
+            | ObjectDescription::Polymesh {
+                material,
+                transform,
+                motion,
+                ..
+            }
+            | ObjectDescription::Csg {
+                material,
+                transform,
+                motion,
+                ..
+            } => (material, transform, motion),
+        };
+
+        let material =
+            match material_name {
+                Some(name) => *materials.get(name.as_str()).ok_or_else(|| {
+                    SceneFileError::UnknownMaterial {
+                        context: context.to_string(),
+                        name: name.clone(),
+                    }
+                })?,
+                None => parent_material.ok_or_else(|| SceneFileError::MissingMaterial {
+                    context: context.to_string(),
+                })?,
+            };
+
+        let invalid = |message: String| SceneFileError::InvalidValue {
+            context: context.to_string(),
+            message,
+        };
+
+        let mut object: SceneObject = match description {
+            ObjectDescription::Sphere { center, radius, .. } => {
+                if *radius <= 0.0 {
+                    return Err(invalid(format!("radius must be positive, got {radius}.")));
+                }
+                Sphere::new(vertex(*center), *radius, material).into()
+            }
+            ObjectDescription::Plane {
+                equation: [a, b, c, d],
+                ..
+            } => {
+                if *a == 0.0 && *b == 0.0 && *c == 0.0 {
+                    return Err(invalid("plane normal must not be zero.".to_string()));
+                }
+                Plane::new(*a, *b, *c, *d, material).into()
+            }
+            ObjectDescription::Quadratic {
+                coefficients,
+                bounds,
+                ..
+            } => {
+                let quadratic = Quadratic::new(*coefficients, material);
+                match bounds {
+                    Some(bounds) => quadratic
+                        .with_bounds(AABB::new(vector(bounds.min), vector(bounds.max)))
+                        .into(),
+                    None => quadratic.into(),
+                }
+            }
+            ObjectDescription::Polymesh { path, smooth, .. } => {
+                let path = self.base_dir.join(path);
+                let path_str = path.to_str().ok_or_else(|| {
+                    invalid(format!("mesh path {} is not valid UTF-8.", path.display()))
+                })?;
+
+                PolyMesh::new(path_str, *smooth, material)
+                    .map_err(|source| SceneFileError::MeshError {
+                        context: context.to_string(),
+                        path: path.clone(),
+                        source,
+                    })?
+                    .into()
+            }
+            ObjectDescription::Csg {
+                mode, left, right, ..
+            } => {
+                let left =
+                    self.build_object(left, materials, Some(material), &format!("{context}.left"))?;
+                let right = self.build_object(
+                    right,
+                    materials,
+                    Some(material),
+                    &format!("{context}.right"),
+                )?;
+                let mode = match mode {
+                    CsgMode::Union => Mode::CsgUnion,
+                    CsgMode::Intersection => Mode::CsgInter,
+                    CsgMode::Difference => Mode::CsgDiff,
+                };
+
+                CSG::new(mode, left, right, material).into()
+            }
+        };
+
+        if !steps.is_empty() {
+            object.transform(&build_transform(steps));
+        }
+
+        if !motion.is_empty() {
+            if motion.len() < 2 {
+                return Err(invalid("motion needs at least two keyframes.".to_string()));
+            }
+            if motion.windows(2).any(|pair| pair[0].time >= pair[1].time) {
+                return Err(invalid(
+                    "motion keyframe times must be increasing.".to_string(),
+                ));
+            }
+
+            let keyframes = motion
+                .iter()
+                .map(|keyframe| (keyframe.time, build_transform(&keyframe.transform)))
+                .collect();
+            object = Instance::animated(
+                Arc::new(object),
+                AnimatedTransform::from_keyframes(keyframes)
+                    .with_interpolation(self.interpolation()),
+                material,
+            )
+            .into();
+        }
+
+        Ok(object)
+    }
+}
+
+/// Combines the steps into a single transform, with the first step applied first.
+pub fn build_transform(steps: &[TransformStep]) -> Transform {
+    steps.iter().fold(Transform::identity(), |acc, step| {
+        let step = match *step {
+            TransformStep::Translate([x, y, z]) => Transform::new([
+                [1.0, 0.0, 0.0, x],
+                [0.0, 1.0, 0.0, y],
+                [0.0, 0.0, 1.0, z],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+            TransformStep::Scale([x, y, z]) => Transform::new([
+                [x, 0.0, 0.0, 0.0],
+                [0.0, y, 0.0, 0.0],
+                [0.0, 0.0, z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]),
+            TransformStep::RotateX(degrees) => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                Transform::new([
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.0, cos, -sin, 0.0],
+                    [0.0, sin, cos, 0.0],
+                    [0.0, 0.0, 0.0, 1.0],
+                ])
+            }
+            TransformStep::RotateY(degrees) => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                Transform::new([
+                    [cos, 0.0, sin, 0.0],
+                    [0.0, 1.0, 0.0, 0.0],
+                    [-sin, 0.0, cos, 0.0],
+                    [0.0, 0.0, 0.0, 1.0],
+                ])
+            }
+            TransformStep::RotateZ(degrees) => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                Transform::new([
+                    [cos, -sin, 0.0, 0.0],
+                    [sin, cos, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [0.0, 0.0, 0.0, 1.0],
+                ])
+            }
+            TransformStep::Matrix(matrix) => Transform::new(matrix),
+        };
+
+        step * acc
+    })
+}
+
+/// Recursively copies the values of `overrides` into `base`, replacing any already there.
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge_tables(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Recursively copies the override values into `base`, rejecting keys that `base` does not have
+/// so that typos are reported instead of silently ignored.
+fn merge_overrides(
+    base: &mut toml::Table,
+    overrides: &toml::Table,
+    prefix: &str,
+) -> Result<(), SceneFileError> {
+    for (key, value) in overrides {
+        let path = format!("{prefix}{key}");
+
+        match (base.get_mut(key), value) {
+            (None, _) => {
+                return Err(SceneFileError::ConfigError(format!(
+                    "unknown option '{path}'."
+                )))
+            }
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge_overrides(base, overrides, &format!("{path}."))?
+            }
+            (Some(toml::Value::Table(_)), _) => {
+                return Err(SceneFileError::ConfigError(format!(
+                    "'{path}' is a section, not a value."
+                )))
+            }
+            (Some(base_value), _) => *base_value = value.clone(),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{geometry::traits::Intersection, primitives::ray::Ray, Scene};
+    use approx::assert_relative_eq;
+
+    fn parse(contents: &str) -> SceneDescription {
+        SceneDescription::from_toml_str(contents, env!("CARGO_MANIFEST_DIR")).unwrap()
+    }
+
+    fn populate_error(contents: &str) -> SceneFileError {
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        parse(contents).populate(&mut scene).unwrap_err()
+    }
+
+    const MATERIALS: &str = r#"
+        [materials.red]
+        type = "phong"
+        ambient = [0.1, 0.0, 0.0]
+        diffuse = [0.8, 0.0, 0.0]
+        specular = [0.2, 0.2, 0.2]
+        power = 40.0
+    "#;
+
+    #[test]
+    fn test_loader_populates_scene() {
+        let description = parse(&format!(
+            r#"
+            {MATERIALS}
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, 10.0]
+            radius = 2.0
+            material = "red"
+
+            [[objects]]
+            type = "plane"
+            equation = [0.0, 1.0, 0.0, 5.0]
+            material = "red"
+
+            [[lights]]
+            type = "directional"
+            direction = [0.0, -1.0, 0.0]
+            intensity = [1.0, 1.0, 1.0]
+            "#
+        ));
+
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        description.populate(&mut scene).unwrap();
+
+        assert_eq!(scene.objects.len(), 2);
+        assert_eq!(scene.lights.len(), 1);
+
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = scene.objects[0].first_hit(&ray).unwrap();
+        assert_relative_eq!(hit.distance, 8.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_loader_csg_children_inherit_material() {
+        let description = parse(&format!(
+            r#"
+            {MATERIALS}
+
+            [[objects]]
+            type = "csg"
+            mode = "difference"
+            material = "red"
+            left = {{ type = "sphere", center = [0.0, 0.0, 10.0], radius = 2.0 }}
+            right = {{ type = "sphere", center = [0.0, 0.0, 8.0], radius = 1.0 }}
+            "#
+        ));
+
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        description.populate(&mut scene).unwrap();
+
+        // The front of the sphere is carved out by the smaller one, which ends at z = 9.
+        let ray = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = scene.objects[0].first_hit(&ray).unwrap();
+        assert_relative_eq!(hit.distance, 9.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_loader_applies_transform_steps_in_order() {
+        let transform = build_transform(&[
+            TransformStep::Scale([2.0, 2.0, 2.0]),
+            TransformStep::Translate([1.0, 0.0, 0.0]),
+            TransformStep::RotateZ(90.0),
+        ]);
+
+        // (1, 0, 0) -> (2, 0, 0) -> (3, 0, 0) -> (0, 3, 0).
+        let mut vertex = Vertex::new(1.0, 0.0, 0.0, 1.0);
+        transform.apply_to_vertex(&mut vertex);
+        assert_relative_eq!(vertex.vector.x, 0.0, epsilon = 1e-5);
+        assert_relative_eq!(vertex.vector.y, 3.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_loader_motion_keyframes() {
+        let description = parse(&format!(
+            r#"
+            {MATERIALS}
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, 10.0]
+            radius = 1.0
+            material = "red"
+            motion = [
+                {{ time = 0.0 }},
+                {{ time = 1.0, transform = [{{ translate = [4.0, 0.0, 0.0] }}] }},
+            ]
+            "#
+        ));
+
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        description.populate(&mut scene).unwrap();
+
+        // The sphere crosses the ray at x = 2 halfway through.
+        let ray = |time| {
+            Ray::new(Vertex::new(2.0, 0.0, 0.0, 1.0), Vector::new(0.0, 0.0, 1.0)).with_time(time)
+        };
+        assert!(scene.objects[0].first_hit(&ray(0.0)).is_none());
+        let hit = scene.objects[0].first_hit(&ray(0.5)).unwrap();
+        assert_relative_eq!(hit.distance, 9.0, epsilon = 1e-4);
+
+        let error = populate_error(&format!(
+            r#"
+            {MATERIALS}
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, 10.0]
+            radius = 1.0
+            material = "red"
+            motion = [{{ time = 1.0 }}, {{ time = 0.0 }}]
+            "#
+        ));
+        assert_eq!(
+            error.to_string(),
+            "objects[0]: motion keyframe times must be increasing."
+        );
+    }
+
+    #[test]
+    fn test_loader_animation_keyframes() {
+        let description = parse(
+            r#"
+            [animation]
+            frames = 3
+            frame_rate = 2.0
+
+            [camera]
+            type = "thin_lens"
+            position = [0.0, 0.0, 0.0]
+            lookat = [0.0, 0.0, 10.0]
+            samples = 1
+            aperture = 0.1
+            keyframes = [
+                { time = 0.0, position = [0.0, 0.0, 0.0] },
+                { time = 1.0, position = [0.0, 0.0, 4.0] },
+            ]
+
+            [[lights]]
+            type = "point"
+            position = [0.0, 10.0, 0.0]
+            intensity = [1.0, 1.0, 1.0]
+            keyframes = [
+                { time = 0.0, intensity = [0.0, 0.0, 0.0] },
+                { time = 1.0, position = [5.0, 10.0, 0.0], intensity = [1.0, 1.0, 1.0] },
+            ]
+            "#,
+        );
+        let timeline = description.animation.as_ref().unwrap();
+        let frames: Vec<RaytracerConfig> = (0..timeline.frames)
+            .map(|frame| RaytracerConfig {
+                motion_blur: timeline.motion_blur(frame),
+                ..Default::default()
+            })
+            .collect();
+
+        // The camera keeps focusing on its look-at point as it moves towards it.
+        let SceneCamera::ThinLens(camera) = description.camera(&frames[1]).unwrap() else {
+            panic!("expected a thin lens camera");
+        };
+        assert_relative_eq!(camera.focus_distance, 8.0, epsilon = 1e-5);
+
+        // A light keyframe may give only some values, and the position given once holds
+        // throughout.
+        let mut scene = Scene::new(&frames[0]);
+        description.populate(&mut scene).unwrap();
+        assert_eq!(
+            scene.lights[0],
+            Light::new_point(vertex([5.0, 10.0, 0.0]), colour([0.0, 0.0, 0.0]))
+        );
+
+        scene.set_config(&frames[1]);
+        description.update_lights(&mut scene).unwrap();
+        assert_eq!(
+            scene.lights[0],
+            Light::new_point(vertex([5.0, 10.0, 0.0]), colour([0.5, 0.5, 0.5]))
+        );
+
+        let error = populate_error(
+            r#"
+            [[lights]]
+            type = "directional"
+            direction = [0.0, -1.0, 0.0]
+            intensity = [1.0, 1.0, 1.0]
+            keyframes = [{ time = 1.0, position = [0.0, 1.0, 0.0] }]
+            "#,
+        );
+        assert_eq!(
+            error.to_string(),
+            "lights[0]: directional lights have no position to keyframe."
+        );
+    }
+
+    #[test]
+    fn test_loader_area_lights() {
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        parse(
+            r#"
+            [[lights]]
+            type = "rectangle"
+            corner = [-1.0, 10.0, -1.0]
+            edge_u = [2.0, 0.0, 0.0]
+            edge_v = [0.0, 0.0, 2.0]
+            intensity = [1.0, 1.0, 1.0]
+
+            [[lights]]
+            type = "disc"
+            center = [0.0, 10.0, 0.0]
+            normal = [0.0, -2.0, 0.0]
+            radius = 0.5
+            intensity = [1.0, 1.0, 1.0]
+            samples = 4
+            keyframes = [{ time = 0.0, position = [0.0, 8.0, 0.0] }]
+
+            [[lights]]
+            type = "sphere"
+            center = [0.0, 5.0, 0.0]
+            radius = 1.0
+            intensity = [1.0, 1.0, 1.0]
+            samples = 9
+            "#,
+        )
+        .populate(&mut scene)
+        .unwrap();
+
+        assert_eq!(scene.lights[0].num_samples(), 16);
+        assert_eq!(
+            scene.lights[0].get_position(),
+            Some(vertex([0.0, 10.0, 0.0]))
+        );
+        assert_eq!(
+            scene.lights[1],
+            Light::new_disc(
+                vertex([0.0, 8.0, 0.0]),
+                vector([0.0, -1.0, 0.0]),
+                0.5,
+                colour([1.0, 1.0, 1.0]),
+                4
+            )
+        );
+        assert_eq!(
+            scene.lights[2],
+            Light::new_sphere(vertex([0.0, 5.0, 0.0]), 1.0, colour([1.0, 1.0, 1.0]), 9)
+        );
+
+        let error = populate_error(
+            r#"
+            [[lights]]
+            type = "sphere"
+            center = [0.0, 5.0, 0.0]
+            radius = 1.0
+            intensity = [1.0, 1.0, 1.0]
+            samples = 5
+            "#,
+        );
+        assert_eq!(
+            error.to_string(),
+            "lights[0]: samples must be a square number, got 5."
+        );
+
+        let error = populate_error(
+            r#"
+            [[lights]]
+            type = "disc"
+            center = [0.0, 5.0, 0.0]
+            normal = [0.0, -1.0, 0.0]
+            radius = 1.0
+            intensity = [1.0, 1.0, 1.0]
+            keyframes = [{ time = 1.0, direction = [0.0, 1.0, 0.0] }]
+            "#,
+        );
+        assert_eq!(
+            error.to_string(),
+            "lights[0]: only directional, spot and IES lights have a direction to keyframe."
+        );
+    }
+
+    #[test]
+    fn test_loader_spot_lights() {
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        parse(
+            r#"
+            [[lights]]
+            type = "spot"
+            position = [0.0, 10.0, 0.0]
+            direction = [0.0, -1.0, 0.0]
+            inner_angle = 15.0
+            outer_angle = 30.0
+            intensity = [100.0, 100.0, 100.0]
+            inverse_square = true
+            keyframes = [{ time = 0.0, direction = [1.0, 0.0, 0.0] }]
+
+            [[lights]]
+            type = "point"
+            position = [0.0, 10.0, 0.0]
+            intensity = [1.0, 1.0, 1.0]
+            "#,
+        )
+        .populate(&mut scene)
+        .unwrap();
+
+        assert_eq!(
+            scene.lights[0],
+            Light::new_spot(
+                vertex([0.0, 10.0, 0.0]),
+                vector([1.0, 0.0, 0.0]),
+                15f32.to_radians(),
+                30f32.to_radians(),
+                colour([100.0, 100.0, 100.0]),
+            )
+            .with_inverse_square()
+        );
+        assert_eq!(
+            scene.lights[1],
+            Light::new_point(vertex([0.0, 10.0, 0.0]), colour([1.0, 1.0, 1.0]))
+        );
+
+        let error = populate_error(
+            r#"
+            [[lights]]
+            type = "spot"
+            position = [0.0, 10.0, 0.0]
+            direction = [0.0, -1.0, 0.0]
+            inner_angle = 45.0
+            outer_angle = 30.0
+            intensity = [1.0, 1.0, 1.0]
+            "#,
+        );
+        assert_eq!(
+            error.to_string(),
+            "lights[0]: spot light angles must satisfy 0 <= inner_angle <= outer_angle <= 180."
+        );
+    }
+
+    #[test]
+    fn test_loader_ies_lights() {
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        parse(
+            r#"
+            [[lights]]
+            type = "ies"
+            position = [0.0, 80.0, 100.0]
+            profile = "src/assets/downlight.ies"
+            intensity = [1.0, 1.0, 1.0]
+            inverse_square = true
+            "#,
+        )
+        .populate(&mut scene)
+        .unwrap();
+
+        let Light::Ies {
+            direction,
+            inverse_square,
+            profile,
+            ..
+        } = &scene.lights[0]
+        else {
+            panic!("expected an IES light");
+        };
+        assert_eq!(*direction, vector([0.0, -1.0, 0.0]));
+        assert!(inverse_square);
+        assert_relative_eq!(profile.max_candela(), 1850.0);
+
+        let error = populate_error(
+            r#"
+            [[lights]]
+            type = "ies"
+            position = [0.0, 80.0, 100.0]
+            profile = "missing.ies"
+            intensity = [1.0, 1.0, 1.0]
+            "#,
+        );
+        assert!(matches!(error, SceneFileError::ProfileError { .. }));
+    }
+
+    #[test]
+    fn test_loader_environment_light() {
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        parse(
+            r#"
+            [[lights]]
+            type = "environment"
+            map = "src/assets/sky.hdr"
+            rotation = 90.0
+            samples = 4
+            "#,
+        )
+        .populate(&mut scene)
+        .unwrap();
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/assets/sky.hdr");
+        assert_eq!(
+            scene.lights[0],
+            Light::new_environment(
+                HdrImage::from_file(&path).unwrap(),
+                90f32.to_radians(),
+                colour([1.0, 1.0, 1.0]),
+                4
+            )
+        );
+
+        let error = populate_error(
+            r#"
+            [[lights]]
+            type = "environment"
+            map = "missing.hdr"
+            "#,
+        );
+        assert!(matches!(error, SceneFileError::EnvironmentMapError { .. }));
+
+        let error = populate_error(
+            r#"
+            [[lights]]
+            type = "environment"
+            map = "src/assets/sky.hdr"
+            keyframes = [{ time = 1.0, position = [0.0, 1.0, 0.0] }]
+            "#,
+        );
+        assert_eq!(
+            error.to_string(),
+            "lights[0]: environment lights have no position to keyframe."
+        );
+    }
+
+    #[test]
+    fn test_loader_update_lights_shares_profiles_and_maps() {
+        let description = parse(
+            r#"
+            [[lights]]
+            type = "ies"
+            position = [0.0, 80.0, 100.0]
+            profile = "src/assets/downlight.ies"
+            direction = [0.0, -2.0, 0.0]
+            intensity = [1.0, 1.0, 1.0]
+            inverse_square = true
+            keyframes = [{ time = 1.0, position = [10.0, 80.0, 100.0] }]
+
+            [[lights]]
+            type = "environment"
+            map = "src/assets/sky.hdr"
+            keyframes = [{ time = 1.0, intensity = [2.0, 2.0, 2.0] }]
+            "#,
+        );
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        description.populate(&mut scene).unwrap();
+        let populated = scene.lights.clone();
+
+        let mut moved = config;
+        moved.motion_blur.shutter_open = 1.0;
+        moved.motion_blur.shutter_close = 1.0;
+        scene.set_config(&moved);
+        description.update_lights(&mut scene).unwrap();
+
+        let (
+            Light::Ies { profile, .. },
+            Light::Ies {
+                profile: shared, ..
+            },
+        ) = (&populated[0], &scene.lights[0])
+        else {
+            panic!("expected IES lights");
+        };
+        assert!(Arc::ptr_eq(profile, shared));
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/assets/downlight.ies");
+        assert_eq!(
+            scene.lights[0],
+            Light::new_ies(
+                vertex([10.0, 80.0, 100.0]),
+                vector([0.0, -1.0, 0.0]),
+                0.0,
+                IesProfile::from_file(&path).unwrap(),
+                colour([1.0, 1.0, 1.0])
+            )
+            .with_inverse_square()
+        );
+
+        let (
+            Light::Environment { map, .. },
+            Light::Environment {
+                map: shared,
+                intensity,
+                ..
+            },
+        ) = (&populated[1], &scene.lights[1])
+        else {
+            panic!("expected environment lights");
+        };
+        assert!(Arc::ptr_eq(map, shared));
+        assert_eq!(*intensity, colour([2.0, 2.0, 2.0]));
+    }
+
+    #[test]
+    fn test_loader_emissive_objects() {
+        let config = RaytracerConfig::default();
+        let mut scene = Scene::new(&config);
+        parse(
+            r#"
+            [materials.lamp]
+            type = "emissive"
+            radiance = [4.0, 3.0, 2.0]
+            samples = 4
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 5.0, 0.0]
+            radius = 0.5
+            material = "lamp"
+            "#,
+        )
+        .populate(&mut scene)
+        .unwrap();
+
+        assert!(scene.lights.is_empty());
+        assert_eq!(
+            scene.emitters,
+            vec![Light::new_sphere(
+                vertex([0.0, 5.0, 0.0]),
+                0.5,
+                colour([4.0, 3.0, 2.0]),
+                4
+            )]
+        );
+
+        let error = populate_error(
+            r#"
+            [materials.lamp]
+            type = "emissive"
+            radiance = [1.0, 1.0, 1.0]
+            samples = 3
+            "#,
+        );
+        assert_eq!(
+            error.to_string(),
+            "materials.lamp: samples must be a square number, got 3."
+        );
+    }
+
+    #[test]
+    fn test_loader_unknown_material() {
+        let error = populate_error(&format!(
+            r#"
+            {MATERIALS}
+
+            [[objects]]
+            type = "csg"
+            mode = "union"
+            material = "red"
+            left = {{ type = "sphere", center = [0.0, 0.0, 0.0], radius = 1.0, material = "green" }}
+            right = {{ type = "sphere", center = [0.0, 0.0, 0.0], radius = 1.0 }}
+            "#
+        ));
+
+        assert_eq!(
+            error.to_string(),
+            "objects[0].left: unknown material 'green'."
+        );
+    }
+
+    #[test]
+    fn test_loader_missing_material() {
+        let error = populate_error(
+            r#"
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, 0.0]
+            radius = 1.0
+            "#,
+        );
+
+        assert!(matches!(error, SceneFileError::MissingMaterial { .. }));
+    }
+
+    #[test]
+    fn test_loader_invalid_values() {
+        let error = populate_error(&format!(
+            r#"
+            {MATERIALS}
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, 0.0]
+            radius = -1.0
+            material = "red"
+            "#
+        ));
+        assert_eq!(
+            error.to_string(),
+            "objects[0]: radius must be positive, got -1."
+        );
+
+        let error = SceneDescription::from_toml_str(
+            r#"
+            [[objects]]
+            type = "torus"
+            "#,
+            "",
+        )
+        .unwrap_err();
+        assert!(matches!(error, SceneFileError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_loader_config_overrides() {
+        let description = parse(
+            r#"
+            [config]
+            camera.num_camera_ray_samples = 4
+            framebuffer = { width = 64 }
+            "#,
+        );
+
+        let base = RaytracerConfig::default();
+        let config = description.config(&base).unwrap();
+        assert_eq!(config.camera.num_camera_ray_samples, 4);
+        assert_eq!(config.framebuffer.width, 64);
+        assert_eq!(config.framebuffer.height, base.framebuffer.height);
+
+        let description = parse(
+            r#"
+            [config]
+            camera.num_samples = 4
+            "#,
+        );
+        let error = description.config(&base).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid config override: unknown option 'camera.num_samples'."
+        );
+    }
+
+    #[test]
+    fn test_loader_set_config() {
+        let mut description = parse(
+            r#"
+            [config]
+            camera = { raytrace_recurse = 2, num_camera_ray_samples = 4 }
+            "#,
+        );
+        description
+            .set_config("camera.raytrace_recurse = 7")
+            .unwrap();
+
+        let config = description.config(&RaytracerConfig::default()).unwrap();
+        assert_eq!(config.camera.raytrace_recurse, 7);
+        assert_eq!(config.camera.num_camera_ray_samples, 4);
+
+        assert!(description.set_config("camera.raytrace_recurse").is_err());
+
+        description
+            .set_config("photon_mapping.num_photons = 20000")
+            .unwrap();
+        let error = description.config(&RaytracerConfig::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid config override: photon_mapping.num_photons must be a square number, got \
+             20000."
+        );
+    }
+
+    #[test]
+    fn test_loader_sampling_camera_needs_square_samples() {
+        let description = parse(
+            r#"
+            [camera]
+            type = "sampling"
+            position = [0.0, 0.0, 0.0]
+            lookat = [0.0, 0.0, 1.0]
+            samples = 3
+            "#,
+        );
+
+        let config = RaytracerConfig::default();
+        assert!(matches!(
+            description.camera(&config),
+            Err(SceneFileError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse("").camera(&config),
+            Err(SceneFileError::MissingCamera)
+        ));
+    }
+
+    #[test]
+    fn test_loader_thin_lens_camera_focuses_on_lookat() {
+        let description = parse(
+            r#"
+            [camera]
+            type = "thin_lens"
+            position = [0.0, 0.0, -2.0]
+            lookat = [0.0, 3.0, 4.0]
+            samples = 4
+            aperture = 0.1
+            blades = 6
+            "#,
+        );
+
+        let config = RaytracerConfig::default();
+        let Ok(SceneCamera::ThinLens(camera)) = description.camera(&config) else {
+            panic!("expected a thin lens camera");
+        };
+        assert_relative_eq!(camera.focus_distance, 45.0_f32.sqrt(), epsilon = 1e-5);
+        assert_eq!(camera.num_samples, 4);
+        assert_eq!(camera.blades, 6);
+    }
+
+    #[test]
+    fn test_loader_panoramic_cameras() {
+        let config = RaytracerConfig::default();
+        let camera = |options: &str| {
+            parse(&format!(
+                r#"
+                [camera]
+                position = [0.0, 0.0, 0.0]
+                lookat = [0.0, 0.0, 1.0]
+                samples = 1
+                {options}
+                "#
+            ))
+            .camera(&config)
+        };
+
+        let Ok(SceneCamera::Fisheye(fisheye)) = camera(
+            r#"type = "fisheye"
+                projection = "equisolid""#,
+        ) else {
+            panic!("expected a fisheye camera");
+        };
+        assert_relative_eq!(fisheye.field_of_view, std::f32::consts::PI);
+        assert_eq!(fisheye.projection, FisheyeProjection::Equisolid);
+
+        assert!(matches!(
+            camera(r#"type = "equirectangular""#),
+            Ok(SceneCamera::Equirectangular(_))
+        ));
+        assert!(matches!(
+            camera(
+                r#"type = "orthographic"
+                view_width = 0.0"#
+            ),
+            Err(SceneFileError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_loader_bundled_scene() {
+        let description = SceneDescription::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/scenes/quick_final_image.toml"
+        ))
+        .unwrap();
+
+        let config = description.config(&RaytracerConfig::default()).unwrap();
+        let mut scene = Scene::new(&config);
+        description.populate(&mut scene).unwrap();
+        description.camera(&config).unwrap();
+
+        assert_eq!(scene.objects.len(), 6 + 3);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::Material;
+
+/// Numbers the distinct materials of a scene's objects, for the material id output variable.
+/// Objects with equal materials share an id, in the order the materials were first seen.
+#[derive(Debug, Default)]
+pub struct MaterialIds {
+    materials: Vec<Material>,
+    /// Material id of each object, by object index.
+    ids: Vec<u32>,
+}
+
+impl MaterialIds {
+    /// Records the material of the next object added to the scene.
+    pub fn push(&mut self, material: &Material) {
+        let id = match self.materials.iter().position(|other| other == material) {
+            Some(id) => id,
+            None => {
+                self.materials.push(*material);
+                self.materials.len() - 1
+            }
+        };
+
+        self.ids.push(id as u32);
+    }
+
+    /// Material id of the object at the given index.
+    pub fn get(&self, object_index: usize) -> Option<u32> {
+        self.ids.get(object_index).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material_ids_are_shared() {
+        let mut ids = MaterialIds::default();
+        ids.push(&Material::reflective(1.0));
+        ids.push(&Material::transparent(1.0, 1.5));
+        ids.push(&Material::reflective(1.0));
+
+        assert_eq!(ids.get(0), Some(0));
+        assert_eq!(ids.get(1), Some(1));
+        assert_eq!(ids.get(2), Some(0));
+        assert_eq!(ids.get(3), None);
+    }
+}
@@ -1,7 +1,13 @@
+pub mod acceleration;
+pub mod animation;
 pub mod basic;
+pub mod loader;
+pub mod material_ids;
 pub mod photon_mapping;
 pub mod traits;
 
+pub use animation::Timeline;
 pub use basic::Scene;
+pub use loader::{SceneDescription, SceneFileError};
 pub use photon_mapping::{PhotonMaps, PhotonScene};
 pub use traits::SceneBuilder;
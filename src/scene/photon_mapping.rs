@@ -4,13 +4,14 @@ use std::{collections::HashSet, f32::consts::E};
 
 use crate::{
     config::RaytracerConfig,
-    geometry::traits::{Bounded, Intersection},
+    geometry::traits::Bounded,
     primitives::{
         photon::{Photon, PhotonOutcome, PhotonType},
         ray::Ray,
-        Colour, Hit, Vector, Vertex,
+        Aovs, Colour, Hit, Vector, Vertex, AABB,
     },
     sampling::{traits::Sampler, MultiJitterSampler},
+    scene::{acceleration::Accelerator, material_ids::MaterialIds},
     shading::traits::Shader,
     Light, Material, Raytracer, SceneBuilder, SceneObject,
 };
@@ -130,8 +131,14 @@ fn russian_roulette(is_specular: bool, is_transparent: bool) -> (PhotonOutcome,
 pub struct PhotonScene<'a> {
     pub objects: Vec<SceneObject>,
     pub lights: Vec<Light>,
+    /// Lights given off by emissive objects.
+    pub emitters: Vec<Light>,
     pub photon_maps: PhotonMaps,
     pub config: &'a RaytracerConfig,
+    accelerator: Accelerator,
+    material_ids: MaterialIds,
+    /// Whether anything that the photon maps depend on has changed since they were built.
+    photon_maps_outdated: bool,
 }
 
 impl<'a> PhotonScene<'a> {
@@ -139,28 +146,88 @@ impl<'a> PhotonScene<'a> {
         Self {
             objects: Vec::new(),
             lights: Vec::new(),
+            emitters: Vec::new(),
             photon_maps: PhotonMaps {
                 global: KdTree::default(),
                 caustic: KdTree::default(),
             },
             config,
+            accelerator: Accelerator::new(config.acceleration.use_bvh),
+            material_ids: MaterialIds::default(),
+            photon_maps_outdated: true,
         }
     }
 
+    /// Switches to the config of another frame of an animation. The photon maps are only
+    /// rebuilt if the photon mapping settings differ, or objects move between the shutter
+    /// intervals of the two frames. Emissive objects that move take their light with them.
+    pub fn set_config(&mut self, config: &'a RaytracerConfig) {
+        let (old, new) = (self.config.motion_blur, config.motion_blur);
+        let start = old.shutter_open.min(new.shutter_open);
+        let end = old.shutter_close.max(new.shutter_close);
+        let objects_move = old != new
+            && self
+                .objects
+                .iter()
+                .any(|object| object.moves_between(start, end));
+
+        if objects_move {
+            self.emitters = self
+                .objects
+                .iter()
+                .filter_map(|object| Light::from_emissive_object(object, new.shutter_open))
+                .collect();
+        }
+        if config.photon_mapping != self.config.photon_mapping || objects_move {
+            self.photon_maps_outdated = true;
+        }
+        self.config = config;
+    }
+
+    /// Whether `setup` has to rebuild the photon maps.
+    pub fn photon_maps_outdated(&self) -> bool {
+        self.photon_maps_outdated
+    }
+
+    /// Light seen by a ray that leaves the scene, from any environment lights.
+    fn background(&self, direction: Vector) -> Colour {
+        let mut colour = Colour::default();
+        for light in &self.lights {
+            colour += light.radiance(direction);
+        }
+        colour
+    }
+
     /// Trace and determine the nearest ray's hit in front of the camera.
-    /// Returns the hit and a reference to the material.
-    fn find_hit(&self, ray: &Ray) -> Option<(Hit, &Material)> {
-        let mut nearest_hit: Option<(Hit, &Material)> = None;
-
-        for object in &self.objects {
-            if let Some(hit) = object.first_hit(ray) {
-                if nearest_hit.is_none() || hit.distance < nearest_hit.unwrap().0.distance {
-                    nearest_hit = Some((hit, object.material()));
-                }
-            }
+    /// Returns the hit and the index of the object.
+    fn find_hit(&self, ray: &Ray) -> Option<(Hit, usize)> {
+        self.accelerator.find_hit(&self.objects, ray, |_| true)
+    }
+
+    /// Shades a hit, returning the material's own contribution (ambient/emission/reflection/
+    /// refraction), the direct lighting and the photon map estimate separately.
+    fn shade_hit(
+        &self,
+        ray: &Ray,
+        hit: &Hit,
+        material: &Material,
+        recurse_depth: u8,
+    ) -> (Colour, Colour, Colour) {
+        let ambient = material.shade_ambient(self, ray, hit, recurse_depth);
+
+        // Always compute direct lighting from light sources
+        let direct = self.compute_lighting(hit, material, ray.time);
+
+        // Add photon contributions for global illumination effects
+        let mut photon = Colour::default();
+        if recurse_depth <= self.config.photon_mapping.recurse_approximate_threshold {
+            // At shallow depths, add photon-based indirect illumination and caustics
+            let viewer = &ray.direction.negate();
+            photon += self.estimate_indirect_illumination(viewer, hit, material);
+            photon += self.estimate_caustics(viewer, hit, material);
         }
 
-        nearest_hit
+        (ambient, direct, photon)
     }
 
     fn photon_trace(
@@ -172,27 +239,16 @@ impl<'a> PhotonScene<'a> {
         photon_outcome: Option<PhotonOutcome>,
         recurse: u8,
     ) {
-        let mut nearest_hit: Option<(Hit, &Material)> = None;
-
-        for object in &self.objects {
-            if let Some(hit) = object.first_hit(ray) {
-                // Only consider interacts that are entering the object, or exiting if the photon
-                // is transmitted.
-                if (hit.entering
-                    || (photon_outcome.is_some()
-                        && photon_outcome.unwrap() == PhotonOutcome::Transmit))
-                    && (nearest_hit.is_none() || hit.distance < nearest_hit.unwrap().0.distance)
-                {
-                    nearest_hit = Some((hit, object.material()));
-                }
-            }
-        }
+        // Only consider interacts that are entering the object, or exiting if the photon
+        // is transmitted.
+        let nearest_hit = self.accelerator.find_hit(&self.objects, ray, |hit| {
+            hit.entering || photon_outcome == Some(PhotonOutcome::Transmit)
+        });
 
-        if nearest_hit.is_none() {
+        let Some((hit, index)) = nearest_hit else {
             return;
-        }
-
-        let (hit, material) = nearest_hit.unwrap();
+        };
+        let material = self.objects[index].material();
 
         photon_map.push(Photon::new(
             hit.position,
@@ -213,7 +269,8 @@ impl<'a> PhotonScene<'a> {
                 let reflected_ray = Ray::new(
                     hit.position + self.config.objects.rounding_error * reflection_direction,
                     reflection_direction,
-                );
+                )
+                .with_time(ray.time);
                 self.photon_trace(
                     photon_map,
                     &reflected_ray,
@@ -227,7 +284,8 @@ impl<'a> PhotonScene<'a> {
                 let absorbed_ray = Ray::new(
                     hit.position + self.config.objects.rounding_error * ray.direction,
                     ray.direction,
-                );
+                )
+                .with_time(ray.time);
                 self.photon_trace(
                     photon_map,
                     &absorbed_ray,
@@ -239,7 +297,7 @@ impl<'a> PhotonScene<'a> {
             }
             PhotonOutcome::Transmit => {
                 if let Some(index_of_refraction) = material.index_of_refraction() {
-                    let mut transmitted_ray = Ray::default();
+                    let mut transmitted_ray = Ray::default().with_time(ray.time);
                     transmitted_ray.direction = ray
                         .direction
                         .refraction(hit.normal, index_of_refraction)
@@ -265,6 +323,7 @@ impl<'a> PhotonScene<'a> {
         hit_position: Vertex,
         light_position: Option<Vertex>,
         light_direction: Vector,
+        time: f32,
     ) -> bool {
         if self.config.photon_mapping.use_shadow_estimation {
             // Second pass - Shadow.
@@ -304,38 +363,50 @@ impl<'a> PhotonScene<'a> {
             }
         }
 
+        let rounding_error = self.config.objects.rounding_error;
         let to_light_direction = light_direction.negate();
         // Move the shadow ray point slightly along the ray (towards the light) to avoid self-shadowing.
         let shadow_ray = Ray::new(
-            hit_position + self.config.objects.rounding_error * to_light_direction,
+            hit_position + rounding_error * to_light_direction,
             to_light_direction,
-        );
+        )
+        .with_time(time);
 
+        // Stop short of the light, so that the surface of an area light doesn't shadow itself.
         let shadow_limit = light_position
-            .map(|light_position| (light_position.vector - shadow_ray.position.vector).length())
+            .map(|light_position| {
+                (light_position.vector - shadow_ray.position.vector).length() - rounding_error
+            })
             .unwrap_or(f32::INFINITY);
 
         self.is_occluded(&shadow_ray, shadow_limit)
     }
 
-    /// Compute contribution of all lights to the hit point.
-    fn compute_lighting(&self, hit: &Hit, material: &Material) -> Colour {
+    /// Compute contribution of all lights to the hit point, as seen at the given time.
+    fn compute_lighting(&self, hit: &Hit, material: &Material, time: f32) -> Colour {
         let mut colour = Colour::default();
 
-        for light in &self.lights {
-            let viewer_direction = (-hit.position.vector).normalise();
-            let (light_position, light_direction, is_lit) = light.get_direction(hit.position);
-
-            // Skip lights that are facing the wrong direction.
-            if light_direction.dot(hit.normal) > 0.0 {
-                continue;
-            }
+        let viewer_direction = (-hit.position.vector).normalise();
+        for light in self.lights.iter().chain(&self.emitters) {
+            // Area lights are sampled at several points, which softens the edges of shadows.
+            MultiJitterSampler::with_cached(light.num_samples(), self.config, |sampler| {
+                light.illuminate(hit.position, sampler, |sample| {
+                    // Skip lights that are facing the wrong direction.
+                    if sample.direction.dot(hit.normal) > 0.0 {
+                        return;
+                    }
 
-            if is_lit && !self.is_point_in_shadow(hit.position, light_position, light_direction) {
-                let intensity = light.get_intensity();
-                colour += intensity
-                    * material.shade_light(self, &viewer_direction, &light_direction, hit);
-            }
+                    if !self.is_point_in_shadow(
+                        hit.position,
+                        sample.position,
+                        sample.direction,
+                        time,
+                    ) {
+                        colour += sample.intensity
+                            * material.shade_light(self, &viewer_direction, &sample.direction, hit);
+                    }
+                });
+            });
         }
 
         colour
@@ -396,73 +467,112 @@ impl<'a> PhotonScene<'a> {
         }
     }
 
-    /// Pass 1: Constructing the Photon Maps.
+    /// Random time within the shutter interval, so that the photon maps light moving objects
+    /// over the whole of it.
+    fn shutter_time(&self) -> f32 {
+        let shutter = self.config.motion_blur;
+        shutter.shutter_open
+            + rand::random::<f32>() * (shutter.shutter_close - shutter.shutter_open)
+    }
+
+    /// Sphere around the bounded objects, which photons from an environment light are aimed at.
+    fn scene_bounds(&self) -> (Vertex, f32) {
+        let bounds = self
+            .objects
+            .iter()
+            .filter_map(|object| object.bounding_box())
+            .fold(AABB::empty(), |bounds, object| bounds.union(&object));
+        if bounds.is_empty() {
+            return (Vertex::default(), 0.0);
+        }
+
+        let center = bounds.centroid();
+        let radius = (bounds.max - center).length();
+        (Vertex::new(center.x, center.y, center.z, 1.0), radius)
+    }
+
+    /// Pass 1: Constructing the Photon Maps. Does nothing if they are already built and nothing
+    /// affecting them has changed.
     pub fn setup(&mut self) {
+        if !self.photon_maps_outdated {
+            return;
+        }
+        self.photon_maps_outdated = false;
+
         let mut sampler =
             MultiJitterSampler::new(self.config.photon_mapping.num_photons, 1.0, self.config);
 
         let mut global_photon_map: Vec<Photon> = Vec::new();
         let mut caustic_photon_map: Vec<Photon> = Vec::new();
 
-        for light in &self.lights {
-            if let Some(light_position) = light.get_position() {
-                // Create global map.
-                for _ in 0..self.config.photon_mapping.num_photons {
-                    let sample_direction = sampler.sample_hemisphere();
-                    // Project samples onto a sphere, so that the photons are emitted in all directions.
-                    let sign = if rand::random::<f32>() > 0.5 {
-                        1.0
-                    } else {
-                        -1.0
-                    };
-
-                    let photon_direction = Vector::new(
-                        sample_direction.x,
-                        sample_direction.y * sign,
-                        sample_direction.z,
-                    );
-                    let photon_ray = Ray::new(light_position, photon_direction);
+        let photon_power = 1.0 / self.config.photon_mapping.num_photons as f32;
+        let scene_bounds = self.scene_bounds();
 
-                    let photon_power = 1.0 / self.config.photon_mapping.num_photons as f32;
+        for light in self.lights.iter().chain(&self.emitters) {
+            // Directional lights have no position to emit photons from.
+            if matches!(light, Light::Directional { .. }) {
+                continue;
+            }
 
-                    self.photon_trace(
-                        &mut global_photon_map,
-                        &photon_ray,
-                        PhotonType::DirectionIllumination,
-                        photon_power * light.get_intensity(),
-                        None,
-                        self.config.photon_mapping.photon_recurse,
-                    );
-                }
+            // Create global map.
+            for _ in 0..self.config.photon_mapping.num_photons {
+                let Some((light_position, photon_direction, power)) =
+                    light.emit_photon(&mut sampler, scene_bounds)
+                else {
+                    continue;
+                };
+                // Start slightly off the surface of an area light, so it doesn't block itself.
+                let photon_ray = Ray::new(
+                    light_position + self.config.objects.rounding_error * photon_direction,
+                    photon_direction,
+                )
+                .with_time(self.shutter_time());
 
-                // Create caustic map.
-                for object in &self.objects {
-                    let material = object.material();
-                    if !material.is_specular() {
-                        continue;
-                    }
+                self.photon_trace(
+                    &mut global_photon_map,
+                    &photon_ray,
+                    PhotonType::DirectionIllumination,
+                    photon_power * power,
+                    None,
+                    self.config.photon_mapping.photon_recurse,
+                );
+            }
 
-                    if let Some(bounding_sphere) = object.bounding_sphere() {
-                        for _ in 0..self.config.photon_mapping.num_photons {
-                            let sample_direction = sampler.sample_hemisphere();
-                            // Shoot photons towards the object.
-                            let target_point =
-                                bounding_sphere.0.vector + (bounding_sphere.1 * sample_direction);
-                            let photon_direction =
-                                (target_point - light_position.vector).normalise();
-                            let photon_ray = Ray::new(light_position, photon_direction);
-
-                            let photon_power = 1.0 / self.config.photon_mapping.num_photons as f32;
-
-                            self.photon_trace(
-                                &mut caustic_photon_map,
-                                &photon_ray,
-                                PhotonType::DirectionIllumination,
-                                photon_power * light.get_intensity(),
-                                None,
-                                self.config.photon_mapping.photon_recurse,
-                            );
+            // Create caustic map.
+            for object in &self.objects {
+                let material = object.material();
+                if !material.is_specular() {
+                    continue;
+                }
+
+                if let Some(bounding_sphere) = object.bounding_sphere() {
+                    for _ in 0..self.config.photon_mapping.num_photons {
+                        let Some(light_position) = light.sample_position(&mut sampler) else {
+                            continue;
+                        };
+                        let sample_direction = sampler.sample_hemisphere();
+                        // Shoot photons towards the object.
+                        let target_point =
+                            bounding_sphere.0.vector + (bounding_sphere.1 * sample_direction);
+                        let photon_direction = (target_point - light_position.vector).normalise();
+                        let power = light.emission(photon_direction);
+                        if power <= 0.0 {
+                            continue;
                         }
+                        let photon_ray = Ray::new(
+                            light_position + self.config.objects.rounding_error * photon_direction,
+                            photon_direction,
+                        )
+                        .with_time(self.shutter_time());
+
+                        self.photon_trace(
+                            &mut caustic_photon_map,
+                            &photon_ray,
+                            PhotonType::DirectionIllumination,
+                            power * photon_power * light.get_intensity(),
+                            None,
+                            self.config.photon_mapping.photon_recurse,
+                        );
                     }
                 }
             }
@@ -494,37 +604,56 @@ impl<'a> Raytracer for PhotonScene<'a> {
         let mut colour = Colour::default();
         let mut depth = 0.0;
 
-        if let Some((hit, material)) = self.find_hit(ray) {
+        if let Some((hit, index)) = self.find_hit(ray) {
             depth = hit.distance;
 
-            // Compute direct material contribution (ambient/emission/reflection/refraction).
-            colour += material.shade_ambient(self, ray, &hit, recurse_depth);
-
-            // Always compute direct lighting from light sources
-            colour += self.compute_lighting(&hit, material);
-
-            // Add photon contributions for global illumination effects
-            if recurse_depth <= self.config.photon_mapping.recurse_approximate_threshold {
-                // At shallow depths, add photon-based indirect illumination and caustics
-                let viewer = &ray.direction.negate();
-                colour += self.estimate_indirect_illumination(viewer, &hit, material);
-                colour += self.estimate_caustics(viewer, &hit, material);
-            }
+            let (ambient, direct, photon) =
+                self.shade_hit(ray, &hit, self.objects[index].material(), recurse_depth);
+            colour += ambient;
+            colour += direct;
+            colour += photon;
+        } else {
+            colour = self.background(ray.direction);
         }
 
         (colour, depth)
     }
 
-    fn is_occluded(&self, ray: &Ray, max_distance: f32) -> bool {
-        for object in &self.objects {
-            if let Some(hit) = object.first_hit(ray) {
-                if 0.0 < hit.distance && hit.distance < max_distance {
-                    return true;
-                }
-            }
+    fn trace_aovs(&self, ray: &Ray) -> (Colour, f32, Aovs) {
+        if self.config.camera.raytrace_recurse == 0 {
+            return (Colour::default(), 0.0, Aovs::default());
         }
+        let Some((hit, index)) = self.find_hit(ray) else {
+            let background = self.background(ray.direction);
+            let aovs = Aovs {
+                indirect: background,
+                ..Default::default()
+            };
+            return (background, 0.0, aovs);
+        };
+        let material = self.objects[index].material();
+
+        let (indirect, direct, photon) = self.shade_hit(ray, &hit, material, 0);
+
+        let aovs = Aovs {
+            normal: hit.normal,
+            albedo: material.albedo(),
+            object_id: Some(index as u32),
+            material_id: self.material_ids.get(index),
+            position: hit.position.vector,
+            direct,
+            indirect,
+            photon,
+            // Set by the camera.
+            ..Default::default()
+        };
+
+        (indirect + direct + photon, hit.distance, aovs)
+    }
 
-        false
+    fn is_occluded(&self, ray: &Ray, max_distance: f32) -> bool {
+        self.accelerator
+            .is_occluded(&self.objects, ray, max_distance)
     }
 
     fn config(&self) -> &RaytracerConfig {
@@ -534,11 +663,33 @@ impl<'a> Raytracer for PhotonScene<'a> {
 
 impl<'a> SceneBuilder for PhotonScene<'a> {
     fn add_object(&mut self, object: SceneObject) {
+        let time = self.config.motion_blur.shutter_open;
+        self.emitters
+            .extend(Light::from_emissive_object(&object, time));
+        self.material_ids.push(object.material());
         self.objects.push(object);
+        self.accelerator.invalidate();
+        self.photon_maps_outdated = true;
     }
 
     fn add_light(&mut self, light: Light) {
         self.lights.push(light);
+        self.photon_maps_outdated = true;
+    }
+
+    fn light(&self, index: usize) -> &Light {
+        &self.lights[index]
+    }
+
+    fn set_light(&mut self, index: usize, light: Light) {
+        if self.lights[index] != light {
+            self.lights[index] = light;
+            self.photon_maps_outdated = true;
+        }
+    }
+
+    fn num_lights(&self) -> usize {
+        self.lights.len()
     }
 
     fn config(&self) -> &RaytracerConfig {
@@ -549,7 +700,12 @@ impl<'a> SceneBuilder for PhotonScene<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::geometry::sphere::Sphere;
+    use crate::{
+        geometry::{sphere::Sphere, Instance},
+        primitives::{AnimatedTransform, Transform},
+        utilities::hdr_reader::HdrImage,
+    };
+    use approx::assert_relative_eq;
 
     fn test_config() -> RaytracerConfig {
         RaytracerConfig::default()
@@ -639,4 +795,89 @@ mod tests {
         assert_eq!(colour.g, 0.0);
         assert_eq!(colour.b, 0.0);
     }
+
+    #[test]
+    fn test_photon_maps_rebuilt_only_when_outdated() {
+        let mut config = test_config();
+        config.photon_mapping.num_photons = 16;
+        let frames: Vec<RaytracerConfig> = (0..3)
+            .map(|frame| {
+                let mut config = config;
+                config.motion_blur.shutter_open = frame as f32;
+                config.motion_blur.shutter_close = frame as f32 + 0.5;
+                config
+            })
+            .collect();
+
+        let mut scene = PhotonScene::new(&frames[0]);
+        let material = Material::phong(
+            Colour::new(0.1, 0.1, 0.1, 1.0),
+            Colour::new(0.8, 0.8, 0.8, 1.0),
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+            32.0,
+        );
+        let light = Light::new_point(
+            Vertex::new(0.0, 5.0, 0.0, 1.0),
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+        );
+        scene.add_object(Sphere::new(Vertex::new(0.0, 0.0, 5.0, 1.0), 1.0, material).into());
+        scene.add_light(light.clone());
+        assert!(scene.photon_maps_outdated());
+        scene.setup();
+        assert!(!scene.photon_maps_outdated());
+
+        // Nothing moves, so the next frame can keep the photon maps.
+        scene.set_config(&frames[1]);
+        scene.set_light(0, light.clone());
+        assert!(!scene.photon_maps_outdated());
+
+        scene.set_light(
+            0,
+            Light::new_point(
+                Vertex::new(1.0, 5.0, 0.0, 1.0),
+                Colour::new(1.0, 1.0, 1.0, 1.0),
+            ),
+        );
+        assert!(scene.photon_maps_outdated());
+        scene.setup();
+
+        // An object moving between the frames outdates them too.
+        let sphere: SceneObject =
+            Sphere::new(Vertex::new(0.0, 0.0, 0.0, 1.0), 1.0, material).into();
+        let motion =
+            AnimatedTransform::between(0.0, Transform::identity(), 10.0, Transform::identity());
+        scene.add_object(Instance::animated(std::sync::Arc::new(sphere), motion, material).into());
+        scene.setup();
+        scene.set_config(&frames[2]);
+        assert!(scene.photon_maps_outdated());
+    }
+
+    #[test]
+    fn test_photon_scene_environment_light() {
+        let mut config = test_config();
+        config.photon_mapping.num_photons = 64;
+        let mut scene = PhotonScene::new(&config);
+        let material = Material::phong(
+            Colour::new(0.1, 0.1, 0.1, 1.0),
+            Colour::new(0.8, 0.8, 0.8, 1.0),
+            Colour::default(),
+            1.0,
+        );
+        let sky = Colour::new(0.5, 0.7, 1.0, 1.0);
+        scene.add_object(Sphere::new(Vertex::new(0.0, 0.0, 5.0, 1.0), 1.0, material).into());
+        scene.add_light(Light::new_environment(
+            HdrImage::new(1, 1, vec![sky]),
+            0.0,
+            Colour::new(1.0, 1.0, 1.0, 1.0),
+            4,
+        ));
+
+        let miss = Ray::new(Vertex::new(0.0, 0.0, 0.0, 1.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(scene.trace(&miss, 0).0, sky);
+
+        // Photons are aimed at the sphere, the only bounded object.
+        assert_relative_eq!(scene.scene_bounds().1, 3.0f32.sqrt(), epsilon = 1e-4);
+        scene.setup();
+        assert!(!scene.get_photon_maps().global.is_empty());
+    }
 }
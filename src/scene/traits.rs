@@ -7,6 +7,15 @@ pub trait SceneBuilder {
     /// Add a light to the scene
     fn add_light(&mut self, light: Light);
 
+    /// Get the light at the given index
+    fn light(&self, index: usize) -> &Light;
+
+    /// Replace the light at the given index, such as to move it in an animation
+    fn set_light(&mut self, index: usize, light: Light);
+
+    /// Number of lights in the scene
+    fn num_lights(&self) -> usize;
+
     /// Get a reference to the raytracer configuration
     fn config(&self) -> &RaytracerConfig;
 }
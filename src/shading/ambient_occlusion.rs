@@ -7,7 +7,7 @@ use crate::{
 
 /// Ambient Occlusion material using the new idiomatic architecture.
 /// Computes ambient occlusion by sampling hemisphere around hit point.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct AmbientOcclusionMaterial {
     ambient: Colour,
     /// Number of samples to take for ambient occlusion.
@@ -30,6 +30,11 @@ impl AmbientOcclusionMaterial {
         }
     }
 
+    /// The colour that ambient light is occluded from
+    pub fn ambient(&self) -> Colour {
+        self.ambient
+    }
+
     /// Get surface properties for this material
     pub fn get_surface_properties(&self) -> SurfaceProperties {
         SurfaceProperties::default()
@@ -37,7 +42,7 @@ impl AmbientOcclusionMaterial {
 }
 
 impl<R: Raytracer> Shader<R> for AmbientOcclusionMaterial {
-    fn shade_ambient(&self, ctx: &R, _ray: &Ray, hit: &Hit, _recurse_depth: u8) -> Colour {
+    fn shade_ambient(&self, ctx: &R, ray: &Ray, hit: &Hit, _recurse_depth: u8) -> Colour {
         let config = ctx.config();
         let rounding_error = config.objects.rounding_error;
         let shadow_distance_limit = config.materials.shadow_distance_limit;
@@ -49,7 +54,8 @@ impl<R: Raytracer> Shader<R> for AmbientOcclusionMaterial {
             let sample = sampler.sample_hemisphere();
             let sample_direction = (hit.normal + sample).normalise();
 
-            let shadow_ray = Ray::new(hit.position + rounding_error * hit.normal, sample_direction);
+            let shadow_ray = Ray::new(hit.position + rounding_error * hit.normal, sample_direction)
+                .with_time(ray.time);
 
             if !ctx.is_occluded(&shadow_ray, shadow_distance_limit) {
                 ambient_occlusion_sum += 1.0;
@@ -0,0 +1,40 @@
+use crate::{
+    primitives::{ray::Ray, Colour, Hit, Vector},
+    shading::traits::{Shader, SurfaceProperties, BRDF},
+    Raytracer,
+};
+
+/// EmissiveMaterial gives off light of its own, and reflects none.
+///
+/// Spheres and meshes with this material also light the rest of the scene like an area light
+/// with the radiance as its intensity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmissiveMaterial {
+    pub radiance: Colour,
+    /// Shadow rays per shaded point when lighting the scene. Must be a square number.
+    pub samples: u32,
+}
+
+impl EmissiveMaterial {
+    pub fn new(radiance: Colour, samples: u32) -> Self {
+        Self { radiance, samples }
+    }
+
+    /// Get surface properties for this material (opaque with no special effects)
+    pub fn get_surface_properties(&self) -> SurfaceProperties {
+        SurfaceProperties::default()
+    }
+}
+
+impl BRDF for EmissiveMaterial {
+    fn eval(&self, _viewer: &Vector, _light_direction: &Vector, _hit: &Hit) -> Colour {
+        Colour::default()
+    }
+}
+
+impl<R: Raytracer> Shader<R> for EmissiveMaterial {
+    /// Seen from either side.
+    fn shade_ambient(&self, _ctx: &R, _ray: &Ray, _hit: &Hit, _recurse_depth: u8) -> Colour {
+        self.radiance
+    }
+}
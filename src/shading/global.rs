@@ -7,7 +7,7 @@ use crate::{
 };
 
 /// GlobalMaterial computes reflection/refraction effects.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct GlobalMaterial {
     pub reflect_weight: Colour,
     pub refract_weight: Colour,
@@ -106,11 +106,11 @@ impl<R: Raytracer> Shader<R> for GlobalMaterial {
         let rounding_error = config.objects.rounding_error;
 
         // Calculate reflection and refraction rays.
-        let mut reflection_ray = Ray::default();
+        let mut reflection_ray = Ray::default().with_time(ray.time);
         reflection_ray.direction = ray.direction.reflection(hit.normal).normalise();
         reflection_ray.position = hit.position + rounding_error * reflection_ray.direction;
 
-        let mut refract_ray = Ray::default();
+        let mut refract_ray = Ray::default().with_time(ray.time);
         refract_ray.direction = ray
             .direction
             .refraction(hit.normal, self.index_of_refraction)
@@ -176,8 +176,8 @@ mod tests {
         let (reflection, transmission) = material.fresnel_coefficients(incident, normal);
 
         // At normal incidence, some reflection occurs
-        assert!(reflection >= 0.0 && reflection <= 1.0);
-        assert!(transmission >= 0.0 && transmission <= 1.0);
+        assert!((0.0..=1.0).contains(&reflection));
+        assert!((0.0..=1.0).contains(&transmission));
         assert!((reflection + transmission - 1.0).abs() < 1e-5);
     }
 
@@ -187,7 +187,7 @@ mod tests {
         let props = material.get_surface_properties();
 
         assert!(props.reflectivity > 0.0);
-        assert_eq!(props.is_specular, true);
+        assert!(props.is_specular);
     }
 
     #[test]
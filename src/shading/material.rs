@@ -5,14 +5,16 @@ use crate::{
 };
 
 use super::{
-    ambient_occlusion::AmbientOcclusionMaterial, global::GlobalMaterial, phong::PhongMaterial,
+    ambient_occlusion::AmbientOcclusionMaterial, emissive::EmissiveMaterial,
+    global::GlobalMaterial, phong::PhongMaterial,
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Material {
     Phong(PhongMaterial),
     Global(GlobalMaterial),
     AmbientOcclusion(AmbientOcclusionMaterial),
+    Emissive(EmissiveMaterial),
 }
 
 impl Material {
@@ -40,6 +42,20 @@ impl Material {
         ))
     }
 
+    pub fn emissive(radiance: Colour, samples: u32) -> Self {
+        Self::Emissive(EmissiveMaterial::new(radiance, samples))
+    }
+
+    /// Base colour of the surface, without lighting (for the albedo output variable)
+    pub fn albedo(&self) -> Colour {
+        match self {
+            Material::Phong(m) => m.diffuse,
+            Material::Global(m) => m.reflect_weight + m.refract_weight,
+            Material::AmbientOcclusion(m) => m.ambient(),
+            Material::Emissive(m) => m.radiance,
+        }
+    }
+
     /// Check if this material is specular (for photon mapping caustics)
     pub fn is_specular(&self) -> bool {
         match self {
@@ -47,7 +63,7 @@ impl Material {
             Material::Global(m) => {
                 m.reflect_weight.r > 0.5 || m.reflect_weight.g > 0.5 || m.reflect_weight.b > 0.5
             }
-            Material::AmbientOcclusion(_) => false,
+            Material::AmbientOcclusion(_) | Material::Emissive(_) => false,
         }
     }
 
@@ -58,7 +74,7 @@ impl Material {
             Material::Global(m) => {
                 m.refract_weight.r > 0.0 || m.refract_weight.g > 0.0 || m.refract_weight.b > 0.0
             }
-            Material::AmbientOcclusion(_) => false,
+            Material::AmbientOcclusion(_) | Material::Emissive(_) => false,
         }
     }
 
@@ -74,7 +90,7 @@ impl Material {
                     None
                 }
             }
-            Material::AmbientOcclusion(_) => None,
+            Material::AmbientOcclusion(_) | Material::Emissive(_) => None,
         }
     }
 
@@ -90,6 +106,7 @@ impl BRDF for Material {
             Material::Phong(m) => m.eval(viewer, light_direction, hit),
             Material::Global(m) => m.eval(viewer, light_direction, hit),
             Material::AmbientOcclusion(_) => Colour::default(), // AO doesn't use BRDF
+            Material::Emissive(m) => m.eval(viewer, light_direction, hit),
         }
     }
 }
@@ -100,6 +117,7 @@ impl<R: Raytracer> Shader<R> for Material {
             Material::Phong(m) => m.shade_ambient(ctx, ray, hit, recurse_depth),
             Material::Global(m) => m.shade_ambient(ctx, ray, hit, recurse_depth),
             Material::AmbientOcclusion(m) => m.shade_ambient(ctx, ray, hit, recurse_depth),
+            Material::Emissive(m) => m.shade_ambient(ctx, ray, hit, recurse_depth),
         }
     }
 
@@ -108,6 +126,7 @@ impl<R: Raytracer> Shader<R> for Material {
             Material::Phong(m) => m.shade_light(ctx, viewer, light_direction, hit),
             Material::Global(m) => m.shade_light(ctx, viewer, light_direction, hit),
             Material::AmbientOcclusion(_) => Colour::default(), // AO doesn't use direct lighting
+            Material::Emissive(_) => Colour::default(),         // Emitters don't reflect light
         }
     }
 
@@ -116,6 +135,7 @@ impl<R: Raytracer> Shader<R> for Material {
             Material::Phong(m) => m.get_surface_properties(),
             Material::Global(m) => m.get_surface_properties(),
             Material::AmbientOcclusion(m) => m.get_surface_properties(),
+            Material::Emissive(m) => m.get_surface_properties(),
         }
     }
 }
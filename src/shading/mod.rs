@@ -1,10 +1,12 @@
 pub mod ambient_occlusion;
+pub mod emissive;
 pub mod global;
 pub mod material;
 pub mod phong;
 pub mod traits;
 
 pub use ambient_occlusion::AmbientOcclusionMaterial;
+pub use emissive::EmissiveMaterial;
 pub use global::GlobalMaterial;
 pub use material::Material;
 pub use phong::PhongMaterial;
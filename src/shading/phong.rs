@@ -5,7 +5,7 @@ use crate::{
 };
 
 /// PhongMaterial implements the Phong surface illumination model.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct PhongMaterial {
     pub ambient: Colour,
     pub diffuse: Colour,
@@ -0,0 +1,102 @@
+use std::io::{Error as IoError, Result as IoResult};
+
+use exr::prelude::{
+    AnyChannel, AnyChannels, Blocks, Compression, Encoding, FlatSamples, Image, Layer,
+    LayerAttributes, LineOrder, WritableImage,
+};
+
+use crate::primitives::{pixel::Pixel, Colour};
+
+use super::image_writer::ImageWriter;
+
+/// How the pixel data of an OpenEXR file is compressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EXRCompression {
+    Uncompressed,
+    /// Lossless zlib compression of blocks of 16 scanlines.
+    #[default]
+    ZIP,
+}
+
+/// OpenEXR file writer.
+/// Writes scanline images with a 32-bit float per channel, so no dynamic range is lost.
+pub struct EXRWriter {
+    width: u16,
+    height: u16,
+    compression: EXRCompression,
+}
+
+impl EXRWriter {
+    /// Creates a ZIP compressed writer.
+    pub fn new(width: u16, height: u16) -> Self {
+        EXRWriter {
+            width,
+            height,
+            compression: EXRCompression::ZIP,
+        }
+    }
+
+    pub fn with_compression(mut self, compression: EXRCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Writes any number of named channels, each with one value per pixel.
+    pub fn write_channels(
+        &self,
+        filename: &str,
+        channels: Vec<(String, Vec<f32>)>,
+    ) -> IoResult<()> {
+        let channels = channels
+            .into_iter()
+            .map(|(name, samples)| AnyChannel::new(name.as_str(), FlatSamples::F32(samples)))
+            .collect();
+
+        let encoding = Encoding {
+            compression: match self.compression {
+                EXRCompression::Uncompressed => Compression::Uncompressed,
+                EXRCompression::ZIP => Compression::ZIP16,
+            },
+            blocks: Blocks::ScanLines,
+            line_order: LineOrder::Increasing,
+        };
+
+        let layer = Layer::new(
+            (self.width as usize, self.height as usize),
+            LayerAttributes::default(),
+            encoding,
+            AnyChannels::sort(channels),
+        );
+
+        Image::from_layer(layer)
+            .write()
+            .to_file(filename)
+            .map_err(IoError::other)
+    }
+}
+
+impl ImageWriter for EXRWriter {
+    fn write_file<F>(&self, filename: &str, pixel_data: &[Pixel], pixel_mapper: F) -> IoResult<()>
+    where
+        F: Fn(&Pixel) -> Colour,
+    {
+        let colours: Vec<Colour> = pixel_data.iter().map(pixel_mapper).collect();
+        let channel = |name: &str, component: fn(&Colour) -> f32| {
+            (name.to_string(), colours.iter().map(component).collect())
+        };
+
+        self.write_channels(
+            filename,
+            vec![
+                channel("R", |colour| colour.r),
+                channel("G", |colour| colour.g),
+                channel("B", |colour| colour.b),
+                channel("A", |colour| colour.a),
+            ],
+        )
+    }
+
+    fn is_high_dynamic_range(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,314 @@
+use std::{fs, io, path::Path};
+
+use thiserror::Error as ThiserrorError;
+
+use crate::primitives::Colour;
+
+#[derive(Debug, ThiserrorError)]
+pub enum HdrError {
+    #[error("{0}")]
+    ReadError(#[from] io::Error),
+
+    #[error("invalid HDR image: {0}")]
+    ParseError(String),
+}
+
+/// Floating point image read from a Radiance `.hdr` or PFM file, such as an environment map.
+#[derive(Debug, PartialEq)]
+pub struct HdrImage {
+    width: usize,
+    height: usize,
+    /// Row by row from the top of the image.
+    pixels: Vec<Colour>,
+}
+
+impl HdrImage {
+    pub fn new(width: usize, height: usize, pixels: Vec<Colour>) -> Self {
+        assert_eq!(pixels.len(), width * height, "Wrong number of pixels.");
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Picks the format from the file's extension.
+    pub fn from_file(path: &Path) -> Result<Self, HdrError> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("hdr") => Self::parse_rgbe(&fs::read(path)?),
+            Some("pfm") => Self::parse_pfm(&fs::read(path)?),
+            _ => Err(HdrError::ParseError(
+                "expected a .hdr or .pfm file.".to_string(),
+            )),
+        }
+    }
+
+    /// Reads a Radiance RGBE image, with flat or run-length encoded scanlines.
+    pub fn parse_rgbe(bytes: &[u8]) -> Result<Self, HdrError> {
+        let error = |message: &str| HdrError::ParseError(message.to_string());
+        let mut data = bytes;
+
+        let magic = read_line(&mut data).ok_or_else(|| error("file ends early."))?;
+        if !magic.starts_with(b"#?") {
+            return Err(error("not a Radiance file."));
+        }
+        // The header ends at an empty line.
+        loop {
+            let line = read_line(&mut data).ok_or_else(|| error("file ends early."))?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix(b"FORMAT=") {
+                if format != b"32-bit_rle_rgbe" {
+                    return Err(error("only RGBE pixels are supported."));
+                }
+            }
+        }
+
+        let resolution = read_line(&mut data).ok_or_else(|| error("file ends early."))?;
+        let resolution = String::from_utf8_lossy(resolution);
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", height, "+X", width] => (height.parse(), width.parse()),
+            _ => {
+                return Err(error(
+                    "only top to bottom, left to right images are supported.",
+                ))
+            }
+        };
+        let (Ok(height), Ok(width)) = (height, width) else {
+            return Err(error("invalid image size."));
+        };
+        if width == 0 || height == 0 {
+            return Err(error("the image is empty."));
+        }
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for _ in 0..height {
+            pixels.extend(read_scanline(&mut data, width)?.into_iter().map(from_rgbe));
+        }
+
+        Ok(Self::new(width, height, pixels))
+    }
+
+    /// Reads a colour or greyscale PFM image, in either byte order.
+    pub fn parse_pfm(bytes: &[u8]) -> Result<Self, HdrError> {
+        let error = |message: &str| HdrError::ParseError(message.to_string());
+        let mut data = bytes;
+        let mut next = || read_token(&mut data).ok_or_else(|| error("file ends early."));
+
+        let channels = match next()? {
+            "PF" => 3,
+            "Pf" => 1,
+            _ => return Err(error("not a PFM file.")),
+        };
+        let width: usize = next()?.parse().map_err(|_| error("invalid image size."))?;
+        let height: usize = next()?.parse().map_err(|_| error("invalid image size."))?;
+        // The sign of the scale gives the byte order.
+        let scale: f32 = next()?.parse().map_err(|_| error("invalid scale."))?;
+        if width == 0 || height == 0 {
+            return Err(error("the image is empty."));
+        }
+
+        let row_length = width * channels;
+        let values: Vec<f32> = data
+            .chunks_exact(4)
+            .take(row_length * height)
+            .map(|value| {
+                let value = [value[0], value[1], value[2], value[3]];
+                if scale < 0.0 {
+                    f32::from_le_bytes(value)
+                } else {
+                    f32::from_be_bytes(value)
+                }
+            })
+            .collect();
+        if values.len() < row_length * height {
+            return Err(error("file ends early."));
+        }
+
+        // Rows are stored from the bottom of the image to the top.
+        let pixels = values
+            .chunks(row_length)
+            .rev()
+            .flat_map(|row| row.chunks(channels))
+            .map(|pixel| match *pixel {
+                [r, g, b] => Colour::new(r, g, b, 1.0),
+                _ => Colour::new(pixel[0], pixel[0], pixel[0], 1.0),
+            })
+            .collect();
+
+        Ok(Self::new(width, height, pixels))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Colour {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn pixels(&self) -> &[Colour] {
+        &self.pixels
+    }
+}
+
+/// Decodes an RGBE pixel, the inverse of `to_rgbe`.
+pub fn from_rgbe([r, g, b, exponent]: [u8; 4]) -> Colour {
+    if exponent == 0 {
+        return Colour::new(0.0, 0.0, 0.0, 1.0);
+    }
+    let scale = 2.0_f32.powi(exponent as i32 - 136);
+    Colour::new(r as f32 * scale, g as f32 * scale, b as f32 * scale, 1.0)
+}
+
+/// Takes the bytes up to the next newline.
+fn read_line<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let end = data.iter().position(|byte| *byte == b'\n')?;
+    let line = &data[..end];
+    *data = &data[end + 1..];
+    Some(line.strip_suffix(b"\r").unwrap_or(line))
+}
+
+/// Takes the next whitespace separated word, and the single whitespace byte after it.
+fn read_token<'a>(data: &mut &'a [u8]) -> Option<&'a str> {
+    let start = data.iter().position(|byte| !byte.is_ascii_whitespace())?;
+    let rest = &data[start..];
+    let end = rest
+        .iter()
+        .position(u8::is_ascii_whitespace)
+        .unwrap_or(rest.len());
+    *data = rest.get(end + 1..).unwrap_or_default();
+    std::str::from_utf8(&rest[..end]).ok()
+}
+
+/// Reads a scanline of RGBE pixels. Run-length encoded scanlines start with two 2s and their
+/// width, followed by each channel in turn as runs of a repeated byte or of literal bytes.
+fn read_scanline(data: &mut &[u8], width: usize) -> Result<Vec<[u8; 4]>, HdrError> {
+    let error = |message: &str| HdrError::ParseError(message.to_string());
+
+    let is_encoded =
+        (8..0x8000).contains(&width) && data.len() >= 4 && data[..2] == [2, 2] && data[2] < 128;
+    if !is_encoded {
+        let (scanline, rest) = data
+            .split_at_checked(4 * width)
+            .ok_or_else(|| error("file ends early."))?;
+        *data = rest;
+        return Ok(scanline
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect());
+    }
+
+    if (data[2] as usize) << 8 | data[3] as usize != width {
+        return Err(error("scanline width doesn't match the image."));
+    }
+    *data = &data[4..];
+
+    let mut scanline = vec![[0; 4]; width];
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let (&count, rest) = data
+                .split_first()
+                .ok_or_else(|| error("file ends early."))?;
+            let (run, is_repeated) = if count > 128 {
+                ((count - 128) as usize, true)
+            } else {
+                (count as usize, false)
+            };
+            if run == 0 || x + run > width {
+                return Err(error("run overflows the scanline."));
+            }
+
+            let length = if is_repeated { 1 } else { run };
+            let (values, rest) = rest
+                .split_at_checked(length)
+                .ok_or_else(|| error("file ends early."))?;
+            for (i, pixel) in scanline[x..x + run].iter_mut().enumerate() {
+                pixel[channel] = values[if is_repeated { 0 } else { i }];
+            }
+            x += run;
+            *data = rest;
+        }
+    }
+
+    Ok(scanline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::rgbe_writer::to_rgbe;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_hdr_parse_rgbe() {
+        let mut flat = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 1\n".to_vec();
+        flat.extend(to_rgbe(Colour::new(1.0, 0.5, 0.0, 1.0)));
+        flat.extend(to_rgbe(Colour::new(0.0, 0.0, 6.0, 1.0)));
+        let image = HdrImage::parse_rgbe(&flat).unwrap();
+
+        assert_eq!((image.width(), image.height()), (1, 2));
+        assert_eq!(image.get_pixel(0, 0), Colour::new(1.0, 0.5, 0.0, 1.0));
+        assert_eq!(image.get_pixel(0, 1), Colour::new(0.0, 0.0, 6.0, 1.0));
+
+        // A run-length encoded scanline of 8 pixels: red is a run of 128s, green is literal,
+        // and blue and the exponent are runs.
+        let mut encoded = b"#?RGBE\n\n-Y 1 +X 8\n".to_vec();
+        encoded.extend([2, 2, 0, 8, 136, 128, 8, 0, 16, 32, 48, 64, 80, 96, 112]);
+        encoded.extend([136, 0, 136, 129]);
+        let image = HdrImage::parse_rgbe(&encoded).unwrap();
+
+        assert_eq!(image.width(), 8);
+        assert_relative_eq!(image.get_pixel(5, 0).r, 1.0);
+        assert_relative_eq!(image.get_pixel(5, 0).g, 0.625);
+        assert_relative_eq!(image.get_pixel(5, 0).b, 0.0);
+    }
+
+    #[test]
+    fn test_hdr_parse_pfm() {
+        // Little-endian, with the bottom row first.
+        let mut bytes = b"PF\n1 2\n-1.0\n".to_vec();
+        for value in [0.0f32, 0.0, 2.0, 1.5, 0.25, 0.0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        let image = HdrImage::parse_pfm(&bytes).unwrap();
+        assert_eq!(image.get_pixel(0, 0), Colour::new(1.5, 0.25, 0.0, 1.0));
+        assert_eq!(image.get_pixel(0, 1), Colour::new(0.0, 0.0, 2.0, 1.0));
+
+        let mut grey = b"Pf 1 1 1.0\n".to_vec();
+        grey.extend(3.0f32.to_be_bytes());
+        let image = HdrImage::parse_pfm(&grey).unwrap();
+        assert_eq!(image.get_pixel(0, 0), Colour::new(3.0, 3.0, 3.0, 1.0));
+    }
+
+    #[test]
+    fn test_hdr_parse_errors() {
+        let truncated = HdrImage::parse_pfm(b"PF\n2 2\n-1.0\n\0\0\0\0");
+        assert_eq!(
+            truncated.unwrap_err().to_string(),
+            "invalid HDR image: file ends early."
+        );
+
+        let flipped = HdrImage::parse_rgbe(b"#?RADIANCE\n\n+Y 1 +X 1\n\0\0\0\0");
+        assert_eq!(
+            flipped.unwrap_err().to_string(),
+            "invalid HDR image: only top to bottom, left to right images are supported."
+        );
+
+        let unknown = HdrImage::from_file(Path::new("map.png"));
+        assert_eq!(
+            unknown.unwrap_err().to_string(),
+            "invalid HDR image: expected a .hdr or .pfm file."
+        );
+    }
+}
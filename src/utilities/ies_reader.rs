@@ -0,0 +1,260 @@
+use std::{fs, io, path::Path};
+
+use thiserror::Error as ThiserrorError;
+
+#[derive(Debug, ThiserrorError)]
+pub enum IesError {
+    #[error("{0}")]
+    ReadError(#[from] io::Error),
+
+    #[error("invalid IES file: {0}")]
+    ParseError(String),
+}
+
+/// Candela distribution of a luminaire, read from an IES LM-63 photometric file.
+///
+/// Only type C photometry, used for nearly all architectural luminaires, is supported. Vertical
+/// angles are measured from the nadir, the direction the luminaire points in, and horizontal
+/// angles around it. Angles are in degrees.
+#[derive(Debug, PartialEq)]
+pub struct IesProfile {
+    vertical_angles: Vec<f32>,
+    horizontal_angles: Vec<f32>,
+    /// Candela for each horizontal angle, over the vertical angles.
+    candela: Vec<Vec<f32>>,
+    max_candela: f32,
+}
+
+impl IesProfile {
+    pub fn from_file(path: &Path) -> Result<Self, IesError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, IesError> {
+        let error = |message: &str| IesError::ParseError(message.to_string());
+
+        // The keyword header ends at the TILT line, and numbers follow it.
+        let mut lines = contents.lines();
+        let tilt = lines
+            .by_ref()
+            .find_map(|line| line.trim().strip_prefix("TILT="))
+            .ok_or_else(|| error("no TILT line."))?
+            .trim()
+            .to_string();
+
+        let mut numbers = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| IesError::ParseError(format!("'{token}' is not a number.")))
+            });
+        let mut next = || {
+            numbers
+                .next()
+                .unwrap_or_else(|| Err(error("file ends early.")))
+        };
+
+        match tilt.as_str() {
+            "NONE" => {}
+            "INCLUDE" => {
+                // Lamp tilt multipliers, which don't apply to a fixed light.
+                next()?;
+                let pairs = next()? as usize;
+                for _ in 0..2 * pairs {
+                    next()?;
+                }
+            }
+            _ => return Err(error("tilt data in a separate file is not supported.")),
+        }
+
+        let _num_lamps = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let num_vertical = next()? as usize;
+        let num_horizontal = next()? as usize;
+        let photometric_type = next()?;
+        let _units = next()?;
+        let _dimensions = [next()?, next()?, next()?];
+        let ballast_factor = next()?;
+        let _future_use = next()?;
+        let _input_watts = next()?;
+
+        if photometric_type != 1.0 {
+            return Err(error("only type C photometry is supported."));
+        }
+        if num_vertical == 0 || num_horizontal == 0 {
+            return Err(error(
+                "there must be at least one vertical and horizontal angle.",
+            ));
+        }
+
+        let vertical_angles = (0..num_vertical)
+            .map(|_| next())
+            .collect::<Result<Vec<_>, _>>()?;
+        let horizontal_angles = (0..num_horizontal)
+            .map(|_| next())
+            .collect::<Result<Vec<_>, _>>()?;
+        let candela = (0..num_horizontal)
+            .map(|_| {
+                (0..num_vertical)
+                    .map(|_| Ok(next()? * multiplier * ballast_factor))
+                    .collect::<Result<Vec<_>, IesError>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let is_sorted = |angles: &[f32]| angles.windows(2).all(|pair| pair[0] < pair[1]);
+        if !is_sorted(&vertical_angles) || !is_sorted(&horizontal_angles) {
+            return Err(error("angles must be in increasing order."));
+        }
+
+        let max_candela = candela.iter().flatten().copied().fold(0.0, f32::max);
+
+        Ok(Self {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+            max_candela,
+        })
+    }
+
+    pub fn max_candela(&self) -> f32 {
+        self.max_candela
+    }
+
+    /// Candela in a direction, interpolated between the tabulated angles. Profiles that only
+    /// cover part of the horizontal circle are mirrored around it, as their symmetry implies,
+    /// and directions outside the vertical angles are dark.
+    pub fn candela(&self, vertical: f32, horizontal: f32) -> f32 {
+        let vertical_angles = &self.vertical_angles;
+        if vertical < vertical_angles[0] || vertical > vertical_angles[vertical_angles.len() - 1] {
+            return 0.0;
+        }
+
+        let first = self.horizontal_angles[0];
+        let last = self.horizontal_angles[self.horizontal_angles.len() - 1];
+        let mut horizontal = horizontal.rem_euclid(360.0);
+        horizontal = match (first, last) {
+            // Rotationally symmetric.
+            (_, 0.0) => 0.0,
+            // Symmetric in each quadrant.
+            (_, 90.0) => 90.0 - (horizontal.rem_euclid(180.0) - 90.0).abs(),
+            // Symmetric about the 0-180 degree plane.
+            (_, 180.0) => 180.0 - (horizontal - 180.0).abs(),
+            // Symmetric about the 90-270 degree plane.
+            (90.0, 270.0) => {
+                90.0 + (horizontal - 90.0)
+                    .rem_euclid(360.0)
+                    .min((90.0 - horizontal).rem_euclid(360.0))
+            }
+            _ => horizontal,
+        };
+
+        let (v0, v1, tv) = interpolation(vertical_angles, vertical);
+        let (h0, h1, th) = interpolation(&self.horizontal_angles, horizontal);
+        let at_vertical = |row: &[f32]| row[v0] + tv * (row[v1] - row[v0]);
+
+        at_vertical(&self.candela[h0]) * (1.0 - th) + at_vertical(&self.candela[h1]) * th
+    }
+
+    /// Candela in a direction as a fraction of the brightest tabulated direction.
+    pub fn relative_intensity(&self, vertical: f32, horizontal: f32) -> f32 {
+        if self.max_candela > 0.0 {
+            self.candela(vertical, horizontal) / self.max_candela
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The two tabulated angles around an angle, and how far it is between them. Angles outside
+/// the table are clamped to its ends.
+fn interpolation(angles: &[f32], angle: f32) -> (usize, usize, f32) {
+    let next = angles.partition_point(|a| *a <= angle);
+    if next == 0 {
+        return (0, 0, 0.0);
+    }
+    if next == angles.len() {
+        return (next - 1, next - 1, 0.0);
+    }
+    let t = (angle - angles[next - 1]) / (angles[next] - angles[next - 1]);
+    (next - 1, next, t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    const DOWNLIGHT: &str = "IESNA:LM-63-2002
+[TEST] Downlight
+[MANUFAC] Example
+TILT=NONE
+1 1000 2.0 3 1 1 2 0.1 0.1 0.0
+1.0 1.0 10
+0 45 90
+0
+100, 50, 0
+";
+
+    #[test]
+    fn test_ies_parse_rotationally_symmetric() {
+        let profile = IesProfile::parse(DOWNLIGHT).unwrap();
+
+        // The multiplier scales every value.
+        assert_relative_eq!(profile.max_candela(), 200.0);
+        assert_relative_eq!(profile.candela(0.0, 0.0), 200.0);
+        assert_relative_eq!(profile.candela(22.5, 0.0), 150.0);
+        assert_relative_eq!(profile.candela(22.5, 123.0), 150.0);
+        assert_relative_eq!(profile.relative_intensity(67.5, 0.0), 0.25);
+        // Above the tabulated angles.
+        assert_relative_eq!(profile.candela(135.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_ies_parse_bilateral_symmetry_and_tilt() {
+        let contents = "IESNA:LM-63-1995
+TILT=INCLUDE
+1
+2
+0 90
+1 1
+1 -1 1 2 3 1 1 0 0 0
+1 1 0
+0 90
+0 90 180
+10 10
+20 20
+30 30
+";
+        let profile = IesProfile::parse(contents).unwrap();
+
+        assert_relative_eq!(profile.candela(0.0, 45.0), 15.0);
+        assert_relative_eq!(profile.candela(0.0, 180.0), 30.0);
+        // Mirrored across the 0-180 degree plane.
+        assert_relative_eq!(profile.candela(0.0, 315.0), 15.0);
+        assert_relative_eq!(profile.candela(0.0, -90.0), 20.0);
+    }
+
+    #[test]
+    fn test_ies_parse_errors() {
+        let missing_tilt = IesProfile::parse("IESNA:LM-63-2002\n1 2 3");
+        assert_eq!(
+            missing_tilt.unwrap_err().to_string(),
+            "invalid IES file: no TILT line."
+        );
+
+        let truncated = IesProfile::parse(&DOWNLIGHT[..DOWNLIGHT.len() - 10]);
+        assert_eq!(
+            truncated.unwrap_err().to_string(),
+            "invalid IES file: file ends early."
+        );
+
+        let type_a = DOWNLIGHT.replace("3 1 1 2", "3 1 3 2");
+        assert_eq!(
+            IesProfile::parse(&type_a).unwrap_err().to_string(),
+            "invalid IES file: only type C photometry is supported."
+        );
+    }
+}
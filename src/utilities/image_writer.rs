@@ -0,0 +1,98 @@
+use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+use std::path::Path;
+
+use crate::primitives::{pixel::Pixel, Colour};
+
+use super::{
+    exr_writer::EXRWriter, pfm_writer::PFMWriter, png_writer::PNGWriter, ppm_writer::PPMWriter,
+    rgbe_writer::RGBEWriter,
+};
+
+/// Writes the pixels of a framebuffer to an image file.
+pub trait ImageWriter {
+    /// Writes pixel data to the file, using the mapping function to turn each pixel into a
+    /// colour with components in the range [0.0, 1.0].
+    fn write_file<F>(&self, filename: &str, pixel_data: &[Pixel], pixel_mapper: F) -> IoResult<()>
+    where
+        F: Fn(&Pixel) -> Colour;
+
+    /// Whether the format stores components outside of [0.0, 1.0] as they are, instead of
+    /// clamping them.
+    fn is_high_dynamic_range(&self) -> bool {
+        false
+    }
+}
+
+/// Bits per channel of an image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+/// Any of the image writers, picked from the extension of the file name.
+pub enum FileWriter {
+    PPM(PPMWriter),
+    PNG(PNGWriter),
+    PFM(PFMWriter),
+    HDR(RGBEWriter),
+    EXR(EXRWriter),
+}
+
+impl FileWriter {
+    /// Returns the writer for the file's extension (`.ppm`, `.png`, `.pfm`, `.hdr` or `.exr`).
+    pub fn for_file(filename: &str, width: u16, height: u16) -> IoResult<Self> {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("ppm") => Ok(Self::PPM(PPMWriter::new(width, height))),
+            Some("png") => Ok(Self::PNG(PNGWriter::new(width, height))),
+            Some("pfm") => Ok(Self::PFM(PFMWriter::new(width, height))),
+            Some("hdr") => Ok(Self::HDR(RGBEWriter::new(width, height))),
+            Some("exr") => Ok(Self::EXR(EXRWriter::new(width, height))),
+            _ => Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("Unsupported image format for '{filename}', expected .ppm, .png, .pfm, .hdr or .exr."),
+            )),
+        }
+    }
+}
+
+impl ImageWriter for FileWriter {
+    fn write_file<F>(&self, filename: &str, pixel_data: &[Pixel], pixel_mapper: F) -> IoResult<()>
+    where
+        F: Fn(&Pixel) -> Colour,
+    {
+        match self {
+            Self::PPM(writer) => writer.write_file(filename, pixel_data, pixel_mapper),
+            Self::PNG(writer) => writer.write_file(filename, pixel_data, pixel_mapper),
+            Self::PFM(writer) => writer.write_file(filename, pixel_data, pixel_mapper),
+            Self::HDR(writer) => writer.write_file(filename, pixel_data, pixel_mapper),
+            Self::EXR(writer) => writer.write_file(filename, pixel_data, pixel_mapper),
+        }
+    }
+
+    fn is_high_dynamic_range(&self) -> bool {
+        match self {
+            Self::PPM(writer) => writer.is_high_dynamic_range(),
+            Self::PNG(writer) => writer.is_high_dynamic_range(),
+            Self::PFM(writer) => writer.is_high_dynamic_range(),
+            Self::HDR(writer) => writer.is_high_dynamic_range(),
+            Self::EXR(writer) => writer.is_high_dynamic_range(),
+        }
+    }
+}
+
+/// Converts a component in the range [0.0, 1.0] to an 8-bit value.
+pub fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Converts a component in the range [0.0, 1.0] to a 16-bit value.
+pub fn to_u16(value: f32) -> u16 {
+    (value.clamp(0.0, 1.0) * 65535.0).round() as u16
+}
@@ -1,4 +1,11 @@
 pub mod cornell_box;
+pub mod exr_writer;
+pub mod hdr_reader;
+pub mod ies_reader;
+pub mod image_writer;
 pub mod linedrawer;
 pub mod obj_reader;
+pub mod pfm_writer;
+pub mod png_writer;
 pub mod ppm_writer;
+pub mod rgbe_writer;
@@ -151,9 +151,8 @@ impl ObjReader {
         assert!(vertex_indices.len() >= 3 && vertex_normals_indices.len() >= 3);
 
         let mut faces: Vec<VertexData> = Vec::new();
-        for (vertex_index, vertex_normal_index) in vertex_indices
-            .into_iter()
-            .zip(vertex_normals_indices.into_iter())
+        for (vertex_index, vertex_normal_index) in
+            vertex_indices.into_iter().zip(vertex_normals_indices)
         {
             faces.push(VertexData {
                 vertex_index,
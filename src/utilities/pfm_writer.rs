@@ -0,0 +1,55 @@
+use std::fs::File;
+use std::io::{BufWriter, Result as IoResult, Write};
+
+use crate::primitives::{pixel::Pixel, Colour};
+
+use super::image_writer::ImageWriter;
+
+/// PFM file writer.
+/// PFM stores each channel as an uncompressed 32-bit float, so no dynamic range is lost.
+pub struct PFMWriter {
+    width: u16,
+    height: u16,
+}
+
+impl PFMWriter {
+    pub fn new(width: u16, height: u16) -> Self {
+        PFMWriter { width, height }
+    }
+
+    /// Writes the PFM header to the provided file.
+    /// The negative scale marks the data as little-endian.
+    fn write_header<W: Write>(&self, outfile: &mut W) -> IoResult<()> {
+        writeln!(outfile, "PF")?;
+        writeln!(outfile, "{} {}", self.width, self.height)?;
+        writeln!(outfile, "-1.0")?;
+        Ok(())
+    }
+}
+
+impl ImageWriter for PFMWriter {
+    /// PFM has no alpha channel, so the alpha of the colours is ignored.
+    fn write_file<F>(&self, filename: &str, pixel_data: &[Pixel], pixel_mapper: F) -> IoResult<()>
+    where
+        F: Fn(&Pixel) -> Colour,
+    {
+        let mut outfile = BufWriter::new(File::create(filename)?);
+        self.write_header(&mut outfile)?;
+
+        // Rows are stored from the bottom of the image to the top.
+        for row in pixel_data.chunks(self.width as usize).rev() {
+            for pixel in row {
+                let colour = pixel_mapper(pixel);
+                for component in [colour.r, colour.g, colour.b] {
+                    outfile.write_all(&component.to_le_bytes())?;
+                }
+            }
+        }
+
+        outfile.flush()
+    }
+
+    fn is_high_dynamic_range(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::File;
+use std::io::{BufWriter, Result as IoResult};
+
+use png::{ColorType, Encoder};
+
+use crate::primitives::{pixel::Pixel, Colour};
+
+use super::image_writer::{to_u16, to_u8, BitDepth, ImageWriter};
+
+/// PNG file writer.
+/// Writes 8 or 16 bits per channel, optionally with the alpha of each pixel's colour.
+pub struct PNGWriter {
+    width: u16,
+    height: u16,
+    bit_depth: BitDepth,
+    alpha: bool,
+}
+
+impl PNGWriter {
+    /// Creates an 8-bit RGB writer.
+    pub fn new(width: u16, height: u16) -> Self {
+        PNGWriter {
+            width,
+            height,
+            bit_depth: BitDepth::Eight,
+            alpha: false,
+        }
+    }
+
+    pub fn with_bit_depth(mut self, bit_depth: BitDepth) -> Self {
+        self.bit_depth = bit_depth;
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: bool) -> Self {
+        self.alpha = alpha;
+        self
+    }
+}
+
+impl ImageWriter for PNGWriter {
+    fn write_file<F>(&self, filename: &str, pixel_data: &[Pixel], pixel_mapper: F) -> IoResult<()>
+    where
+        F: Fn(&Pixel) -> Colour,
+    {
+        let outfile = BufWriter::new(File::create(filename)?);
+
+        let mut encoder = Encoder::new(outfile, self.width as u32, self.height as u32);
+        encoder.set_color(if self.alpha {
+            ColorType::Rgba
+        } else {
+            ColorType::Rgb
+        });
+        encoder.set_depth(match self.bit_depth {
+            BitDepth::Eight => png::BitDepth::Eight,
+            BitDepth::Sixteen => png::BitDepth::Sixteen,
+        });
+
+        let channels = if self.alpha { 4 } else { 3 };
+        let bytes_per_channel = match self.bit_depth {
+            BitDepth::Eight => 1,
+            BitDepth::Sixteen => 2,
+        };
+        let mut data = Vec::with_capacity(pixel_data.len() * channels * bytes_per_channel);
+
+        for pixel in pixel_data {
+            let colour = pixel_mapper(pixel);
+            let components = [colour.r, colour.g, colour.b, colour.a];
+
+            for &component in &components[..channels] {
+                match self.bit_depth {
+                    BitDepth::Eight => data.push(to_u8(component)),
+                    // PNG stores samples big-endian.
+                    BitDepth::Sixteen => data.extend_from_slice(&to_u16(component).to_be_bytes()),
+                }
+            }
+        }
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+
+        Ok(())
+    }
+}
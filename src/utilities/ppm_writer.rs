@@ -1,7 +1,9 @@
 use std::fs::File;
-use std::io::{Result as IoResult, Write};
+use std::io::{BufWriter, Result as IoResult, Write};
 
-use crate::primitives::pixel::Pixel;
+use crate::primitives::{pixel::Pixel, Colour};
+
+use super::image_writer::{to_u8, ImageWriter};
 
 /// PPM file writer.
 /// PPM is a simple image format that is used to store images.
@@ -16,31 +18,28 @@ impl PPMWriter {
     }
 
     /// Writes the PPM header to the provided file.
-    fn write_header(&self, outfile: &mut File) -> IoResult<()> {
+    fn write_header<W: Write>(&self, outfile: &mut W) -> IoResult<()> {
         writeln!(outfile, "P6")?;
         writeln!(outfile, "{} {}", self.width, self.height)?;
         writeln!(outfile, "255")?;
         Ok(())
     }
+}
 
-    /// Writes pixel data to the file using the provided pixel mapping function.
-    pub fn write_file<F>(
-        &self,
-        filename: &str,
-        pixel_data: &[Pixel],
-        pixel_mapper: F,
-    ) -> IoResult<()>
+impl ImageWriter for PPMWriter {
+    /// PPM has no alpha channel, so the alpha of the colours is ignored.
+    fn write_file<F>(&self, filename: &str, pixel_data: &[Pixel], pixel_mapper: F) -> IoResult<()>
     where
-        F: Fn(&Pixel) -> (u8, u8, u8),
+        F: Fn(&Pixel) -> Colour,
     {
-        let mut outfile = File::create(filename)?;
+        let mut outfile = BufWriter::new(File::create(filename)?);
         self.write_header(&mut outfile)?;
 
         for pixel in pixel_data {
-            let (red, green, blue) = pixel_mapper(pixel);
-            outfile.write_all(&[red, green, blue])?;
+            let colour = pixel_mapper(pixel);
+            outfile.write_all(&[to_u8(colour.r), to_u8(colour.g), to_u8(colour.b)])?;
         }
 
-        Ok(())
+        outfile.flush()
     }
 }
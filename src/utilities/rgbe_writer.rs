@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::io::{BufWriter, Result as IoResult, Write};
+
+use crate::primitives::{pixel::Pixel, Colour};
+
+use super::image_writer::ImageWriter;
+
+/// Radiance `.hdr` file writer.
+/// Each pixel is stored as RGBE: an 8-bit mantissa per channel with a shared 8-bit exponent.
+pub struct RGBEWriter {
+    width: u16,
+    height: u16,
+}
+
+impl RGBEWriter {
+    pub fn new(width: u16, height: u16) -> Self {
+        RGBEWriter { width, height }
+    }
+
+    /// Writes the Radiance header to the provided file.
+    fn write_header<W: Write>(&self, outfile: &mut W) -> IoResult<()> {
+        writeln!(outfile, "#?RADIANCE")?;
+        writeln!(outfile, "FORMAT=32-bit_rle_rgbe")?;
+        writeln!(outfile)?;
+        writeln!(outfile, "-Y {} +X {}", self.height, self.width)?;
+        Ok(())
+    }
+}
+
+/// Encodes a colour as RGBE. Negative components can't be represented and are stored as zero.
+pub fn to_rgbe(colour: Colour) -> [u8; 4] {
+    let r = colour.r.max(0.0);
+    let g = colour.g.max(0.0);
+    let b = colour.b.max(0.0);
+    let max = r.max(g).max(b);
+
+    if max < 1e-32 || !max.is_finite() {
+        return [0, 0, 0, 0];
+    }
+
+    // Split the largest component into a mantissa in [0.5, 1) and a power of two exponent.
+    let mut exponent = max.log2().floor() as i32 + 1;
+    if max / 2.0_f32.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale = 256.0 / 2.0_f32.powi(exponent);
+
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+impl ImageWriter for RGBEWriter {
+    /// The format has no alpha channel, so the alpha of the colours is ignored.
+    fn write_file<F>(&self, filename: &str, pixel_data: &[Pixel], pixel_mapper: F) -> IoResult<()>
+    where
+        F: Fn(&Pixel) -> Colour,
+    {
+        let mut outfile = BufWriter::new(File::create(filename)?);
+        self.write_header(&mut outfile)?;
+
+        // Scanlines are written flat, without run-length encoding.
+        for pixel in pixel_data {
+            outfile.write_all(&to_rgbe(pixel_mapper(pixel)))?;
+        }
+
+        outfile.flush()
+    }
+
+    fn is_high_dynamic_range(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgbe_encoding() {
+        assert_eq!(to_rgbe(Colour::new(1.0, 0.5, 0.0, 1.0)), [128, 64, 0, 129]);
+        assert_eq!(to_rgbe(Colour::new(0.0, 0.0, 6.0, 1.0)), [0, 0, 192, 131]);
+        assert_eq!(to_rgbe(Colour::new(-1.0, 0.0, 0.0, 1.0)), [0, 0, 0, 0]);
+    }
+}